@@ -8,7 +8,7 @@ use crate::{
     ErrorKind, FieldConversionError, FieldIOError, FieldIterator, FieldValue, ReadableRecord,
 };
 
-impl<'de, 'a, 'f, R: Read + Seek> SeqAccess<'de> for &mut FieldIterator<'a, R> {
+impl<'de, 'a, R: Read + Seek> SeqAccess<'de> for &mut FieldIterator<'a, R> {
     type Error = FieldIOError;
 
     fn next_element_seed<T>(
@@ -27,7 +27,7 @@ impl<'de, 'a, 'f, R: Read + Seek> SeqAccess<'de> for &mut FieldIterator<'a, R> {
 }
 
 //TODO maybe we can deserialize numbers other than f32 & f64 by converting using TryFrom
-impl<'de, 'a, 'f, T: Read + Seek> Deserializer<'de> for &mut FieldIterator<'a, T> {
+impl<'de, 'a, T: Read + Seek> Deserializer<'de> for &mut FieldIterator<'a, T> {
     type Error = FieldIOError;
 
     fn deserialize_any<V>(self, _visitor: V) -> Result<<V as Visitor<'de>>::Value, Self::Error>
@@ -207,6 +207,19 @@ impl<'de, 'a, 'f, T: Read + Seek> Deserializer<'de> for &mut FieldIterator<'a, T
                 self.skip_next_field()?;
                 visitor.visit_none()
             }
+            FieldValue::Integer(None)
+            | FieldValue::Currency(None)
+            | FieldValue::Double(None)
+            | FieldValue::DateTime(None)
+            | FieldValue::Varchar(None)
+            | FieldValue::Varbinary(None)
+            | FieldValue::Long(None)
+            | FieldValue::Autoincrement(None)
+            | FieldValue::Timestamp(None)
+            | FieldValue::DBase7Double(None) => {
+                self.skip_next_field()?;
+                visitor.visit_none()
+            }
             _ => visitor.visit_some(self),
         }
     }
@@ -0,0 +1,269 @@
+//! Detection of the encoding of tables whose code page mark cannot be trusted
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::encoding::{AsCodePageMark, DynEncoding};
+use crate::error::{DecodeError, EncodeError};
+use crate::reading::read_cpg_file;
+use crate::{CodePageMark, Encoding, Error, FieldValue, Reader};
+
+/// The encoding chosen by [Reader::from_path_detect_encoding]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DetectedEncoding {
+    /// The code page of the encoding
+    pub code_page_mark: CodePageMark,
+    /// How much more plausible the text is with this encoding than with the others,
+    /// from 0 (no difference) to 1
+    pub confidence: f32,
+}
+
+impl Reader<BufReader<File>> {
+    /// Creates a new dbase Reader from a path, guessing the encoding of its strings
+    ///
+    /// The Character, Varchar and Memo fields of the first `sample_size` records are decoded
+    /// with UTF-8, CP1252, CP850 and the code page of the table, and the encoding
+    /// giving the most plausible text (letters rather than symbols, box drawing
+    /// or control characters, no capital letters within words) is kept.
+    ///
+    /// When a `.cpg` file is next to the table, its encoding is used without detection.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// let (reader, detected) = dbase::Reader::from_path_detect_encoding("tests/data/line.dbf", 100)?;
+    /// // The text of the table is ASCII, it reads the same with any encoding
+    /// assert_eq!(detected.confidence, 1.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_path_detect_encoding<P: AsRef<Path>>(
+        path: P,
+        sample_size: usize,
+    ) -> Result<(Self, DetectedEncoding), Error> {
+        if let Some(encoding) = read_cpg_file(path.as_ref())? {
+            let detected = DetectedEncoding {
+                code_page_mark: encoding.code_page_mark(),
+                confidence: 1.0,
+            };
+            return Self::from_path_impl(path, Some(encoding)).map(|reader| (reader, detected));
+        }
+
+        // Strings are read as their bytes to decode them with each candidate
+        let mut reader = Self::from_path_impl(path, Some(DynEncoding::new(RawBytes)))?;
+        let records = reader
+            .iter_records()
+            .take(sample_size)
+            .collect::<Result<Vec<_>, _>>()?;
+        let samples = records
+            .into_iter()
+            .flat_map(|record| record.into_iter().map(|(_name, value)| value))
+            .filter_map(|value| match value {
+                FieldValue::Character(Some(text))
+                | FieldValue::Varchar(Some(text))
+                | FieldValue::Memo(text) => Some(text.chars().map(|c| c as u8).collect()),
+                _ => None,
+            })
+            .filter(|bytes: &Vec<u8>| !bytes.is_ascii())
+            .collect::<Vec<_>>();
+        let offset = u64::from(reader.header.offset_to_first_record);
+        reader
+            .source
+            .seek(SeekFrom::Start(offset))
+            .map_err(|error| Error::io_error(error, 0))?;
+
+        let mut candidates = vec![
+            CodePageMark::Utf8,
+            CodePageMark::CP1252,
+            CodePageMark::CP850,
+        ];
+        let table_mark = reader.header.code_page_mark;
+        if table_mark != CodePageMark::Undefined
+            && candidates
+                .iter()
+                .all(|candidate| candidate.code_page() != table_mark.code_page())
+        {
+            // Preferred when the text is just as plausible with another encoding
+            candidates.insert(0, table_mark);
+        }
+        let candidates = candidates
+            .into_iter()
+            .filter_map(|mark| mark.to_encoding().map(|encoding| (mark, encoding)))
+            .collect();
+        let (encoding, detected) = detect_encoding(&samples, candidates);
+        reader.encoding = encoding;
+        Ok((reader, detected))
+    }
+}
+
+/// An encoding with the text it decodes from the samples
+struct Candidate {
+    mark: CodePageMark,
+    encoding: DynEncoding,
+    decoded: Vec<Option<String>>,
+    score: f32,
+}
+
+/// Returns the candidate that decodes the samples to the most plausible text,
+/// the first one when several are as plausible
+fn detect_encoding(
+    samples: &[Vec<u8>],
+    candidates: Vec<(CodePageMark, DynEncoding)>,
+) -> (DynEncoding, DetectedEncoding) {
+    let mut candidates = candidates
+        .into_iter()
+        .map(|(mark, encoding)| {
+            let decoded = samples
+                .iter()
+                .map(|bytes| encoding.decode(bytes).ok().map(Cow::into_owned))
+                .collect::<Vec<_>>();
+            let score = plausibility(samples, &decoded);
+            Candidate {
+                mark,
+                encoding,
+                decoded,
+                score,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut best = 0;
+    for (i, candidate) in candidates.iter().enumerate() {
+        if candidate.score > candidates[best].score {
+            best = i;
+        }
+    }
+    let best = candidates.swap_remove(best);
+    // Encodings giving the same text are not competing
+    let second = candidates
+        .iter()
+        .filter(|candidate| candidate.decoded != best.decoded)
+        .map(|candidate| candidate.score)
+        .fold(0.0, f32::max);
+    let detected = DetectedEncoding {
+        code_page_mark: best.mark,
+        confidence: (best.score - second).max(0.0),
+    };
+    (best.encoding, detected)
+}
+
+/// Returns the share of the non ASCII characters of the decoded samples that are plausible
+/// (ASCII control characters included)
+fn plausibility(samples: &[Vec<u8>], decoded: &[Option<String>]) -> f32 {
+    let mut total = 0.0;
+    let mut count = 0usize;
+    for (bytes, text) in samples.iter().zip(decoded) {
+        let Some(text) = text else {
+            // Text that cannot be decoded is not plausible at all
+            count += bytes.iter().filter(|byte| !byte.is_ascii()).count();
+            continue;
+        };
+        let mut previous = None;
+        for c in text.chars() {
+            // Control characters are only expected between the lines of memos
+            if !c.is_ascii() || (c.is_ascii_control() && !matches!(c, '\t' | '\r' | '\n')) {
+                total += character_plausibility(c, previous);
+                count += 1;
+            }
+            previous = Some(c);
+        }
+    }
+    if count == 0 {
+        1.0
+    } else {
+        total / count as f32
+    }
+}
+
+/// Returns how plausible a non ASCII character is in text, given the one before it
+fn character_plausibility(c: char, previous: Option<char>) -> f32 {
+    let after_letter = previous.is_some_and(char::is_alphabetic);
+    // The ordinal indicators are letters, but mostly show up in mojibake
+    if c.is_alphabetic() && !matches!(c, 'ª' | 'º') {
+        if c.is_uppercase() && previous.is_some_and(char::is_lowercase) {
+            0.25
+        } else {
+            1.0
+        }
+    } else if c.is_control()
+        || c == char::REPLACEMENT_CHARACTER
+        || ('\u{2500}'..='\u{259F}').contains(&c)
+    {
+        0.0
+    } else if after_letter {
+        // Symbols within words
+        0.1
+    } else {
+        0.5
+    }
+}
+
+/// Reads each byte as the character of the same code, to get back the bytes of the strings
+#[derive(Copy, Clone)]
+struct RawBytes;
+
+impl AsCodePageMark for RawBytes {
+    fn code_page_mark(&self) -> CodePageMark {
+        CodePageMark::Undefined
+    }
+}
+
+impl Encoding for RawBytes {
+    fn decode<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>, DecodeError> {
+        Ok(bytes.iter().map(|&byte| char::from(byte)).collect())
+    }
+
+    fn encode<'a>(&self, s: &'a str) -> Result<Cow<'a, [u8]>, EncodeError> {
+        Ok(s.chars().map(|c| c as u8).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoding::MacRoman;
+    use crate::Unicode;
+
+    fn detect(samples: &[&[u8]]) -> DetectedEncoding {
+        let samples = samples
+            .iter()
+            .map(|bytes| bytes.to_vec())
+            .collect::<Vec<_>>();
+        let candidates = vec![
+            (CodePageMark::Utf8, DynEncoding::new_send(Unicode)),
+            (
+                CodePageMark::StandardMacIntosh,
+                DynEncoding::new_send(MacRoman),
+            ),
+        ];
+        detect_encoding(&samples, candidates).1
+    }
+
+    #[test]
+    fn detect_the_most_plausible_encoding() {
+        let detected = detect(&["Müller".as_bytes(), "Café".as_bytes()]);
+        assert_eq!(detected.code_page_mark, CodePageMark::Utf8);
+        assert!(detected.confidence > 0.5, "{:?}", detected);
+
+        // "Müller" and "Café" in Mac OS Roman, which are not valid UTF-8
+        let detected = detect(&[b"M\x9Fller", b"Caf\x8E"]);
+        assert_eq!(detected.code_page_mark, CodePageMark::StandardMacIntosh);
+        assert!(detected.confidence > 0.5, "{:?}", detected);
+
+        // Without text to tell them apart, the first candidate is kept
+        let detected = detect(&[]);
+        assert_eq!(detected.code_page_mark, CodePageMark::Utf8);
+        assert_eq!(detected.confidence, 1.0);
+    }
+
+    #[test]
+    fn capitals_within_words_are_not_plausible() {
+        let lowercase = [Some("Café".to_string())];
+        let uppercase = [Some("CafÉ".to_string())];
+        let samples = [b"Caf\xE9".to_vec()];
+        assert!(plausibility(&samples, &lowercase) > plausibility(&samples, &uppercase));
+        assert_eq!(plausibility(&samples, &[None]), 0.0);
+    }
+}
@@ -1,6 +1,19 @@
 //! Support for working with different codepages / encodings.
 
-use crate::error::{DecodeError, EncodeError};
+pub use crate::error::{DecodeError, EncodeError};
+
+#[cfg(feature = "encoding_rs")]
+mod cjk;
+mod dos;
+#[cfg(feature = "encoding_rs")]
+mod iso;
+mod mac;
+#[cfg(feature = "encoding_rs")]
+pub use cjk::{CP932, CP936, CP949, CP950};
+pub use dos::{Kamenicky, Mazovia};
+#[cfg(feature = "encoding_rs")]
+pub use iso::Iso8859;
+pub use mac::{MacCentralEuropean, MacCyrillic, MacGreek, MacRoman};
 use std::borrow::Cow;
 use std::fmt::Debug;
 
@@ -9,7 +22,7 @@ pub trait AsCodePageMark {
 }
 
 macro_rules! impl_as_code_page_mark {
-    ($($t:ty => $cp:path),* $(,)?) => {
+    ($($t:ty => $cp:expr),* $(,)?) => {
         $(
             impl AsCodePageMark for $t {
                 fn code_page_mark(&self) -> crate::CodePageMark {
@@ -42,20 +55,61 @@ impl_as_code_page_mark!(
     yore::code_pages::CP1251 => crate::CodePageMark::CP1251,
     yore::code_pages::CP1254 => crate::CodePageMark::CP1254,
     yore::code_pages::CP1253 => crate::CodePageMark::CP1253,
+    yore::code_pages::CP737 => crate::CodePageMark::CP737,
+    yore::code_pages::CP857 => crate::CodePageMark::CP857,
+    yore::code_pages::CP863 => crate::CodePageMark::CP863,
+    yore::code_pages::CP1257 => crate::CodePageMark::CP1257,
+    // The Portuguese OEM language driver
+    yore::code_pages::CP860 => crate::CodePageMark::Other(0x24),
 );
 
 /// Trait for reading strings from the database files.
 ///
-/// If the `yore` feature isn't on, this is implemented only by [`UnicodeLossy`] and [`Unicode`].
+/// If the `yore` feature isn't on, this is implemented only by [`UnicodeLossy`], [`Unicode`],
+/// [`Ascii`], the Macintosh code pages ([`MacRoman`]...) and the Czech and Polish
+/// DOS code pages ([`Kamenicky`] and [`Mazovia`]).
 ///
 /// If the `yore` feature is on, this is implemented by all [`yore::CodePage`].
 ///
+/// If the `encoding_rs` feature is on, this is implemented by the double-byte code pages
+/// of Japanese, Chinese and Korean (`CP932`, `CP936`, `CP949` and `CP950`)
+/// and by the parts of ISO-8859 (`Iso8859`).
+///
 /// Note: This trait might be extended with an `encode` function in the future.
 pub trait Encoding: EncodingClone + AsCodePageMark {
     /// Decode encoding into UTF-8 string. If codepoints can't be represented, an error is returned.
     fn decode<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>, DecodeError>;
 
     fn encode<'a>(&self, s: &'a str) -> Result<Cow<'a, [u8]>, EncodeError>;
+
+    /// Decode encoding into UTF-8 string, replacing the bytes that can't be decoded
+    /// with the replacement character (U+FFFD).
+    ///
+    /// The default implementation decodes the shortest sequences of up to 4 bytes it can,
+    /// one byte at a time being replaced.
+    fn decode_lossy<'a>(&self, bytes: &'a [u8]) -> Cow<'a, str> {
+        if let Ok(decoded) = self.decode(bytes) {
+            return decoded;
+        }
+        let mut text = String::with_capacity(bytes.len());
+        let mut start = 0;
+        while start < bytes.len() {
+            let end = bytes.len().min(start + 4);
+            let decoded = (start + 1..=end)
+                .find_map(|stop| self.decode(&bytes[start..stop]).ok().map(|s| (stop, s)));
+            match decoded {
+                Some((stop, decoded)) => {
+                    text.push_str(&decoded);
+                    start = stop;
+                }
+                None => {
+                    text.push(char::REPLACEMENT_CHARACTER);
+                    start += 1;
+                }
+            }
+        }
+        Cow::Owned(text)
+    }
 }
 
 /// Trait to be able to clone a Box<dyn Encoding>
@@ -112,6 +166,10 @@ impl Encoding for Unicode {
             .map_err(DecodeError::FromUtf8)
     }
 
+    fn decode_lossy<'a>(&self, bytes: &'a [u8]) -> Cow<'a, str> {
+        String::from_utf8_lossy(bytes)
+    }
+
     fn encode<'a>(&self, s: &'a str) -> Result<Cow<'a, [u8]>, EncodeError> {
         Ok(s.as_bytes().into())
     }
@@ -137,13 +195,91 @@ impl Encoding for Ascii {
 #[derive(Clone)]
 pub(crate) struct DynEncoding {
     inner: Box<dyn Encoding>,
+    /// The same encoding, when it is known to be `Send`, to decode records on other threads
+    #[cfg(feature = "rayon")]
+    send: Option<SendEncoding>,
 }
 
 impl DynEncoding {
     pub(crate) fn new<E: Encoding + 'static>(encoding: E) -> Self {
         Self {
             inner: Box::new(encoding) as Box<dyn Encoding>,
+            #[cfg(feature = "rayon")]
+            send: None,
+        }
+    }
+
+    /// Creates an encoding whose clones can be sent to other threads
+    pub(crate) fn new_send<E: Encoding + Clone + Send + 'static>(encoding: E) -> Self {
+        #[cfg(feature = "rayon")]
+        {
+            Self {
+                inner: Box::new(encoding.clone()) as Box<dyn Encoding>,
+                send: Some(SendEncoding(Box::new(encoding))),
+            }
         }
+        #[cfg(not(feature = "rayon"))]
+        {
+            Self::new(encoding)
+        }
+    }
+
+    /// Returns a clone of the encoding that can be sent to other threads, if it is `Send`
+    #[cfg(feature = "rayon")]
+    pub(crate) fn to_send(&self) -> Option<SendEncoding> {
+        self.send.clone()
+    }
+}
+
+/// An encoding that can be sent to other threads
+#[cfg(feature = "rayon")]
+pub(crate) struct SendEncoding(Box<dyn SendClone>);
+
+#[cfg(feature = "rayon")]
+trait SendClone: Encoding + Send {
+    fn clone_send(&self) -> SendEncoding;
+}
+
+#[cfg(feature = "rayon")]
+impl<T: Encoding + Clone + Send + 'static> SendClone for T {
+    fn clone_send(&self) -> SendEncoding {
+        SendEncoding(Box::new(self.clone()))
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl Clone for SendEncoding {
+    fn clone(&self) -> Self {
+        self.0.clone_send()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl From<SendEncoding> for DynEncoding {
+    fn from(encoding: SendEncoding) -> Self {
+        Self::new(encoding)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl AsCodePageMark for SendEncoding {
+    fn code_page_mark(&self) -> crate::CodePageMark {
+        self.0.code_page_mark()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl Encoding for SendEncoding {
+    fn decode<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>, DecodeError> {
+        self.0.decode(bytes)
+    }
+
+    fn decode_lossy<'a>(&self, bytes: &'a [u8]) -> Cow<'a, str> {
+        self.0.decode_lossy(bytes)
+    }
+
+    fn encode<'a>(&self, s: &'a str) -> Result<Cow<'a, [u8]>, EncodeError> {
+        self.0.encode(s)
     }
 }
 
@@ -158,6 +294,10 @@ impl Encoding for DynEncoding {
         self.inner.decode(bytes)
     }
 
+    fn decode_lossy<'a>(&self, bytes: &'a [u8]) -> Cow<'a, str> {
+        self.inner.decode_lossy(bytes)
+    }
+
     fn encode<'a>(&self, s: &'a str) -> Result<Cow<'a, [u8]>, EncodeError> {
         self.inner.encode(s)
     }
@@ -175,6 +315,10 @@ where
     fn encode<'a>(&self, s: &'a str) -> Result<Cow<'a, [u8]>, EncodeError> {
         self.encode(s).map_err(Into::into)
     }
+
+    fn decode_lossy<'a>(&self, bytes: &'a [u8]) -> Cow<'a, str> {
+        yore::CodePage::decode_lossy(self, bytes)
+    }
 }
 
 #[cfg(feature = "yore")]
@@ -204,3 +348,18 @@ where
         Ok(self.0.encode_lossy(s, b'?'))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_lossy_replaces_invalid_bytes() {
+        assert_eq!(
+            Ascii.decode_lossy(b"Caf\xE9 au lait"),
+            "Caf\u{FFFD} au lait"
+        );
+        assert_eq!(Ascii.decode_lossy(b"Tea"), "Tea");
+        assert_eq!(Unicode.decode_lossy(b"Caf\xC3\xA9\xFF"), "Café\u{FFFD}");
+    }
+}
@@ -0,0 +1,116 @@
+//! The double-byte code pages of Japanese, Chinese and Korean, provided by encoding_rs
+use std::borrow::Cow;
+
+use super::{AsCodePageMark, Encoding};
+use crate::error::{DecodeError, EncodeError};
+use crate::CodePageMark;
+
+macro_rules! cjk_code_page {
+    ($(#[$doc:meta])* $name:ident, $encoding:path, $mark:path) => {
+        $(#[$doc])*
+        #[derive(Copy, Clone, Debug)]
+        pub struct $name;
+
+        impl AsCodePageMark for $name {
+            fn code_page_mark(&self) -> CodePageMark {
+                $mark
+            }
+        }
+
+        impl Encoding for $name {
+            fn decode<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>, DecodeError> {
+                decode_with($encoding, stringify!($name), bytes)
+            }
+
+            fn encode<'a>(&self, s: &'a str) -> Result<Cow<'a, [u8]>, EncodeError> {
+                encode_with($encoding, stringify!($name), s)
+            }
+
+            fn decode_lossy<'a>(&self, bytes: &'a [u8]) -> Cow<'a, str> {
+                $encoding.decode_without_bom_handling(bytes).0
+            }
+        }
+    };
+}
+
+/// Decodes the bytes with an encoding of encoding_rs, failing on the invalid ones
+pub(super) fn decode_with<'a>(
+    encoding: &'static encoding_rs::Encoding,
+    name: &str,
+    bytes: &'a [u8],
+) -> Result<Cow<'a, str>, DecodeError> {
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .ok_or_else(|| {
+            DecodeError::Message(format!(
+                "The bytes {:?} cannot be decoded in {}",
+                bytes, name
+            ))
+        })
+}
+
+/// Encodes the text with an encoding of encoding_rs, failing on the unmappable characters
+pub(super) fn encode_with<'a>(
+    encoding: &'static encoding_rs::Encoding,
+    name: &str,
+    text: &'a str,
+) -> Result<Cow<'a, [u8]>, EncodeError> {
+    // encoding_rs replaces the characters it cannot encode by HTML entities
+    let (encoded, _, had_unmappable) = encoding.encode(text);
+    if had_unmappable {
+        return Err(EncodeError::Message(format!(
+            "'{}' cannot be encoded in {}",
+            text, name
+        )));
+    }
+    Ok(encoded)
+}
+
+cjk_code_page!(
+    /// The Japanese Shift-JIS code page (932), of the `CP932` language driver
+    CP932,
+    encoding_rs::SHIFT_JIS,
+    CodePageMark::CP932
+);
+cjk_code_page!(
+    /// The Simplified Chinese GBK code page (936), of the `CP936` language driver
+    CP936,
+    encoding_rs::GBK,
+    CodePageMark::CP936
+);
+cjk_code_page!(
+    /// The Korean code page (949), of the `CP949` language driver
+    CP949,
+    encoding_rs::EUC_KR,
+    CodePageMark::CP949
+);
+cjk_code_page!(
+    /// The Traditional Chinese Big5 code page (950), of the `CP950` language driver
+    CP950,
+    encoding_rs::BIG5,
+    CodePageMark::CP950
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let text = "東京都";
+        let encoded = CP932.encode(text).unwrap();
+        assert_eq!(encoded.as_ref(), b"\x93\x8C\x8B\x9E\x93\x73");
+        assert_eq!(CP932.decode(&encoded).unwrap(), text);
+
+        let encoded = CP950.encode("臺北").unwrap();
+        assert_eq!(CP950.decode(&encoded).unwrap(), "臺北");
+    }
+
+    #[test]
+    fn invalid_bytes() {
+        // A lead byte without its trail byte
+        assert!(CP936.decode(b"abc\x81").is_err());
+        assert_eq!(CP936.decode_lossy(b"abc\x81"), "abc\u{FFFD}");
+        assert!(CP949.encode("東京 \u{1F600}").is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! The Czech and Polish DOS code pages, which yore does not provide
+//!
+//! Both keep the box drawing and mathematical characters of CP437
+//! in their bytes 0xB0 to 0xFF.
+use std::borrow::Cow;
+
+use super::mac::{decode_with, encode_with, single_byte_code_page};
+use super::{AsCodePageMark, Encoding};
+use crate::error::{DecodeError, EncodeError};
+use crate::CodePageMark;
+
+single_byte_code_page!(
+    /// The Kamenicky code page (895), of the `CP895` language driver of Czech tables
+    Kamenicky,
+    KAMENICKY,
+    CodePageMark::CP895
+);
+single_byte_code_page!(
+    /// The Mazovia code page (620), of the `CP620` language driver of Polish tables
+    Mazovia,
+    MAZOVIA,
+    CodePageMark::CP620
+);
+
+/// Characters of the bytes 0x80 to 0xFF of Kamenicky
+const KAMENICKY: [char; 128] = [
+    '\u{010C}', '\u{00FC}', '\u{00E9}', '\u{010F}', '\u{00E4}', '\u{010E}', '\u{0164}', '\u{010D}',
+    '\u{011B}', '\u{011A}', '\u{0139}', '\u{00CD}', '\u{013E}', '\u{013A}', '\u{00C4}', '\u{00C1}',
+    '\u{00C9}', '\u{017E}', '\u{017D}', '\u{00F4}', '\u{00F6}', '\u{00D3}', '\u{016F}', '\u{00DA}',
+    '\u{00FD}', '\u{00D6}', '\u{00DC}', '\u{0160}', '\u{013D}', '\u{00DD}', '\u{0158}', '\u{0165}',
+    '\u{00E1}', '\u{00ED}', '\u{00F3}', '\u{00FA}', '\u{0148}', '\u{0147}', '\u{016E}', '\u{00D4}',
+    '\u{0161}', '\u{0159}', '\u{0155}', '\u{0154}', '\u{00BC}', '\u{00A7}', '\u{00AB}', '\u{00BB}',
+    '\u{2591}', '\u{2592}', '\u{2593}', '\u{2502}', '\u{2524}', '\u{2561}', '\u{2562}', '\u{2556}',
+    '\u{2555}', '\u{2563}', '\u{2551}', '\u{2557}', '\u{255D}', '\u{255C}', '\u{255B}', '\u{2510}',
+    '\u{2514}', '\u{2534}', '\u{252C}', '\u{251C}', '\u{2500}', '\u{253C}', '\u{255E}', '\u{255F}',
+    '\u{255A}', '\u{2554}', '\u{2569}', '\u{2566}', '\u{2560}', '\u{2550}', '\u{256C}', '\u{2567}',
+    '\u{2568}', '\u{2564}', '\u{2565}', '\u{2559}', '\u{2558}', '\u{2552}', '\u{2553}', '\u{256B}',
+    '\u{256A}', '\u{2518}', '\u{250C}', '\u{2588}', '\u{2584}', '\u{258C}', '\u{2590}', '\u{2580}',
+    '\u{03B1}', '\u{00DF}', '\u{0393}', '\u{03C0}', '\u{03A3}', '\u{03C3}', '\u{00B5}', '\u{03C4}',
+    '\u{03A6}', '\u{0398}', '\u{03A9}', '\u{03B4}', '\u{221E}', '\u{03C6}', '\u{03B5}', '\u{2229}',
+    '\u{2261}', '\u{00B1}', '\u{2265}', '\u{2264}', '\u{2320}', '\u{2321}', '\u{00F7}', '\u{2248}',
+    '\u{00B0}', '\u{2219}', '\u{00B7}', '\u{221A}', '\u{207F}', '\u{00B2}', '\u{25A0}', '\u{00A0}',
+];
+
+/// Characters of the bytes 0x80 to 0xFF of Mazovia
+const MAZOVIA: [char; 128] = [
+    '\u{00C7}', '\u{00FC}', '\u{00E9}', '\u{00E2}', '\u{00E4}', '\u{00E0}', '\u{0105}', '\u{00E7}',
+    '\u{00EA}', '\u{00EB}', '\u{00E8}', '\u{00EF}', '\u{00EE}', '\u{0107}', '\u{00C4}', '\u{0104}',
+    '\u{0118}', '\u{0119}', '\u{0142}', '\u{00F4}', '\u{00F6}', '\u{0106}', '\u{00FB}', '\u{00F9}',
+    '\u{015A}', '\u{00D6}', '\u{00DC}', '\u{00A2}', '\u{0141}', '\u{00A5}', '\u{015B}', '\u{0192}',
+    '\u{0179}', '\u{017B}', '\u{00F3}', '\u{00D3}', '\u{0144}', '\u{0143}', '\u{017A}', '\u{017C}',
+    '\u{00BF}', '\u{2310}', '\u{00AC}', '\u{00BD}', '\u{00BC}', '\u{00A1}', '\u{00AB}', '\u{00BB}',
+    '\u{2591}', '\u{2592}', '\u{2593}', '\u{2502}', '\u{2524}', '\u{2561}', '\u{2562}', '\u{2556}',
+    '\u{2555}', '\u{2563}', '\u{2551}', '\u{2557}', '\u{255D}', '\u{255C}', '\u{255B}', '\u{2510}',
+    '\u{2514}', '\u{2534}', '\u{252C}', '\u{251C}', '\u{2500}', '\u{253C}', '\u{255E}', '\u{255F}',
+    '\u{255A}', '\u{2554}', '\u{2569}', '\u{2566}', '\u{2560}', '\u{2550}', '\u{256C}', '\u{2567}',
+    '\u{2568}', '\u{2564}', '\u{2565}', '\u{2559}', '\u{2558}', '\u{2552}', '\u{2553}', '\u{256B}',
+    '\u{256A}', '\u{2518}', '\u{250C}', '\u{2588}', '\u{2584}', '\u{258C}', '\u{2590}', '\u{2580}',
+    '\u{03B1}', '\u{00DF}', '\u{0393}', '\u{03C0}', '\u{03A3}', '\u{03C3}', '\u{00B5}', '\u{03C4}',
+    '\u{03A6}', '\u{0398}', '\u{03A9}', '\u{03B4}', '\u{221E}', '\u{03C6}', '\u{03B5}', '\u{2229}',
+    '\u{2261}', '\u{00B1}', '\u{2265}', '\u{2264}', '\u{2320}', '\u{2321}', '\u{00F7}', '\u{2248}',
+    '\u{00B0}', '\u{2219}', '\u{00B7}', '\u{221A}', '\u{207F}', '\u{00B2}', '\u{25A0}', '\u{00A0}',
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_and_encode() {
+        let text = Kamenicky.decode(b"\x9Bkoda \x87\x88sk\xA0").unwrap();
+        assert_eq!(text, "\u{0160}koda \u{010D}\u{011B}sk\u{00E1}");
+        assert_eq!(
+            Kamenicky.encode(&text).unwrap(),
+            &b"\x9Bkoda \x87\x88sk\xA0"[..]
+        );
+
+        let text = Mazovia.decode(b"\x92\xA4d\x9C \xA6").unwrap();
+        assert_eq!(text, "\u{0142}\u{0144}d\u{0141} \u{017A}");
+        assert!(Mazovia.encode("\u{00A4}").is_err());
+        assert_eq!(Mazovia.encode("\u{2592}").unwrap(), &b"\xB1"[..]);
+    }
+}
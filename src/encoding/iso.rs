@@ -0,0 +1,75 @@
+//! The ISO-8859 code pages, provided by encoding_rs
+use std::borrow::Cow;
+
+use super::cjk::{decode_with, encode_with};
+use super::{AsCodePageMark, Encoding};
+use crate::error::{DecodeError, EncodeError};
+use crate::CodePageMark;
+
+/// A part of ISO-8859, which GIS software may name in the `.cpg` file of a table
+///
+/// No language driver uses these code pages, so tables written with them
+/// have an undefined code page mark.
+///
+/// # Example
+///
+/// ```
+/// use dbase::Encoding;
+/// let latin_9 = dbase::encoding::Iso8859::new(15).unwrap();
+/// assert_eq!(latin_9.decode(b"\xA4").unwrap(), "€");
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Iso8859 {
+    part: u8,
+    encoding: &'static encoding_rs::Encoding,
+}
+
+impl Iso8859 {
+    /// Returns the given part of ISO-8859
+    ///
+    /// Returns `None` for the parts 1, 9 and 11, which are read as their Windows supersets
+    /// (CP1252, CP1254 and CP874), and for the parts that do not exist.
+    pub fn new(part: u8) -> Option<Self> {
+        let encoding = match part {
+            2 => encoding_rs::ISO_8859_2,
+            3 => encoding_rs::ISO_8859_3,
+            4 => encoding_rs::ISO_8859_4,
+            5 => encoding_rs::ISO_8859_5,
+            6 => encoding_rs::ISO_8859_6,
+            7 => encoding_rs::ISO_8859_7,
+            8 => encoding_rs::ISO_8859_8,
+            10 => encoding_rs::ISO_8859_10,
+            13 => encoding_rs::ISO_8859_13,
+            14 => encoding_rs::ISO_8859_14,
+            15 => encoding_rs::ISO_8859_15,
+            16 => encoding_rs::ISO_8859_16,
+            _ => return None,
+        };
+        Some(Self { part, encoding })
+    }
+
+    /// Returns the number of the part of ISO-8859
+    pub fn part(&self) -> u8 {
+        self.part
+    }
+}
+
+impl AsCodePageMark for Iso8859 {
+    fn code_page_mark(&self) -> CodePageMark {
+        CodePageMark::Undefined
+    }
+}
+
+impl Encoding for Iso8859 {
+    fn decode<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>, DecodeError> {
+        decode_with(self.encoding, self.encoding.name(), bytes)
+    }
+
+    fn encode<'a>(&self, s: &'a str) -> Result<Cow<'a, [u8]>, EncodeError> {
+        encode_with(self.encoding, self.encoding.name(), s)
+    }
+
+    fn decode_lossy<'a>(&self, bytes: &'a [u8]) -> Cow<'a, str> {
+        self.encoding.decode_without_bom_handling(bytes).0
+    }
+}
@@ -0,0 +1,202 @@
+//! The Macintosh code pages, which yore does not provide
+use std::borrow::Cow;
+
+use super::{AsCodePageMark, Encoding};
+use crate::error::{DecodeError, EncodeError};
+use crate::CodePageMark;
+
+/// Defines an encoding whose upper half is given by a table of characters
+macro_rules! single_byte_code_page {
+    ($(#[$doc:meta])* $name:ident, $table:ident, $mark:path) => {
+        $(#[$doc])*
+        #[derive(Copy, Clone, Debug)]
+        pub struct $name;
+
+        impl AsCodePageMark for $name {
+            fn code_page_mark(&self) -> CodePageMark {
+                $mark
+            }
+        }
+
+        impl Encoding for $name {
+            fn decode<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>, DecodeError> {
+                Ok(decode_with(&$table, bytes))
+            }
+
+            fn encode<'a>(&self, s: &'a str) -> Result<Cow<'a, [u8]>, EncodeError> {
+                encode_with(&$table, stringify!($name), s)
+            }
+        }
+    };
+}
+pub(super) use single_byte_code_page;
+
+single_byte_code_page!(
+    /// The Mac OS Roman code page (10000), of the `StandardMacIntosh` language driver
+    MacRoman,
+    MAC_ROMAN,
+    CodePageMark::StandardMacIntosh
+);
+single_byte_code_page!(
+    /// The Mac OS Cyrillic code page (10007), of the `RussianMacIntosh` language driver
+    MacCyrillic,
+    MAC_CYRILLIC,
+    CodePageMark::RussianMacIntosh
+);
+single_byte_code_page!(
+    /// The Mac OS Central European code page (10029), of the `MacIntoshEE` language driver
+    MacCentralEuropean,
+    MAC_CENTRAL_EUROPEAN,
+    CodePageMark::MacIntoshEE
+);
+single_byte_code_page!(
+    /// The Mac OS Greek code page (10006), of the `GreekMacIntosh` language driver
+    MacGreek,
+    MAC_GREEK,
+    CodePageMark::GreekMacIntosh
+);
+
+/// Decodes the bytes of a code page whose lower half is ASCII,
+/// `table` giving the characters of the upper half
+pub(super) fn decode_with<'a>(table: &[char; 128], bytes: &'a [u8]) -> Cow<'a, str> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) if bytes.is_ascii() => Cow::Borrowed(text),
+        _ => bytes
+            .iter()
+            .map(|&byte| match byte {
+                0..=0x7F => char::from(byte),
+                _ => table[usize::from(byte - 0x80)],
+            })
+            .collect(),
+    }
+}
+
+/// Encodes the text in a code page whose lower half is ASCII,
+/// `table` giving the characters of the upper half
+pub(super) fn encode_with<'a>(
+    table: &[char; 128],
+    name: &str,
+    text: &'a str,
+) -> Result<Cow<'a, [u8]>, EncodeError> {
+    if text.is_ascii() {
+        return Ok(Cow::Borrowed(text.as_bytes()));
+    }
+    text.chars()
+        .map(|c| match c {
+            '\0'..='\x7F' => Ok(c as u8),
+            _ => table
+                .iter()
+                .position(|&candidate| candidate == c)
+                .map(|position| 0x80 + position as u8)
+                .ok_or_else(|| {
+                    EncodeError::Message(format!("'{}' cannot be encoded in {}", c, name))
+                }),
+        })
+        .collect::<Result<Vec<u8>, _>>()
+        .map(Cow::Owned)
+}
+
+/// Characters of the bytes 0x80 to 0xFF of Mac OS Roman
+const MAC_ROMAN: [char; 128] = [
+    '\u{00C4}', '\u{00C5}', '\u{00C7}', '\u{00C9}', '\u{00D1}', '\u{00D6}', '\u{00DC}', '\u{00E1}',
+    '\u{00E0}', '\u{00E2}', '\u{00E4}', '\u{00E3}', '\u{00E5}', '\u{00E7}', '\u{00E9}', '\u{00E8}',
+    '\u{00EA}', '\u{00EB}', '\u{00ED}', '\u{00EC}', '\u{00EE}', '\u{00EF}', '\u{00F1}', '\u{00F3}',
+    '\u{00F2}', '\u{00F4}', '\u{00F6}', '\u{00F5}', '\u{00FA}', '\u{00F9}', '\u{00FB}', '\u{00FC}',
+    '\u{2020}', '\u{00B0}', '\u{00A2}', '\u{00A3}', '\u{00A7}', '\u{2022}', '\u{00B6}', '\u{00DF}',
+    '\u{00AE}', '\u{00A9}', '\u{2122}', '\u{00B4}', '\u{00A8}', '\u{2260}', '\u{00C6}', '\u{00D8}',
+    '\u{221E}', '\u{00B1}', '\u{2264}', '\u{2265}', '\u{00A5}', '\u{00B5}', '\u{2202}', '\u{2211}',
+    '\u{220F}', '\u{03C0}', '\u{222B}', '\u{00AA}', '\u{00BA}', '\u{03A9}', '\u{00E6}', '\u{00F8}',
+    '\u{00BF}', '\u{00A1}', '\u{00AC}', '\u{221A}', '\u{0192}', '\u{2248}', '\u{2206}', '\u{00AB}',
+    '\u{00BB}', '\u{2026}', '\u{00A0}', '\u{00C0}', '\u{00C3}', '\u{00D5}', '\u{0152}', '\u{0153}',
+    '\u{2013}', '\u{2014}', '\u{201C}', '\u{201D}', '\u{2018}', '\u{2019}', '\u{00F7}', '\u{25CA}',
+    '\u{00FF}', '\u{0178}', '\u{2044}', '\u{20AC}', '\u{2039}', '\u{203A}', '\u{FB01}', '\u{FB02}',
+    '\u{2021}', '\u{00B7}', '\u{201A}', '\u{201E}', '\u{2030}', '\u{00C2}', '\u{00CA}', '\u{00C1}',
+    '\u{00CB}', '\u{00C8}', '\u{00CD}', '\u{00CE}', '\u{00CF}', '\u{00CC}', '\u{00D3}', '\u{00D4}',
+    '\u{F8FF}', '\u{00D2}', '\u{00DA}', '\u{00DB}', '\u{00D9}', '\u{0131}', '\u{02C6}', '\u{02DC}',
+    '\u{00AF}', '\u{02D8}', '\u{02D9}', '\u{02DA}', '\u{00B8}', '\u{02DD}', '\u{02DB}', '\u{02C7}',
+];
+
+/// Characters of the bytes 0x80 to 0xFF of Mac OS Cyrillic
+const MAC_CYRILLIC: [char; 128] = [
+    '\u{0410}', '\u{0411}', '\u{0412}', '\u{0413}', '\u{0414}', '\u{0415}', '\u{0416}', '\u{0417}',
+    '\u{0418}', '\u{0419}', '\u{041A}', '\u{041B}', '\u{041C}', '\u{041D}', '\u{041E}', '\u{041F}',
+    '\u{0420}', '\u{0421}', '\u{0422}', '\u{0423}', '\u{0424}', '\u{0425}', '\u{0426}', '\u{0427}',
+    '\u{0428}', '\u{0429}', '\u{042A}', '\u{042B}', '\u{042C}', '\u{042D}', '\u{042E}', '\u{042F}',
+    '\u{2020}', '\u{00B0}', '\u{0490}', '\u{00A3}', '\u{00A7}', '\u{2022}', '\u{00B6}', '\u{0406}',
+    '\u{00AE}', '\u{00A9}', '\u{2122}', '\u{0402}', '\u{0452}', '\u{2260}', '\u{0403}', '\u{0453}',
+    '\u{221E}', '\u{00B1}', '\u{2264}', '\u{2265}', '\u{0456}', '\u{00B5}', '\u{0491}', '\u{0408}',
+    '\u{0404}', '\u{0454}', '\u{0407}', '\u{0457}', '\u{0409}', '\u{0459}', '\u{040A}', '\u{045A}',
+    '\u{0458}', '\u{0405}', '\u{00AC}', '\u{221A}', '\u{0192}', '\u{2248}', '\u{2206}', '\u{00AB}',
+    '\u{00BB}', '\u{2026}', '\u{00A0}', '\u{040B}', '\u{045B}', '\u{040C}', '\u{045C}', '\u{0455}',
+    '\u{2013}', '\u{2014}', '\u{201C}', '\u{201D}', '\u{2018}', '\u{2019}', '\u{00F7}', '\u{201E}',
+    '\u{040E}', '\u{045E}', '\u{040F}', '\u{045F}', '\u{2116}', '\u{0401}', '\u{0451}', '\u{044F}',
+    '\u{0430}', '\u{0431}', '\u{0432}', '\u{0433}', '\u{0434}', '\u{0435}', '\u{0436}', '\u{0437}',
+    '\u{0438}', '\u{0439}', '\u{043A}', '\u{043B}', '\u{043C}', '\u{043D}', '\u{043E}', '\u{043F}',
+    '\u{0440}', '\u{0441}', '\u{0442}', '\u{0443}', '\u{0444}', '\u{0445}', '\u{0446}', '\u{0447}',
+    '\u{0448}', '\u{0449}', '\u{044A}', '\u{044B}', '\u{044C}', '\u{044D}', '\u{044E}', '\u{20AC}',
+];
+
+/// Characters of the bytes 0x80 to 0xFF of Mac OS Central European
+const MAC_CENTRAL_EUROPEAN: [char; 128] = [
+    '\u{00C4}', '\u{0100}', '\u{0101}', '\u{00C9}', '\u{0104}', '\u{00D6}', '\u{00DC}', '\u{00E1}',
+    '\u{0105}', '\u{010C}', '\u{00E4}', '\u{010D}', '\u{0106}', '\u{0107}', '\u{00E9}', '\u{0179}',
+    '\u{017A}', '\u{010E}', '\u{00ED}', '\u{010F}', '\u{0112}', '\u{0113}', '\u{0116}', '\u{00F3}',
+    '\u{0117}', '\u{00F4}', '\u{00F6}', '\u{00F5}', '\u{00FA}', '\u{011A}', '\u{011B}', '\u{00FC}',
+    '\u{2020}', '\u{00B0}', '\u{0118}', '\u{00A3}', '\u{00A7}', '\u{2022}', '\u{00B6}', '\u{00DF}',
+    '\u{00AE}', '\u{00A9}', '\u{2122}', '\u{0119}', '\u{00A8}', '\u{2260}', '\u{0123}', '\u{012E}',
+    '\u{012F}', '\u{012A}', '\u{2264}', '\u{2265}', '\u{012B}', '\u{0136}', '\u{2202}', '\u{2211}',
+    '\u{0142}', '\u{013B}', '\u{013C}', '\u{013D}', '\u{013E}', '\u{0139}', '\u{013A}', '\u{0145}',
+    '\u{0146}', '\u{0143}', '\u{00AC}', '\u{221A}', '\u{0144}', '\u{0147}', '\u{2206}', '\u{00AB}',
+    '\u{00BB}', '\u{2026}', '\u{00A0}', '\u{0148}', '\u{0150}', '\u{00D5}', '\u{0151}', '\u{014C}',
+    '\u{2013}', '\u{2014}', '\u{201C}', '\u{201D}', '\u{2018}', '\u{2019}', '\u{00F7}', '\u{25CA}',
+    '\u{014D}', '\u{0154}', '\u{0155}', '\u{0158}', '\u{2039}', '\u{203A}', '\u{0159}', '\u{0156}',
+    '\u{0157}', '\u{0160}', '\u{201A}', '\u{201E}', '\u{0161}', '\u{015A}', '\u{015B}', '\u{00C1}',
+    '\u{0164}', '\u{0165}', '\u{00CD}', '\u{017D}', '\u{017E}', '\u{016A}', '\u{00D3}', '\u{00D4}',
+    '\u{016B}', '\u{016E}', '\u{00DA}', '\u{016F}', '\u{0170}', '\u{0171}', '\u{0172}', '\u{0173}',
+    '\u{00DD}', '\u{00FD}', '\u{0137}', '\u{017B}', '\u{0141}', '\u{017C}', '\u{0122}', '\u{02C7}',
+];
+
+/// Characters of the bytes 0x80 to 0xFF of Mac OS Greek
+const MAC_GREEK: [char; 128] = [
+    '\u{00C4}', '\u{00B9}', '\u{00B2}', '\u{00C9}', '\u{00B3}', '\u{00D6}', '\u{00DC}', '\u{0385}',
+    '\u{00E0}', '\u{00E2}', '\u{00E4}', '\u{0384}', '\u{00A8}', '\u{00E7}', '\u{00E9}', '\u{00E8}',
+    '\u{00EA}', '\u{00EB}', '\u{00A3}', '\u{2122}', '\u{00EE}', '\u{00EF}', '\u{2022}', '\u{00BD}',
+    '\u{2030}', '\u{00F4}', '\u{00F6}', '\u{00A6}', '\u{20AC}', '\u{00F9}', '\u{00FB}', '\u{00FC}',
+    '\u{2020}', '\u{0393}', '\u{0394}', '\u{0398}', '\u{039B}', '\u{039E}', '\u{03A0}', '\u{00DF}',
+    '\u{00AE}', '\u{00A9}', '\u{03A3}', '\u{03AA}', '\u{00A7}', '\u{2260}', '\u{00B0}', '\u{00B7}',
+    '\u{0391}', '\u{00B1}', '\u{2264}', '\u{2265}', '\u{00A5}', '\u{0392}', '\u{0395}', '\u{0396}',
+    '\u{0397}', '\u{0399}', '\u{039A}', '\u{039C}', '\u{03A6}', '\u{03AB}', '\u{03A8}', '\u{03A9}',
+    '\u{03AC}', '\u{039D}', '\u{00AC}', '\u{039F}', '\u{03A1}', '\u{2248}', '\u{03A4}', '\u{00AB}',
+    '\u{00BB}', '\u{2026}', '\u{00A0}', '\u{03A5}', '\u{03A7}', '\u{0386}', '\u{0388}', '\u{0153}',
+    '\u{2013}', '\u{2015}', '\u{201C}', '\u{201D}', '\u{2018}', '\u{2019}', '\u{00F7}', '\u{0389}',
+    '\u{038A}', '\u{038C}', '\u{038E}', '\u{03AD}', '\u{03AE}', '\u{03AF}', '\u{03CC}', '\u{038F}',
+    '\u{03CD}', '\u{03B1}', '\u{03B2}', '\u{03C8}', '\u{03B4}', '\u{03B5}', '\u{03C6}', '\u{03B3}',
+    '\u{03B7}', '\u{03B9}', '\u{03BE}', '\u{03BA}', '\u{03BB}', '\u{03BC}', '\u{03BD}', '\u{03BF}',
+    '\u{03C0}', '\u{03CE}', '\u{03C1}', '\u{03C3}', '\u{03C4}', '\u{03B8}', '\u{03C9}', '\u{03C2}',
+    '\u{03C7}', '\u{03C5}', '\u{03B6}', '\u{03CA}', '\u{03CB}', '\u{0390}', '\u{03B0}', '\u{00AD}',
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_and_encode() {
+        let bytes = b"Caf\x8E \xA5 \xDB";
+        let text = MacRoman.decode(bytes).unwrap();
+        assert_eq!(text, "Caf\u{e9} \u{2022} \u{20ac}");
+        assert_eq!(MacRoman.encode(&text).unwrap(), &bytes[..]);
+        assert!(matches!(
+            MacRoman.decode(b"ascii").unwrap(),
+            Cow::Borrowed("ascii")
+        ));
+        assert!(MacRoman.encode("\u{0416}").is_err());
+
+        assert_eq!(
+            MacCyrillic.decode(b"\x8C\xEE\xF1").unwrap(),
+            "\u{041c}\u{043e}\u{0441}"
+        );
+        assert_eq!(MacCentralEuropean.encode("\u{0141}").unwrap(), &b"\xFC"[..]);
+        assert_eq!(MacGreek.decode(b"\xE1").unwrap(), "\u{03b1}");
+    }
+}
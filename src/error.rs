@@ -15,6 +15,8 @@ pub enum ErrorKind {
     /// Happens when at least one field is a Memo type
     /// and the that additional memo file could not be found / was not given
     MissingMemoFile,
+    /// The table has no production index (.mdx or .cdx) next to it
+    MissingIndexFile,
     /// Something went wrong when we tried to open the associated memo file
     ErrorOpeningMemoFile(std::io::Error),
     /// The conversion from a FieldValue to another type could not be made
@@ -34,6 +36,18 @@ pub enum ErrorKind {
     StringDecodeError(DecodeError),
     /// A string from the database could not be encoded
     StringEncodeError(EncodeError),
+    /// The index does not correspond to any record of the file
+    RecordIndexOutOfRange(usize),
+    /// No field of the file has the given name
+    FieldNotFound(String),
+    /// The index file is corrupted or uses an unsupported layout
+    InvalidIndex(String),
+    /// No tag of the index file has the given name
+    TagNotFound(String),
+    /// The key expression of an index cannot be computed by this crate
+    UnsupportedKeyExpression(String),
+    /// An xBase expression could not be parsed or evaluated
+    InvalidExpression(String),
     Message(String),
 }
 
@@ -186,6 +200,9 @@ impl std::fmt::Display for ErrorKind {
                 write!(f, "The FieldType code '{}' is note a valid one", c)
             }
             ErrorKind::MissingMemoFile => write!(f, "The memo file could not be found"),
+            ErrorKind::MissingIndexFile => {
+                write!(f, "The production index file could not be found")
+            }
             ErrorKind::ErrorOpeningMemoFile(err) => {
                 write!(
                     f,
@@ -218,6 +235,18 @@ impl std::fmt::Display for ErrorKind {
             ErrorKind::UnsupportedCodePage(code) => {
                 write!(f, "The code page '{:?}' is not supported", code)
             }
+            ErrorKind::RecordIndexOutOfRange(index) => {
+                write!(f, "There is no record at index {}", index)
+            }
+            ErrorKind::FieldNotFound(name) => write!(f, "There is no field named '{}'", name),
+            ErrorKind::InvalidIndex(reason) => write!(f, "The index file is invalid: {}", reason),
+            ErrorKind::TagNotFound(name) => write!(f, "There is no index tag named '{}'", name),
+            ErrorKind::InvalidExpression(reason) => {
+                write!(f, "The expression is invalid: {}", reason)
+            }
+            ErrorKind::UnsupportedKeyExpression(expression) => {
+                write!(f, "The key expression '{}' is not supported", expression)
+            }
             ErrorKind::Message(ref msg) => write!(f, "{}", msg),
         }
     }
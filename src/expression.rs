@@ -0,0 +1,1084 @@
+//! Parsing and evaluation of xBase expressions
+//!
+//! dBase, FoxPro and Clipper use the same language for the key expressions of indexes,
+//! their `FOR` clauses and the filters of applications, for example
+//! `UPPER(LASTNAME)+DTOS(HIREDATE)`, `STR(QTY,5)` or `.NOT. DELETED() .AND. AMOUNT > 100`.
+//!
+//! An [Expression] evaluates to a character, numeric, date or logical value.
+//! It can use:
+//!
+//! - literals: `'text'`, `"text"`, `[text]`, numbers, `.T.` and `.F.`
+//! - field names, optionally prefixed by an alias (`CUSTOMER->NAME`)
+//! - arithmetic operators: `+`, `-`, `*`, `/`, `%`, `**` or `^`.
+//!   `+` and `-` also concatenate strings and add days to dates.
+//! - comparison operators: `=`, `==`, `<>`, `#`, `!=`, `<`, `<=`, `>`, `>=`
+//!   and `$` (contained in)
+//! - logical operators: `.AND.`, `.OR.`, `.NOT.` or `!`
+//! - the functions `UPPER`, `LOWER`, `TRIM`, `LTRIM`, `RTRIM`, `ALLTRIM`, `SUBSTR`, `LEFT`,
+//!   `RIGHT`, `STR`, `VAL`, `DTOS`, `CTOD`, `YEAR`, `MONTH`, `DAY`, `IIF`, `EMPTY`,
+//!   `DELETED` and `RECNO`, whose names can be abbreviated to their first four letters.
+//!
+//! As dBase does with `SET EXACT OFF`, `=` compares strings up to the length of the right one,
+//! so `NAME = 'SM'` is true for `SMITH`; `==` compares them exactly.
+//! `CTOD` expects american dates (`MM/DD/YY` or `MM/DD/YYYY`).
+//!
+//! # Example
+//!
+//! ```
+//! use dbase::expression::Expression;
+//! use dbase::{FieldValue, Record, RecordMeta};
+//! # fn main() -> Result<(), dbase::Error> {
+//! let expression = Expression::parse("UPPER(NAME) = 'LOVE' .AND. AMOUNT > 100")?;
+//!
+//! let mut record = Record::default();
+//! record.insert("NAME".to_string(), FieldValue::Character(Some("Lovelace".to_string())));
+//! record.insert("AMOUNT".to_string(), FieldValue::Numeric(Some(150.0)));
+//! let meta = RecordMeta { index: 0, is_deleted: false };
+//! assert!(expression.matches(&record, meta)?);
+//! # Ok(())
+//! # }
+//! ```
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::record::field::Date;
+use crate::{Error, ErrorKind, FieldInfo, FieldValue, Record, RecordMeta};
+
+/// A parsed xBase expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    root: Node,
+}
+
+impl Expression {
+    /// Parses the expression
+    pub fn parse(expression: &str) -> Result<Self, Error> {
+        let tokens = tokenize(expression).map_err(|kind| expression_error(kind, 0))?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let root = parser
+            .parse_expression()
+            .and_then(|root| match parser.tokens.get(parser.position) {
+                None => Ok(root),
+                Some(token) => Err(invalid(format!("unexpected {}", token))),
+            })
+            .map_err(|kind| expression_error(kind, 0))?;
+        Ok(Self { root })
+    }
+
+    /// Evaluates the expression on the record
+    ///
+    /// The result is a [FieldValue::Character], [FieldValue::Numeric],
+    /// [FieldValue::Date] or [FieldValue::Logical].
+    pub fn evaluate(&self, record: &Record, meta: RecordMeta) -> Result<FieldValue, Error> {
+        let context = Context { record, meta };
+        let value = context
+            .evaluate(&self.root)
+            .map_err(|kind| expression_error(kind, meta.index))?;
+        Ok(match value {
+            Value::Character(text) => FieldValue::Character(Some(text)),
+            Value::Numeric(number) => FieldValue::Numeric(Some(number)),
+            Value::Date(date) => FieldValue::Date(date),
+            Value::Logical(value) => FieldValue::Logical(Some(value)),
+        })
+    }
+
+    /// Returns whether the record matches the expression, which must be a logical one
+    pub fn matches(&self, record: &Record, meta: RecordMeta) -> Result<bool, Error> {
+        match self.evaluate(record, meta)? {
+            FieldValue::Logical(Some(value)) => Ok(value),
+            _ => Err(expression_error(
+                invalid("the expression is not a logical one"),
+                meta.index,
+            )),
+        }
+    }
+
+    /// Returns the names of the fields used by the expression, without duplicates
+    pub(crate) fn field_names(&self) -> Vec<&str> {
+        let mut names = vec![];
+        self.root.field_names(&mut names);
+        let mut unique_names: Vec<&str> = vec![];
+        for name in names {
+            if !unique_names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+                unique_names.push(name);
+            }
+        }
+        unique_names
+    }
+
+    /// Checks that the fields used by the expression are in `fields`
+    pub(crate) fn check_fields(&self, fields: &[FieldInfo]) -> Result<(), Error> {
+        let mut names = vec![];
+        self.root.field_names(&mut names);
+        match names.into_iter().find(|name| {
+            !fields
+                .iter()
+                .any(|field| field.name.eq_ignore_ascii_case(name))
+        }) {
+            Some(name) => Err(expression_error(ErrorKind::FieldNotFound(name.clone()), 0)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl FromStr for Expression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Character(String),
+    Numeric(f64),
+    /// `None` for blank dates
+    Date(Option<Date>),
+    Logical(bool),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Character(_) => "character",
+            Value::Numeric(_) => "numeric",
+            Value::Date(_) => "date",
+            Value::Logical(_) => "logical",
+        }
+    }
+
+    fn from_field(value: &FieldValue) -> Result<Self, ErrorKind> {
+        Ok(match value {
+            FieldValue::Character(text) | FieldValue::Varchar(text) => {
+                Value::Character(text.clone().unwrap_or_default())
+            }
+            FieldValue::Memo(text) => Value::Character(text.clone()),
+            FieldValue::Numeric(number)
+            | FieldValue::Currency(number)
+            | FieldValue::Double(number)
+            | FieldValue::DBase7Double(number) => Value::Numeric(number.unwrap_or_default()),
+            FieldValue::Float(number) => Value::Numeric(number.map(f64::from).unwrap_or_default()),
+            FieldValue::Integer(number)
+            | FieldValue::Long(number)
+            | FieldValue::Autoincrement(number) => {
+                Value::Numeric(number.map(f64::from).unwrap_or_default())
+            }
+            FieldValue::Date(date) => Value::Date(*date),
+            FieldValue::DateTime(date_time) | FieldValue::Timestamp(date_time) => {
+                Value::Date(date_time.as_ref().map(|date_time| date_time.date()))
+            }
+            FieldValue::Logical(value) => Value::Logical(value.unwrap_or_default()),
+            FieldValue::Varbinary(_)
+            | FieldValue::BinaryMemo(_)
+            | FieldValue::General(_)
+            | FieldValue::Picture(_)
+            | FieldValue::Blob(_) => {
+                return Err(invalid("binary fields cannot be used in expressions"))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Literal(Value),
+    Field(String),
+    Negate(Box<Node>),
+    Not(Box<Node>),
+    Binary(Operator, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+impl Node {
+    fn field_names<'a>(&'a self, names: &mut Vec<&'a String>) {
+        match self {
+            Node::Literal(_) => {}
+            Node::Field(name) => names.push(name),
+            Node::Negate(node) | Node::Not(node) => node.field_names(names),
+            Node::Binary(_, left, right) => {
+                left.field_names(names);
+                right.field_names(names);
+            }
+            Node::Call(_, arguments) => {
+                for argument in arguments {
+                    argument.field_names(names);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Power,
+    Equal,
+    ExactlyEqual,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Contained,
+    And,
+    Or,
+}
+
+impl Operator {
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        Some(match symbol {
+            "+" => Operator::Add,
+            "-" => Operator::Subtract,
+            "*" => Operator::Multiply,
+            "/" => Operator::Divide,
+            "%" => Operator::Modulo,
+            "**" | "^" => Operator::Power,
+            "=" => Operator::Equal,
+            "==" => Operator::ExactlyEqual,
+            "<>" | "#" | "!=" => Operator::NotEqual,
+            "<" => Operator::Less,
+            "<=" => Operator::LessOrEqual,
+            ">" => Operator::Greater,
+            ">=" => Operator::GreaterOrEqual,
+            "$" => Operator::Contained,
+            ".AND." => Operator::And,
+            ".OR." => Operator::Or,
+            _ => return None,
+        })
+    }
+
+    fn is_comparison(self) -> bool {
+        matches!(
+            self,
+            Operator::Equal
+                | Operator::ExactlyEqual
+                | Operator::NotEqual
+                | Operator::Less
+                | Operator::LessOrEqual
+                | Operator::Greater
+                | Operator::GreaterOrEqual
+                | Operator::Contained
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Function {
+    Upper,
+    Lower,
+    Trim,
+    Ltrim,
+    Rtrim,
+    Alltrim,
+    Substr,
+    Left,
+    Right,
+    Str,
+    Val,
+    Dtos,
+    Ctod,
+    Year,
+    Month,
+    Day,
+    Iif,
+    Empty,
+    Deleted,
+    Recno,
+}
+
+/// Name, minimum and maximum number of arguments of the functions
+const FUNCTIONS: [(&str, Function, usize, usize); 20] = [
+    ("UPPER", Function::Upper, 1, 1),
+    ("LOWER", Function::Lower, 1, 1),
+    ("TRIM", Function::Trim, 1, 1),
+    ("LTRIM", Function::Ltrim, 1, 1),
+    ("RTRIM", Function::Rtrim, 1, 1),
+    ("ALLTRIM", Function::Alltrim, 1, 1),
+    ("SUBSTR", Function::Substr, 2, 3),
+    ("LEFT", Function::Left, 2, 2),
+    ("RIGHT", Function::Right, 2, 2),
+    ("STR", Function::Str, 1, 3),
+    ("VAL", Function::Val, 1, 1),
+    ("DTOS", Function::Dtos, 1, 1),
+    ("CTOD", Function::Ctod, 1, 1),
+    ("YEAR", Function::Year, 1, 1),
+    ("MONTH", Function::Month, 1, 1),
+    ("DAY", Function::Day, 1, 1),
+    ("IIF", Function::Iif, 3, 3),
+    ("EMPTY", Function::Empty, 1, 1),
+    ("DELETED", Function::Deleted, 0, 0),
+    ("RECNO", Function::Recno, 0, 0),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    String(String),
+    Logical(bool),
+    Identifier(String),
+    Operator(&'static str),
+    LeftParenthesis,
+    RightParenthesis,
+    Comma,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(number) => write!(f, "'{}'", number),
+            Token::String(text) => write!(f, "'\"{}\"'", text),
+            Token::Logical(true) => write!(f, "'.T.'"),
+            Token::Logical(false) => write!(f, "'.F.'"),
+            Token::Identifier(name) => write!(f, "'{}'", name),
+            Token::Operator(symbol) => write!(f, "'{}'", symbol),
+            Token::LeftParenthesis => write!(f, "'('"),
+            Token::RightParenthesis => write!(f, "')'"),
+            Token::Comma => write!(f, "','"),
+        }
+    }
+}
+
+/// Symbols of the operators, the longest ones first
+const SYMBOLS: [&str; 17] = [
+    "**", "==", "<>", "!=", "<=", ">=", "+", "-", "*", "/", "%", "^", "=", "#", "<", ">", "$",
+];
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, ErrorKind> {
+    let chars = expression.chars().collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut position = 0;
+    let rest_starts_with = |position: usize, text: &str| {
+        text.chars()
+            .enumerate()
+            .all(|(i, c)| chars.get(position + i) == Some(&c))
+    };
+    while let Some(&c) = chars.get(position) {
+        let next = chars.get(position + 1).copied();
+        if c.is_whitespace() {
+            position += 1;
+        } else if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) {
+            let start = position;
+            while chars.get(position).is_some_and(char::is_ascii_digit) {
+                position += 1;
+            }
+            if chars.get(position) == Some(&'.')
+                && chars.get(position + 1).is_some_and(char::is_ascii_digit)
+            {
+                position += 1;
+                while chars.get(position).is_some_and(char::is_ascii_digit) {
+                    position += 1;
+                }
+            }
+            let number = chars[start..position].iter().collect::<String>();
+            tokens.push(Token::Number(
+                number
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid number '{}'", number)))?,
+            ));
+        } else if c == '.' {
+            // .AND., .OR., .NOT., .T., .F.
+            let end = chars[position + 1..]
+                .iter()
+                .position(|c| *c == '.')
+                .map(|end| position + 1 + end)
+                .ok_or_else(|| invalid("unexpected '.'"))?;
+            let word = chars[position + 1..end]
+                .iter()
+                .collect::<String>()
+                .to_ascii_uppercase();
+            tokens.push(match word.as_str() {
+                "AND" => Token::Operator(".AND."),
+                "OR" => Token::Operator(".OR."),
+                "NOT" => Token::Operator(".NOT."),
+                "T" | "Y" => Token::Logical(true),
+                "F" | "N" => Token::Logical(false),
+                _ => return Err(invalid(format!("unknown operator '.{}.'", word))),
+            });
+            position = end + 1;
+        } else if c == '\'' || c == '"' || c == '[' {
+            let closing = if c == '[' { ']' } else { c };
+            let end = chars[position + 1..]
+                .iter()
+                .position(|c| *c == closing)
+                .map(|end| position + 1 + end)
+                .ok_or_else(|| invalid("unterminated string"))?;
+            tokens.push(Token::String(chars[position + 1..end].iter().collect()));
+            position = end + 1;
+        } else if c.is_alphabetic() || c == '_' {
+            let name = loop {
+                let start = position;
+                while chars
+                    .get(position)
+                    .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+                {
+                    position += 1;
+                }
+                // The alias of the table is ignored
+                if rest_starts_with(position, "->") {
+                    position += 2;
+                } else {
+                    break chars[start..position].iter().collect::<String>();
+                }
+            };
+            if name.is_empty() {
+                return Err(invalid("expected a field name after '->'"));
+            }
+            tokens.push(Token::Identifier(name));
+        } else if c == '(' {
+            tokens.push(Token::LeftParenthesis);
+            position += 1;
+        } else if c == ')' {
+            tokens.push(Token::RightParenthesis);
+            position += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            position += 1;
+        } else if let Some(symbol) = SYMBOLS
+            .iter()
+            .find(|symbol| rest_starts_with(position, symbol))
+        {
+            tokens.push(Token::Operator(symbol));
+            position += symbol.len();
+        } else if c == '!' {
+            tokens.push(Token::Operator(".NOT."));
+            position += 1;
+        } else {
+            return Err(invalid(format!("unexpected character '{}'", c)));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser, from the operators with the lowest precedence to the highest
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    /// Consumes the next token if it is one of the operators
+    fn eat_operator(&mut self, symbols: &[&str]) -> Option<Operator> {
+        match self.peek() {
+            Some(Token::Operator(symbol)) if symbols.contains(symbol) => {
+                let operator = Operator::from_symbol(symbol);
+                self.position += 1;
+                operator
+            }
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ErrorKind> {
+        match self.peek() {
+            Some(token) if *token == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(token) => Err(invalid(format!("expected {}, found {}", expected, token))),
+            None => Err(invalid(format!("expected {}", expected))),
+        }
+    }
+
+    fn parse_expression(&mut self) -> Result<Node, ErrorKind> {
+        let mut node = self.parse_and()?;
+        while let Some(operator) = self.eat_operator(&[".OR."]) {
+            node = Node::Binary(operator, Box::new(node), Box::new(self.parse_and()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_and(&mut self) -> Result<Node, ErrorKind> {
+        let mut node = self.parse_not()?;
+        while let Some(operator) = self.eat_operator(&[".AND."]) {
+            node = Node::Binary(operator, Box::new(node), Box::new(self.parse_not()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_not(&mut self) -> Result<Node, ErrorKind> {
+        if self.peek() == Some(&Token::Operator(".NOT.")) {
+            self.position += 1;
+            return Ok(Node::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Node, ErrorKind> {
+        let node = self.parse_additive()?;
+        match self.peek() {
+            Some(Token::Operator(symbol))
+                if Operator::from_symbol(symbol).is_some_and(Operator::is_comparison) =>
+            {
+                let operator = Operator::from_symbol(symbol).unwrap();
+                self.position += 1;
+                let right = self.parse_additive()?;
+                Ok(Node::Binary(operator, Box::new(node), Box::new(right)))
+            }
+            _ => Ok(node),
+        }
+    }
+
+    fn parse_additive(&mut self) -> Result<Node, ErrorKind> {
+        let mut node = self.parse_multiplicative()?;
+        while let Some(operator) = self.eat_operator(&["+", "-"]) {
+            node = Node::Binary(
+                operator,
+                Box::new(node),
+                Box::new(self.parse_multiplicative()?),
+            );
+        }
+        Ok(node)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Node, ErrorKind> {
+        let mut node = self.parse_power()?;
+        while let Some(operator) = self.eat_operator(&["*", "/", "%"]) {
+            node = Node::Binary(operator, Box::new(node), Box::new(self.parse_power()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_power(&mut self) -> Result<Node, ErrorKind> {
+        let mut node = self.parse_unary()?;
+        while let Some(operator) = self.eat_operator(&["**", "^"]) {
+            node = Node::Binary(operator, Box::new(node), Box::new(self.parse_unary()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, ErrorKind> {
+        match self.eat_operator(&["-", "+"]) {
+            Some(Operator::Subtract) => Ok(Node::Negate(Box::new(self.parse_unary()?))),
+            Some(_) => self.parse_unary(),
+            None => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Node, ErrorKind> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| invalid("unexpected end of the expression"))?;
+        self.position += 1;
+        match token {
+            Token::Number(number) => Ok(Node::Literal(Value::Numeric(number))),
+            Token::String(text) => Ok(Node::Literal(Value::Character(text))),
+            Token::Logical(value) => Ok(Node::Literal(Value::Logical(value))),
+            Token::Identifier(name) if self.peek() == Some(&Token::LeftParenthesis) => {
+                self.position += 1;
+                self.parse_call(&name)
+            }
+            Token::Identifier(name) => Ok(Node::Field(name)),
+            Token::LeftParenthesis => {
+                let node = self.parse_expression()?;
+                self.expect(Token::RightParenthesis)?;
+                Ok(node)
+            }
+            token => Err(invalid(format!("unexpected {}", token))),
+        }
+    }
+
+    /// Parses the arguments of a function, the opening parenthesis being consumed
+    fn parse_call(&mut self, name: &str) -> Result<Node, ErrorKind> {
+        let upper_name = name.to_ascii_uppercase();
+        // Like dBase, accept names abbreviated to four letters
+        let (full_name, function, min_arguments, max_arguments) = FUNCTIONS
+            .iter()
+            .find(|(full_name, ..)| *full_name == upper_name)
+            .or_else(|| {
+                FUNCTIONS.iter().find(|(full_name, ..)| {
+                    upper_name.len() >= 4 && full_name.starts_with(&upper_name)
+                })
+            })
+            .copied()
+            .ok_or_else(|| invalid(format!("unknown function '{}'", name)))?;
+
+        let mut arguments = vec![];
+        if self.peek() != Some(&Token::RightParenthesis) {
+            arguments.push(self.parse_expression()?);
+            while self.peek() == Some(&Token::Comma) {
+                self.position += 1;
+                arguments.push(self.parse_expression()?);
+            }
+        }
+        self.expect(Token::RightParenthesis)?;
+        if arguments.len() < min_arguments || arguments.len() > max_arguments {
+            return Err(invalid(format!(
+                "wrong number of arguments for {}",
+                full_name
+            )));
+        }
+        Ok(Node::Call(function, arguments))
+    }
+}
+
+struct Context<'a> {
+    record: &'a Record,
+    meta: RecordMeta,
+}
+
+impl Context<'_> {
+    fn evaluate(&self, node: &Node) -> Result<Value, ErrorKind> {
+        match node {
+            Node::Literal(value) => Ok(value.clone()),
+            Node::Field(name) => self.field(name),
+            Node::Negate(node) => match self.evaluate(node)? {
+                Value::Numeric(number) => Ok(Value::Numeric(-number)),
+                value => Err(type_error("-", &value, None)),
+            },
+            Node::Not(node) => Ok(Value::Logical(!self.evaluate_logical(node, ".NOT.")?)),
+            Node::Binary(Operator::And, left, right) => Ok(Value::Logical(
+                self.evaluate_logical(left, ".AND.")? && self.evaluate_logical(right, ".AND.")?,
+            )),
+            Node::Binary(Operator::Or, left, right) => Ok(Value::Logical(
+                self.evaluate_logical(left, ".OR.")? || self.evaluate_logical(right, ".OR.")?,
+            )),
+            Node::Binary(operator, left, right) => {
+                apply(*operator, self.evaluate(left)?, self.evaluate(right)?)
+            }
+            Node::Call(Function::Iif, arguments) => {
+                if self.evaluate_logical(&arguments[0], "IIF")? {
+                    self.evaluate(&arguments[1])
+                } else {
+                    self.evaluate(&arguments[2])
+                }
+            }
+            Node::Call(function, arguments) => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.evaluate(argument))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.call(*function, arguments)
+            }
+        }
+    }
+
+    fn evaluate_logical(&self, node: &Node, operation: &str) -> Result<bool, ErrorKind> {
+        match self.evaluate(node)? {
+            Value::Logical(value) => Ok(value),
+            value => Err(type_error(operation, &value, None)),
+        }
+    }
+
+    fn field(&self, name: &str) -> Result<Value, ErrorKind> {
+        let value = self.record.get(name).or_else(|| {
+            self.record
+                .as_ref()
+                .iter()
+                .find(|(field_name, _)| field_name.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value)
+        });
+        match value {
+            Some(value) => Value::from_field(value),
+            None => Err(ErrorKind::FieldNotFound(name.to_string())),
+        }
+    }
+
+    fn call(&self, function: Function, arguments: Vec<Value>) -> Result<Value, ErrorKind> {
+        use Value::*;
+        let name = FUNCTIONS
+            .iter()
+            .find(|(_, f, ..)| *f == function)
+            .map_or("", |(name, ..)| name);
+        let mismatch = |arguments: &[Value]| match arguments.first() {
+            Some(first) => type_error(name, first, arguments.get(1)),
+            None => invalid(format!("wrong number of arguments for {}", name)),
+        };
+        let count = |number: f64| number.max(0.0) as usize;
+        Ok(match (function, arguments.as_slice()) {
+            (Function::Upper, [Character(text)]) => Character(text.to_uppercase()),
+            (Function::Lower, [Character(text)]) => Character(text.to_lowercase()),
+            (Function::Trim | Function::Rtrim, [Character(text)]) => {
+                Character(text.trim_end_matches(' ').to_string())
+            }
+            (Function::Ltrim, [Character(text)]) => {
+                Character(text.trim_start_matches(' ').to_string())
+            }
+            (Function::Alltrim, [Character(text)]) => Character(text.trim_matches(' ').to_string()),
+            (Function::Substr, [Character(text), Numeric(start)]) => {
+                Character(text.chars().skip(count(*start).max(1) - 1).collect())
+            }
+            (Function::Substr, [Character(text), Numeric(start), Numeric(length)]) => Character(
+                text.chars()
+                    .skip(count(*start).max(1) - 1)
+                    .take(count(*length))
+                    .collect(),
+            ),
+            (Function::Left, [Character(text), Numeric(length)]) => {
+                Character(text.chars().take(count(*length)).collect())
+            }
+            (Function::Right, [Character(text), Numeric(length)]) => {
+                let num_chars = text.chars().count();
+                Character(
+                    text.chars()
+                        .skip(num_chars.saturating_sub(count(*length)))
+                        .collect(),
+                )
+            }
+            (Function::Str, [Numeric(number), rest @ ..]) => {
+                let length = match rest.first() {
+                    Some(Numeric(length)) => count(*length),
+                    Some(_) => return Err(mismatch(&arguments)),
+                    None => 10,
+                };
+                let decimals = match rest.get(1) {
+                    Some(Numeric(decimals)) => count(*decimals),
+                    Some(_) => return Err(mismatch(&arguments)),
+                    None => 0,
+                };
+                Character(format_number(*number, length, decimals))
+            }
+            (Function::Val, [Character(text)]) => Numeric(parse_number(text)),
+            (Function::Dtos, [Date(date)]) => Character(match date {
+                Some(date) => date.to_string(),
+                None => " ".repeat(8),
+            }),
+            (Function::Ctod, [Character(text)]) => Date(parse_american_date(text)),
+            (Function::Year, [Date(date)]) => Numeric(date.map_or(0.0, |d| f64::from(d.year()))),
+            (Function::Month, [Date(date)]) => Numeric(date.map_or(0.0, |d| f64::from(d.month()))),
+            (Function::Day, [Date(date)]) => Numeric(date.map_or(0.0, |d| f64::from(d.day()))),
+            (Function::Empty, [value]) => Logical(match value {
+                Character(text) => text.trim().is_empty(),
+                Numeric(number) => *number == 0.0,
+                Date(date) => date.is_none(),
+                Logical(value) => !value,
+            }),
+            (Function::Deleted, []) => Logical(self.meta.is_deleted),
+            (Function::Recno, []) => Numeric((self.meta.index + 1) as f64),
+            _ => return Err(mismatch(&arguments)),
+        })
+    }
+}
+
+fn apply(operator: Operator, left: Value, right: Value) -> Result<Value, ErrorKind> {
+    use Value::*;
+    if operator.is_comparison() {
+        return compare(operator, &left, &right).map(Logical);
+    }
+    let symbol = match operator {
+        Operator::Add => "+",
+        Operator::Subtract => "-",
+        Operator::Multiply => "*",
+        Operator::Divide => "/",
+        Operator::Modulo => "%",
+        _ => "**",
+    };
+    Ok(match (operator, left, right) {
+        (Operator::Add, Numeric(a), Numeric(b)) => Numeric(a + b),
+        (Operator::Add, Character(a), Character(b)) => Character(a + &b),
+        (Operator::Add, Date(date), Numeric(days)) | (Operator::Add, Numeric(days), Date(date)) => {
+            Date(date.map(|date| add_days(date, days)))
+        }
+        (Operator::Subtract, Numeric(a), Numeric(b)) => Numeric(a - b),
+        // The trailing blanks of the left string are moved to the end of the result
+        (Operator::Subtract, Character(a), Character(b)) => {
+            let trimmed = a.trim_end_matches(' ');
+            let blanks = &a[trimmed.len()..];
+            Character(format!("{}{}{}", trimmed, b, blanks))
+        }
+        (Operator::Subtract, Date(a), Date(b)) => match (a, b) {
+            (Some(a), Some(b)) => Numeric(f64::from(
+                a.to_julian_day_number() - b.to_julian_day_number(),
+            )),
+            _ => Numeric(0.0),
+        },
+        (Operator::Subtract, Date(date), Numeric(days)) => {
+            Date(date.map(|date| add_days(date, -days)))
+        }
+        (Operator::Multiply, Numeric(a), Numeric(b)) => Numeric(a * b),
+        (Operator::Divide | Operator::Modulo, Numeric(_), Numeric(0.0)) => {
+            return Err(invalid("division by zero"))
+        }
+        (Operator::Divide, Numeric(a), Numeric(b)) => Numeric(a / b),
+        // Like dBase, the result has the sign of the divisor
+        (Operator::Modulo, Numeric(a), Numeric(b)) => Numeric(a - b * (a / b).floor()),
+        (Operator::Power, Numeric(a), Numeric(b)) => Numeric(a.powf(b)),
+        (_, left, right) => return Err(type_error(symbol, &left, Some(&right))),
+    })
+}
+
+fn compare(operator: Operator, left: &Value, right: &Value) -> Result<bool, ErrorKind> {
+    use Value::*;
+    let ordering = match (left, right) {
+        (Character(a), Character(b)) => match operator {
+            Operator::Contained => return Ok(b.contains(a.as_str())),
+            Operator::ExactlyEqual => return Ok(a == b),
+            // Only the beginning of the left string is compared
+            _ => a.chars().take(b.chars().count()).cmp(b.chars()),
+        },
+        (Numeric(a), Numeric(b)) if operator != Operator::Contained => {
+            a.partial_cmp(b).unwrap_or(Ordering::Equal)
+        }
+        (Date(a), Date(b)) if operator != Operator::Contained => {
+            let day_number = |date: &Option<crate::record::field::Date>| {
+                date.map_or(i32::MIN, |date| date.to_julian_day_number())
+            };
+            day_number(a).cmp(&day_number(b))
+        }
+        (Logical(a), Logical(b)) if operator != Operator::Contained => a.cmp(b),
+        _ => {
+            let symbol = match operator {
+                Operator::Contained => "$",
+                _ => "a comparison",
+            };
+            return Err(type_error(symbol, left, Some(right)));
+        }
+    };
+    Ok(match operator {
+        Operator::Equal | Operator::ExactlyEqual => ordering == Ordering::Equal,
+        Operator::NotEqual => ordering != Ordering::Equal,
+        Operator::Less => ordering == Ordering::Less,
+        Operator::LessOrEqual => ordering != Ordering::Greater,
+        Operator::Greater => ordering == Ordering::Greater,
+        _ => ordering != Ordering::Less,
+    })
+}
+
+fn add_days(date: Date, days: f64) -> Date {
+    Date::julian_day_number_to_gregorian_date(date.to_julian_day_number() + days as i32)
+}
+
+/// Formats the number as `STR()` does
+///
+/// Decimals are dropped when the number does not fit otherwise,
+/// and the result is filled with `*` when it still does not fit.
+fn format_number(number: f64, length: usize, decimals: usize) -> String {
+    (0..=decimals)
+        .rev()
+        .map(|decimals| format!("{:>length$.decimals$}", number))
+        .find(|text| text.len() <= length)
+        .unwrap_or_else(|| "*".repeat(length))
+}
+
+/// Parses the number at the start of the text, as `VAL()` does
+fn parse_number(text: &str) -> f64 {
+    let text = text.trim_start();
+    let mut end = 0;
+    let mut seen_point = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '+' | '-' if i == 0 => {}
+            '.' if !seen_point => seen_point = true,
+            c if c.is_ascii_digit() => {}
+            _ => break,
+        }
+        end = i + 1;
+    }
+    text[..end].parse().unwrap_or_default()
+}
+
+/// Parses a `MM/DD/YY` or `MM/DD/YYYY` date, two digits years being in the 1900s
+fn parse_american_date(text: &str) -> Option<Date> {
+    let parts = text
+        .trim()
+        .split(['/', '-', '.'])
+        .map(|part| part.trim().parse::<u32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let [month, day, year] = parts[..] else {
+        return None;
+    };
+    let year = if year < 100 { 1900 + year } else { year };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Rejects the days after the end of the month
+    let date = Date::new(day, month, year);
+    let round_trip = Date::julian_day_number_to_gregorian_date(date.to_julian_day_number());
+    (round_trip == date).then_some(date)
+}
+
+fn invalid<S: Into<String>>(reason: S) -> ErrorKind {
+    ErrorKind::InvalidExpression(reason.into())
+}
+
+fn type_error(operation: &str, left: &Value, right: Option<&Value>) -> ErrorKind {
+    match right {
+        Some(right) => invalid(format!(
+            "{} cannot be applied to {} and {} values",
+            operation,
+            left.type_name(),
+            right.type_name()
+        )),
+        None => invalid(format!(
+            "{} cannot be applied to a {} value",
+            operation,
+            left.type_name()
+        )),
+    }
+}
+
+fn expression_error(kind: ErrorKind, record_num: usize) -> Error {
+    Error {
+        record_num,
+        field: None,
+        kind,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record() -> Record {
+        let mut record = Record::default();
+        record.insert(
+            "LastName".to_string(),
+            FieldValue::Character(Some("Lovelace".to_string())),
+        );
+        record.insert("Qty".to_string(), FieldValue::Numeric(Some(12.0)));
+        record.insert("Price".to_string(), FieldValue::Float(Some(2.5)));
+        record.insert(
+            "Hired".to_string(),
+            FieldValue::Date(Some(Date::new(10, 12, 1843))),
+        );
+        record.insert("Active".to_string(), FieldValue::Logical(Some(true)));
+        record.insert("Notes".to_string(), FieldValue::Character(None));
+        record
+    }
+
+    fn evaluate(expression: &str) -> FieldValue {
+        let meta = RecordMeta {
+            index: 4,
+            is_deleted: true,
+        };
+        Expression::parse(expression)
+            .unwrap()
+            .evaluate(&record(), meta)
+            .unwrap()
+    }
+
+    fn text(value: &str) -> FieldValue {
+        FieldValue::Character(Some(value.to_string()))
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(evaluate("QTY * PRICE + 1"), FieldValue::Numeric(Some(31.0)));
+        assert_eq!(evaluate("-2 ** 2 - 10 / 4"), FieldValue::Numeric(Some(1.5)));
+        assert_eq!(evaluate("-7 % 3"), FieldValue::Numeric(Some(2.0)));
+        assert_eq!(evaluate("'Ada' + ' ' + lastname"), text("Ada Lovelace"));
+        assert_eq!(evaluate("'Ada  ' - 'L'"), text("AdaL  "));
+        assert_eq!(
+            evaluate("HIRED + 30"),
+            FieldValue::Date(Some(Date::new(9, 1, 1844)))
+        );
+        assert_eq!(
+            evaluate("CTOD('01/09/1844') - HIRED"),
+            FieldValue::Numeric(Some(30.0))
+        );
+        assert_eq!(
+            evaluate("customer->QTY > 10 .AND. .NOT. (Active .OR. .F.)"),
+            FieldValue::Logical(Some(false))
+        );
+        assert_eq!(evaluate("!ACTIVE"), FieldValue::Logical(Some(false)));
+        assert_eq!(
+            evaluate("'lace' $ LASTNAME .and. 'lace' # LASTNAME"),
+            FieldValue::Logical(Some(true))
+        );
+    }
+
+    #[test]
+    fn string_comparisons() {
+        let is = |expression: &str| evaluate(expression) == FieldValue::Logical(Some(true));
+        assert!(is("LASTNAME = 'Love'"));
+        assert!(is("LASTNAME = ''"));
+        assert!(!is("LASTNAME == 'Love'"));
+        assert!(is("LASTNAME == 'Lovelace'"));
+        assert!(!is("'Love' = LASTNAME"));
+        assert!(is("'Love' < LASTNAME"));
+        assert!(is("LASTNAME >= 'Lovelace'"));
+        assert!(is("LASTNAME <> 'Babbage'"));
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(
+            evaluate("UPPER(LASTNAME)+DTOS(HIRED)"),
+            text("LOVELACE18431210")
+        );
+        assert_eq!(evaluate("LOWER(LEFT(LASTNAME, 4))"), text("love"));
+        assert_eq!(evaluate("RIGHT(LASTNAME, 4)"), text("lace"));
+        assert_eq!(evaluate("SUBSTR(LASTNAME, 3, 2)"), text("ve"));
+        assert_eq!(evaluate("SUBS(LASTNAME, 5)"), text("lace"));
+        assert_eq!(
+            evaluate("TRIM('  a  ') + LTRIM('  b  ') + '|'"),
+            text("  ab  |")
+        );
+        assert_eq!(evaluate("RTRIM(' a ') + ALLTRIM(' b ')"), text(" ab"));
+        assert_eq!(evaluate("STR(QTY,5)"), text("   12"));
+        assert_eq!(evaluate("STR(PRICE, 6, 2)"), text("  2.50"));
+        assert_eq!(evaluate("STR(-QTY)"), text("       -12"));
+        assert_eq!(evaluate("STR(1234.25, 5, 2)"), text(" 1234"));
+        assert_eq!(evaluate("STR(123456, 4)"), text("****"));
+        assert_eq!(
+            evaluate("VAL(' -12.5kg')"),
+            FieldValue::Numeric(Some(-12.5))
+        );
+        assert_eq!(evaluate("VAL('kg')"), FieldValue::Numeric(Some(0.0)));
+        assert_eq!(
+            evaluate("CTOD('2/29/96')"),
+            FieldValue::Date(Some(Date::new(29, 2, 1996)))
+        );
+        assert_eq!(evaluate("CTOD('02/30/1996')"), FieldValue::Date(None));
+        assert_eq!(evaluate("DTOS(CTOD(''))"), text("        "));
+        assert_eq!(
+            evaluate("YEAR(HIRED) * 100 + MONTH(HIRED)"),
+            FieldValue::Numeric(Some(184312.0))
+        );
+        assert_eq!(evaluate("IIF(QTY > 10, 'many', 1 / 0)"), text("many"));
+        assert_eq!(
+            evaluate("EMPTY(NOTES) .AND. .NOT. EMPTY(QTY)"),
+            FieldValue::Logical(Some(true))
+        );
+        assert_eq!(
+            evaluate("DELETED() .AND. RECNO() = 5"),
+            FieldValue::Logical(Some(true))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        for expression in [
+            "QTY >",
+            "(QTY",
+            "UPPER(QTY, 2)",
+            "FOO(QTY)",
+            "'unterminated",
+            "QTY .XOR. QTY",
+            "QTY QTY",
+        ] {
+            assert!(
+                matches!(
+                    Expression::parse(expression).unwrap_err().kind(),
+                    ErrorKind::InvalidExpression(_)
+                ),
+                "{}",
+                expression
+            );
+        }
+        let meta = RecordMeta {
+            index: 3,
+            is_deleted: false,
+        };
+        for expression in ["QTY + 'a'", "UPPER(QTY)", "QTY / 0", "MISSING = 1", "QTY"] {
+            let error = Expression::parse(expression)
+                .unwrap()
+                .matches(&record(), meta)
+                .unwrap_err();
+            assert_eq!(error.record_num(), 3, "{}", expression);
+        }
+    }
+}
@@ -7,7 +7,12 @@ use crate::record::field::{Date, MemoFileType};
 
 // Used this as source: https://blog.codetitans.pl/post/dbf-and-language-code-page/
 // also https://github.com/ethanfurman/dbf/blob/4f8ff35bec18ca167981ba741bfe353f5f362f99/dbf/__init__.py#L8299
-#[derive(Copy, Clone, Debug)]
+/// The language driver of a table, which gives the code page of its strings
+///
+/// Language drivers that use the code page of another one (the Dutch and French
+/// OEM drivers use CP437 for example) are kept as [CodePageMark::Other],
+/// so that writing a table does not change its language driver.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum CodePageMark {
     Undefined,
     // OEM United States
@@ -16,7 +21,8 @@ pub enum CodePageMark {
     CP850,
     // ANSI Latin 1; Western European (Windows)
     CP1252,
-    // StandardMacIntosh, // 10000
+    // Mac OS Roman
+    StandardMacIntosh, // 10000
     // OEM Latin 2; Central European (DOS)
     CP852, // 852
     // OEM Russian; Cyrillic (DOS)
@@ -25,10 +31,16 @@ pub enum CodePageMark {
     CP865,
     // OEM Icelandic; Icelandic (DOS)
     CP861,
+    // Kamenicky; Czech (DOS)
     CP895,
+    // Mazovia; Polish (DOS)
     CP620,
+    // OEM Greek (DOS)
     CP737,
+    // OEM Turkish; Turkish (DOS)
     CP857,
+    // OEM French Canadian; French Canadian (DOS)
+    CP863,
     CP950,
     CP949,
     CP936,
@@ -39,9 +51,12 @@ pub enum CodePageMark {
     CP1255,
     // ANSI Arabic; Arabic (Windows)
     CP1256, // 1256
-    // RussianMacIntosh, // 10007
-    // MacIntoshEE,      // 10029
-    // GreekMacIntosh,   // 10006
+    // Mac OS Cyrillic
+    RussianMacIntosh, // 10007
+    // Mac OS Central European
+    MacIntoshEE, // 10029
+    // Mac OS Greek
+    GreekMacIntosh, // 10006
     // ANSI Central European; Central European (Windows)
     CP1250,
     // ANSI Cyrillic; Cyrillic (Windows)
@@ -50,54 +65,268 @@ pub enum CodePageMark {
     CP1254,
     // ANSI Greek; Greek (Windows)
     CP1253,
+    // ANSI Baltic; Baltic (Windows)
+    CP1257,
     Utf8,
-    Invalid,
+    /// Any other language driver, see [CodePageMark::code_page]
+    Other(u8),
 }
 
 impl CodePageMark {
+    /// Returns the number of the code page of the language driver,
+    /// `None` when it is undefined or unknown
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// // The French OEM language driver
+    /// assert_eq!(dbase::CodePageMark::from(0x0D).code_page(), Some(437));
+    /// ```
+    pub fn code_page(self) -> Option<u16> {
+        Some(match self {
+            CodePageMark::Undefined => return None,
+            CodePageMark::CP437 => 437,
+            CodePageMark::CP850 => 850,
+            CodePageMark::CP1252 => 1252,
+            CodePageMark::StandardMacIntosh => 10000,
+            CodePageMark::CP852 => 852,
+            CodePageMark::CP866 => 866,
+            CodePageMark::CP865 => 865,
+            CodePageMark::CP861 => 861,
+            CodePageMark::CP895 => 895,
+            CodePageMark::CP620 => 620,
+            CodePageMark::CP737 => 737,
+            CodePageMark::CP857 => 857,
+            CodePageMark::CP863 => 863,
+            CodePageMark::CP950 => 950,
+            CodePageMark::CP949 => 949,
+            CodePageMark::CP936 => 936,
+            CodePageMark::CP932 => 932,
+            CodePageMark::CP874 => 874,
+            CodePageMark::CP1255 => 1255,
+            CodePageMark::CP1256 => 1256,
+            CodePageMark::RussianMacIntosh => 10007,
+            CodePageMark::MacIntoshEE => 10029,
+            CodePageMark::GreekMacIntosh => 10006,
+            CodePageMark::CP1250 => 1250,
+            CodePageMark::CP1251 => 1251,
+            CodePageMark::CP1254 => 1254,
+            CodePageMark::CP1253 => 1253,
+            CodePageMark::CP1257 => 1257,
+            CodePageMark::Utf8 => 65001,
+            CodePageMark::Other(code) => match code {
+                // Danish, Norwegian OEM
+                0x08 | 0x17 => 865,
+                // Dutch, Finnish, French, German, Italian, Swedish, Spanish, English OEM
+                0x09 | 0x0B | 0x0D | 0x0F | 0x11 | 0x15 | 0x18 | 0x19 | 0x1B => 437,
+                // Dutch, French, German, Italian, Spanish, Swedish, English, Portuguese OEM*
+                0x0A | 0x0E | 0x10 | 0x12 | 0x14 | 0x16 | 0x1A | 0x1D | 0x25 | 0x37 => 850,
+                // Japanese Shift-JIS
+                0x13 => 932,
+                // French Canadian OEM
+                0x1C => 863,
+                // Czech, Hungarian, Polish, Romanian, Slovenian OEM
+                0x1F | 0x22 | 0x23 | 0x40 | 0x87 => 852,
+                // Portuguese OEM
+                0x24 => 860,
+                // Russian OEM
+                0x26 => 866,
+                // Chinese GBK (PRC)
+                0x4D => 936,
+                // Korean
+                0x4E => 949,
+                // Chinese Big5 (Taiwan)
+                0x4F => 950,
+                // Thai
+                0x50 => 874,
+                // Current, Western European and Spanish ANSI
+                0x57..=0x59 => 1252,
+                // Greek OEM
+                0x86 => 737,
+                // Turkish OEM
+                0x88 => 857,
+                _ => return None,
+            },
+        })
+    }
+
+    /// Returns the language driver of the code page,
+    /// the one dBase and FoxPro use when there are several
+    pub(crate) fn from_code_page(code_page: u16) -> Option<Self> {
+        Some(match code_page {
+            437 => CodePageMark::CP437,
+            850 => CodePageMark::CP850,
+            1252 => CodePageMark::CP1252,
+            10000 => CodePageMark::StandardMacIntosh,
+            852 => CodePageMark::CP852,
+            866 => CodePageMark::CP866,
+            865 => CodePageMark::CP865,
+            861 => CodePageMark::CP861,
+            895 => CodePageMark::CP895,
+            620 => CodePageMark::CP620,
+            737 => CodePageMark::CP737,
+            857 => CodePageMark::CP857,
+            863 => CodePageMark::CP863,
+            860 => CodePageMark::Other(0x24),
+            950 => CodePageMark::CP950,
+            949 => CodePageMark::CP949,
+            936 => CodePageMark::CP936,
+            932 => CodePageMark::CP932,
+            874 => CodePageMark::CP874,
+            1255 => CodePageMark::CP1255,
+            1256 => CodePageMark::CP1256,
+            10007 => CodePageMark::RussianMacIntosh,
+            10029 => CodePageMark::MacIntoshEE,
+            10006 => CodePageMark::GreekMacIntosh,
+            1250 => CodePageMark::CP1250,
+            1251 => CodePageMark::CP1251,
+            1254 => CodePageMark::CP1254,
+            1253 => CodePageMark::CP1253,
+            1257 => CodePageMark::CP1257,
+            65001 => CodePageMark::Utf8,
+            _ => return None,
+        })
+    }
+
     pub(crate) fn to_encoding(self) -> Option<DynEncoding> {
+        #[cfg(feature = "encoding_rs")]
+        {
+            use crate::encoding::{CP932, CP936, CP949, CP950};
+            match self.code_page() {
+                Some(932) => return Some(DynEncoding::new_send(CP932)),
+                Some(936) => return Some(DynEncoding::new_send(CP936)),
+                Some(949) => return Some(DynEncoding::new_send(CP949)),
+                Some(950) => return Some(DynEncoding::new_send(CP950)),
+                _ => {}
+            }
+        }
         #[cfg(feature = "yore")]
         {
-            use crate::encoding::{LossyCodePage, Unicode};
+            use crate::encoding::{
+                Kamenicky, LossyCodePage, MacCentralEuropean, MacCyrillic, MacGreek, MacRoman,
+                Mazovia, Unicode,
+            };
             use yore::code_pages;
-            Some(match self {
-                CodePageMark::CP437 => DynEncoding::new(code_pages::CP437),
-                CodePageMark::CP850 => DynEncoding::new(code_pages::CP850),
-                CodePageMark::CP1252 => DynEncoding::new(code_pages::CP1252),
-                CodePageMark::CP852 => DynEncoding::new(code_pages::CP852),
-                CodePageMark::CP866 => DynEncoding::new(code_pages::CP866),
-                CodePageMark::CP865 => DynEncoding::new(code_pages::CP865),
-                CodePageMark::CP861 => DynEncoding::new(code_pages::CP861),
-                CodePageMark::CP874 => DynEncoding::new(code_pages::CP874),
-                CodePageMark::CP1255 => DynEncoding::new(code_pages::CP1255),
-                CodePageMark::CP1256 => DynEncoding::new(code_pages::CP1256),
-                CodePageMark::CP1250 => DynEncoding::new(code_pages::CP1250),
-                CodePageMark::CP1251 => DynEncoding::new(code_pages::CP1251),
-                CodePageMark::CP1254 => DynEncoding::new(code_pages::CP1254),
-                CodePageMark::CP1253 => DynEncoding::new(code_pages::CP1253),
-                CodePageMark::Utf8 => DynEncoding::new(Unicode),
-                CodePageMark::Undefined | CodePageMark::Invalid => {
-                    DynEncoding::new(LossyCodePage(code_pages::CP1252))
-                }
-                CodePageMark::CP895
-                | CodePageMark::CP620
-                | CodePageMark::CP737
-                | CodePageMark::CP857
-                | CodePageMark::CP950
-                | CodePageMark::CP949
-                | CodePageMark::CP936
-                | CodePageMark::CP932 => {
-                    return None;
+            Some(match self.code_page() {
+                Some(437) => DynEncoding::new_send(code_pages::CP437),
+                Some(850) => DynEncoding::new_send(code_pages::CP850),
+                Some(1252) => DynEncoding::new_send(code_pages::CP1252),
+                Some(10000) => DynEncoding::new_send(MacRoman),
+                Some(852) => DynEncoding::new_send(code_pages::CP852),
+                Some(866) => DynEncoding::new_send(code_pages::CP866),
+                Some(865) => DynEncoding::new_send(code_pages::CP865),
+                Some(861) => DynEncoding::new_send(code_pages::CP861),
+                Some(895) => DynEncoding::new_send(Kamenicky),
+                Some(620) => DynEncoding::new_send(Mazovia),
+                Some(737) => DynEncoding::new_send(code_pages::CP737),
+                Some(857) => DynEncoding::new_send(code_pages::CP857),
+                Some(860) => DynEncoding::new_send(code_pages::CP860),
+                Some(863) => DynEncoding::new_send(code_pages::CP863),
+                Some(874) => DynEncoding::new_send(code_pages::CP874),
+                Some(1255) => DynEncoding::new_send(code_pages::CP1255),
+                Some(1256) => DynEncoding::new_send(code_pages::CP1256),
+                Some(10007) => DynEncoding::new_send(MacCyrillic),
+                Some(10029) => DynEncoding::new_send(MacCentralEuropean),
+                Some(10006) => DynEncoding::new_send(MacGreek),
+                Some(1250) => DynEncoding::new_send(code_pages::CP1250),
+                Some(1251) => DynEncoding::new_send(code_pages::CP1251),
+                Some(1254) => DynEncoding::new_send(code_pages::CP1254),
+                Some(1253) => DynEncoding::new_send(code_pages::CP1253),
+                Some(1257) => DynEncoding::new_send(code_pages::CP1257),
+                Some(65001) => DynEncoding::new_send(Unicode),
+                None if self == CodePageMark::Undefined => {
+                    DynEncoding::new_send(LossyCodePage(code_pages::CP1252))
                 }
+                // The CJK code pages without the encoding_rs feature,
+                // and the unknown language drivers
+                _ => return None,
             })
         }
         #[cfg(not(feature = "yore"))]
         {
-            Some(DynEncoding::new(crate::encoding::UnicodeLossy))
+            Some(DynEncoding::new_send(crate::encoding::UnicodeLossy))
+        }
+    }
+
+    /// Returns whether the byte is the first of a two bytes character,
+    /// for the double-byte code pages of Japanese, Chinese and Korean
+    pub(crate) fn is_lead_byte(self, byte: u8) -> bool {
+        match self.code_page() {
+            Some(932) => matches!(byte, 0x81..=0x9F | 0xE0..=0xFC),
+            Some(936 | 949 | 950) => matches!(byte, 0x81..=0xFE),
+            _ => false,
+        }
+    }
+
+    /// Returns the length to which the encoded text must be truncated
+    /// to fit in `max_length` bytes without splitting a character
+    pub(crate) fn truncated_length(self, bytes: &[u8], max_length: usize) -> usize {
+        let mut length = 0;
+        while length < bytes.len() {
+            let char_length = if self.is_lead_byte(bytes[length]) {
+                2
+            } else {
+                1
+            };
+            if length + char_length > max_length {
+                break;
+            }
+            length += char_length;
+        }
+        length.min(max_length)
+    }
+
+    /// Returns the code page named in a `.cpg` file, as written by GIS software
+    /// ("UTF-8", "1252", "CP850", "ISO-8859-1"...)
+    pub(crate) fn from_cpg_name(name: &str) -> Option<Self> {
+        let name = name
+            .trim_start_matches('\u{feff}')
+            .trim()
+            .to_ascii_uppercase();
+        match name.as_str() {
+            "UTF-8" | "UTF8" | "65001" => return Some(CodePageMark::Utf8),
+            "LATIN1" => return Some(CodePageMark::CP1252),
+            _ => {}
+        }
+        // Latin 1, Latin 5 and Thai are read as their supersets, like web browsers do,
+        // the other parts of ISO-8859 have no language driver
+        match iso_8859_part(&name) {
+            Some(1) => return Some(CodePageMark::CP1252),
+            Some(9) => return Some(CodePageMark::CP1254),
+            Some(11) => return Some(CodePageMark::CP874),
+            Some(_) => return None,
+            None => {}
+        }
+        let number = ["WINDOWS-", "CP", "ANSI ", "OEM ", "IBM", "MS"]
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix))
+            .unwrap_or(&name)
+            .trim();
+        Self::from_code_page(number.parse().ok()?)
+    }
+
+    /// Returns the name of the code page to write in a `.cpg` file
+    pub(crate) fn cpg_name(self) -> Option<String> {
+        match self {
+            CodePageMark::Utf8 => Some("UTF-8".to_string()),
+            _ => self.code_page().map(|code_page| code_page.to_string()),
         }
     }
 }
 
+/// Returns the number of the part of ISO-8859 named in a `.cpg` file
+/// ("ISO-8859-15", "ISO 8859-2", "88595"...)
+pub(crate) fn iso_8859_part(name: &str) -> Option<u8> {
+    let name = name
+        .trim_start_matches('\u{feff}')
+        .trim()
+        .to_ascii_uppercase();
+    let part = ["ISO-8859-", "ISO8859-", "ISO_8859-", "ISO 8859-", "8859"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))?;
+    part.parse().ok()
+}
+
 impl From<u8> for CodePageMark {
     fn from(code: u8) -> Self {
         match code {
@@ -105,7 +334,7 @@ impl From<u8> for CodePageMark {
             0x01 => Self::CP437,
             0x02 => Self::CP850,
             0x03 => Self::CP1252,
-            // 0x04 => Self::StandardMacIntosh,
+            0x04 => Self::StandardMacIntosh,
             0x64 => Self::CP852,
             0x65 => Self::CP866,
             0x66 => Self::CP865,
@@ -114,6 +343,7 @@ impl From<u8> for CodePageMark {
             0x69 => Self::CP620,
             0x6A => Self::CP737,
             0x6B => Self::CP857,
+            0x6C => Self::CP863,
             0x78 => Self::CP950,
             0x79 => Self::CP949,
             0x7A => Self::CP936,
@@ -121,14 +351,16 @@ impl From<u8> for CodePageMark {
             0x7C => Self::CP874,
             0x7D => Self::CP1255,
             0x7E => Self::CP1256,
-            // 0x96 => Self::RussianMacIntosh,
-            // 0x98 => Self::GreekMacIntosh,
+            0x96 => Self::RussianMacIntosh,
+            0x97 => Self::MacIntoshEE,
+            0x98 => Self::GreekMacIntosh,
             0xC8 => Self::CP1250,
             0xC9 => Self::CP1251,
             0xCA => Self::CP1254,
             0xCB => Self::CP1253,
-            0xf0 => Self::Utf8,
-            _ => Self::Invalid,
+            0xCC => Self::CP1257,
+            0xF0 => Self::Utf8,
+            _ => Self::Other(code),
         }
     }
 }
@@ -140,7 +372,7 @@ impl From<CodePageMark> for u8 {
             CodePageMark::CP437 => 0x01,
             CodePageMark::CP850 => 0x02,
             CodePageMark::CP1252 => 0x03,
-            // CodePageMark::StandardMacIntosh => 0x04,
+            CodePageMark::StandardMacIntosh => 0x04,
             CodePageMark::CP852 => 0x64,
             CodePageMark::CP866 => 0x65,
             CodePageMark::CP865 => 0x66,
@@ -149,6 +381,7 @@ impl From<CodePageMark> for u8 {
             CodePageMark::CP620 => 0x69,
             CodePageMark::CP737 => 0x6A,
             CodePageMark::CP857 => 0x6B,
+            CodePageMark::CP863 => 0x6C,
             CodePageMark::CP950 => 0x78,
             CodePageMark::CP949 => 0x79,
             CodePageMark::CP936 => 0x7A,
@@ -156,14 +389,16 @@ impl From<CodePageMark> for u8 {
             CodePageMark::CP874 => 0x7C,
             CodePageMark::CP1255 => 0x7D,
             CodePageMark::CP1256 => 0x7E,
-            // CodePageMark::RussianMacIntosh => 0x96,
-            // CodePageMark::GreekMacIntosh => 0x98,
+            CodePageMark::RussianMacIntosh => 0x96,
+            CodePageMark::MacIntoshEE => 0x97,
+            CodePageMark::GreekMacIntosh => 0x98,
             CodePageMark::CP1250 => 0xC8,
             CodePageMark::CP1251 => 0xC9,
             CodePageMark::CP1254 => 0xCA,
             CodePageMark::CP1253 => 0xCB,
-            CodePageMark::Utf8 => 0xf0,
-            _ => 0,
+            CodePageMark::CP1257 => 0xCC,
+            CodePageMark::Utf8 => 0xF0,
+            CodePageMark::Other(code) => code,
         }
     }
 }
@@ -176,6 +411,7 @@ pub enum Version {
     VisualFoxPro,
     DBase4 { supports_memo: bool },
     FoxPro2 { supports_memo: bool },
+    DBase7 { supports_memo: bool },
     Unknown(u8),
 }
 
@@ -202,13 +438,40 @@ impl Version {
             Version::FoxPro2 {
                 supports_memo: true,
             } => Some(MemoFileType::FoxBaseMemo),
+            Version::DBase7 {
+                supports_memo: true,
+            } => Some(MemoFileType::DbaseMemo4),
             _ => None,
         }
     }
 
+    /// Returns the version to use so that a memo file
+    /// can be associated to the table
+    pub(crate) fn with_memo_support(self) -> Self {
+        match self {
+            Version::DBase3 { .. } | Version::Unknown(_) => Version::DBase3 {
+                supports_memo: true,
+            },
+            Version::DBase4 { .. } => Version::DBase4 {
+                supports_memo: true,
+            },
+            Version::FoxPro2 { .. } => Version::FoxPro2 {
+                supports_memo: true,
+            },
+            Version::DBase7 { .. } => Version::DBase7 {
+                supports_memo: true,
+            },
+            Version::FoxBase | Version::VisualFoxPro => self,
+        }
+    }
+
     pub(crate) fn is_visual_fox_pro(self) -> bool {
         matches!(self, Version::VisualFoxPro)
     }
+
+    pub(crate) fn is_dbase7(self) -> bool {
+        matches!(self, Version::DBase7 { .. })
+    }
 }
 
 impl From<Version> for u8 {
@@ -234,6 +497,12 @@ impl From<Version> for u8 {
             Version::FoxPro2 {
                 supports_memo: true,
             } => 0xf5,
+            Version::DBase7 {
+                supports_memo: false,
+            } => 0x04,
+            Version::DBase7 {
+                supports_memo: true,
+            } => 0x8c,
             Version::Unknown(v) => v,
         }
     }
@@ -251,7 +520,7 @@ impl From<u8> for Version {
             },
             // Each version has different feature (varchar / autoincrement)
             // but we don't support that for now
-            0x30 | 0x31 | 0x32 => Version::VisualFoxPro,
+            0x30..=0x32 => Version::VisualFoxPro,
             // Same here these different version num means that some features are different
             0x8b | 0xcb => Version::DBase4 {
                 supports_memo: true,
@@ -265,6 +534,12 @@ impl From<u8> for Version {
             0xf5 => Version::FoxPro2 {
                 supports_memo: true,
             },
+            0x04 => Version::DBase7 {
+                supports_memo: false,
+            },
+            0x8c => Version::DBase7 {
+                supports_memo: true,
+            },
             b => Version::Unknown(b),
         }
     }
@@ -278,6 +553,14 @@ impl TableFlags {
         (self.0 & 0x01) == 1
     }
 
+    pub(crate) fn set_has_structural_cdx(&mut self, value: bool) {
+        if value {
+            self.0 |= 0x01;
+        } else {
+            self.0 &= !0x01;
+        }
+    }
+
     pub fn has_memo_field(&self) -> bool {
         (self.0 & 0x02) == 2
     }
@@ -300,10 +583,14 @@ pub struct Header {
     pub encryption_flag: u8,
     pub table_flags: TableFlags,
     pub code_page_mark: CodePageMark,
+    /// Name of the language driver (dBase 7 only)
+    pub language_driver_name: [u8; 32],
 }
 
 impl Header {
     pub(crate) const SIZE: usize = 32;
+    /// dBase 7 adds the language driver name and reserved bytes
+    pub(crate) const DBASE7_SIZE: usize = 68;
 
     pub(crate) fn new(num_records: u32, offset: u16, size_of_records: u16) -> Self {
         let current_date = Self::get_today_date();
@@ -319,6 +606,16 @@ impl Header {
             encryption_flag: 0,
             table_flags: TableFlags(0),
             code_page_mark: CodePageMark::Undefined,
+            language_driver_name: [0u8; 32],
+        }
+    }
+
+    /// Returns the number of bytes the header takes in the file
+    pub(crate) fn size(&self) -> usize {
+        if self.file_type.is_dbase7() {
+            Self::DBASE7_SIZE
+        } else {
+            Self::SIZE
         }
     }
 
@@ -332,6 +629,11 @@ impl Header {
         }
     }
 
+    /// Returns the position in the file of the start of the record at `index`
+    pub(crate) fn record_position(&self, index: usize) -> u64 {
+        u64::from(self.offset_to_first_record) + (index as u64 * u64::from(self.size_of_record))
+    }
+
     pub(crate) fn update_date(&mut self) {
         self.last_update = Self::get_today_date();
     }
@@ -353,7 +655,7 @@ impl Header {
 
         let _reserved = source.read_u16::<LittleEndian>()?;
 
-        let is_transaction_incomplete = (source.read_u8()? != 0) as bool;
+        let is_transaction_incomplete = source.read_u8()? != 0;
         let encryption_flag = source.read_u8()?;
 
         let mut _reserved = [0u8; 12];
@@ -366,6 +668,13 @@ impl Header {
         let _reserved = source.read_u8()?;
         let _reserved = source.read_u8()?;
 
+        let mut language_driver_name = [0u8; 32];
+        if file_type.is_dbase7() {
+            source.read_exact(&mut language_driver_name)?;
+            let mut _reserved = [0u8; 4];
+            source.read_exact(&mut _reserved)?;
+        }
+
         Ok(Self {
             file_type,
             last_update,
@@ -376,6 +685,7 @@ impl Header {
             size_of_record,
             table_flags,
             code_page_mark,
+            language_driver_name,
         })
     }
 
@@ -403,6 +713,11 @@ impl Header {
         // Reserved
         dest.write_u8(0)?;
         dest.write_u8(0)?;
+
+        if self.file_type.is_dbase7() {
+            dest.write_all(&self.language_driver_name)?;
+            dest.write_all(&[0u8; 4])?;
+        }
         Ok(())
     }
 }
@@ -410,7 +725,7 @@ impl Header {
 #[cfg(test)]
 mod test {
     use std::fs::File;
-    use std::io::{Cursor, Seek, SeekFrom};
+    use std::io::{Cursor, Seek};
 
     use super::*;
 
@@ -418,7 +733,7 @@ mod test {
     fn pos_after_reading_header() {
         let mut file = File::open("tests/data/line.dbf").unwrap();
         let _hdr = Header::read_from(&mut file).unwrap();
-        let pos_after_reading = file.seek(SeekFrom::Current(0)).unwrap();
+        let pos_after_reading = file.stream_position().unwrap();
         assert_eq!(pos_after_reading, Header::SIZE as u64);
     }
 
@@ -429,7 +744,7 @@ mod test {
 
         let mut out = Cursor::new(Vec::<u8>::with_capacity(Header::SIZE));
         hdr.write_to(&mut out).unwrap();
-        let pos_after_writing = out.seek(SeekFrom::Current(0)).unwrap();
+        let pos_after_writing = out.stream_position().unwrap();
         assert_eq!(pos_after_writing, Header::SIZE as u64);
     }
 
@@ -451,4 +766,57 @@ mod test {
 
         assert_eq!(hdr_bytes_written, hdr_bytes);
     }
+
+    #[test]
+    fn parse_cpg_names() {
+        let mark = |name| CodePageMark::from_cpg_name(name).map(u8::from);
+        assert_eq!(mark("UTF-8"), Some(0xF0));
+        assert_eq!(mark("\u{feff}utf8\r\n"), Some(0xF0));
+        assert_eq!(mark("1252"), Some(0x03));
+        assert_eq!(mark("ISO-8859-1"), Some(0x03));
+        assert_eq!(mark("88591"), Some(0x03));
+        assert_eq!(mark("ISO 8859-9"), Some(0xCA));
+        assert_eq!(mark("ISO-8859-15"), None);
+        assert_eq!(iso_8859_part("iso_8859-15\n"), Some(15));
+        assert_eq!(iso_8859_part("88592"), Some(2));
+        assert_eq!(iso_8859_part("8859-2"), None);
+        assert_eq!(mark("CP936"), Some(0x7A));
+        assert_eq!(mark("windows-1251"), Some(0xC9));
+        assert_eq!(mark("OEM 850"), Some(0x02));
+        assert_eq!(mark("EBCDIC"), None);
+        assert_eq!(mark("860"), Some(0x24));
+        assert_eq!(CodePageMark::CP1252.cpg_name().as_deref(), Some("1252"));
+        assert_eq!(CodePageMark::Other(0x0D).cpg_name().as_deref(), Some("437"));
+        assert_eq!(CodePageMark::Undefined.cpg_name(), None);
+    }
+
+    #[test]
+    fn language_drivers_round_trip() {
+        for code in 0..=u8::MAX {
+            assert_eq!(u8::from(CodePageMark::from(code)), code);
+        }
+        assert_eq!(CodePageMark::from(0x04), CodePageMark::StandardMacIntosh);
+        assert_eq!(CodePageMark::from(0x13).code_page(), Some(932));
+        assert_eq!(CodePageMark::from(0xFF).code_page(), None);
+        for code in 0..=u8::MAX {
+            let mark = CodePageMark::from(code);
+            if let Some(code_page) = mark.code_page() {
+                let canonical = CodePageMark::from_code_page(code_page).unwrap();
+                assert_eq!(canonical.code_page(), Some(code_page));
+            }
+        }
+    }
+
+    #[cfg(all(feature = "yore", feature = "encoding_rs"))]
+    #[test]
+    fn language_drivers_encodings() {
+        for code in 0..=u8::MAX {
+            let mark = CodePageMark::from(code);
+            let supported = mark.to_encoding().is_some();
+            match mark {
+                CodePageMark::Other(_) => assert_eq!(supported, mark.code_page().is_some()),
+                _ => assert!(supported, "{:?}", mark),
+            }
+        }
+    }
 }
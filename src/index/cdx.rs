@@ -0,0 +1,1512 @@
+//! FoxPro compound index files (.cdx)
+//!
+//! A .cdx file holds several indexes, called tags, each one being a B-tree of 512 bytes nodes
+//! described by a 1024 bytes header. The file starts with the header of a tree
+//! whose keys are the names of the tags, pointing to their headers.
+//!
+//! The leaf nodes store the keys compressed: each key only stores the bytes that are not
+//! shared with the previous key, nor trailing blanks.
+//!
+//! When the `has_structural_cdx` table flag of the header is set,
+//! the table has a structural .cdx with the same name.
+//! [TableWriterBuilder::add_index_tag](crate::TableWriterBuilder::add_index_tag) writes one
+//! along with the table, and [reindex] rebuilds it after the table was modified.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # fn main() -> Result<(), dbase::Error> {
+//! use dbase::index::cdx::CdxIndex;
+//!
+//! let mut reader = dbase::Reader::from_path("customers.dbf")?;
+//! let mut index = CdxIndex::from_path("customers.cdx")?.with_table_fields(reader.fields());
+//!
+//! for tag in index.tags() {
+//!     println!("{}: {}", tag.name(), tag.key_expression());
+//! }
+//!
+//! if let Some(record_index) = index.seek("CUSTNO", 1234)? {
+//!     reader.seek(record_index)?;
+//!     let customer = reader.iter_records().next().unwrap()?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::cmp::Ordering;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use super::key::{pad_character_values, KeyExpression};
+use super::{
+    compare_character_key, index_error, invalid_index, is_above_lower_bound, is_below_upper_bound,
+    key_type_of_expression, IndexKey, KeyType,
+};
+use crate::expression::Expression;
+use crate::header::Header;
+use crate::record::field::{dbase7_double_bytes, dbase7_long_bytes};
+use crate::{DeletionPolicy, Encoding, Error, ErrorKind, FieldInfo, Reader, Record, RecordMeta};
+
+const NODE_SIZE: usize = 512;
+const HEADER_SIZE: usize = 1024;
+/// Marks the absence of sibling node
+const NO_NODE: u32 = u32::MAX;
+/// Guards against cycles in corrupted files
+const MAX_DEPTH: usize = 64;
+
+/// Positions in the header of a tag
+mod header {
+    pub(super) const ROOT: usize = 0;
+    pub(super) const FREE_LIST: usize = 4;
+    pub(super) const KEY_LENGTH: usize = 12;
+    pub(super) const OPTIONS: usize = 14;
+    pub(super) const SIGNATURE: usize = 15;
+    pub(super) const DESCENDING: usize = 502;
+    pub(super) const FOR_EXPRESSION_POSITION: usize = 504;
+    pub(super) const FOR_EXPRESSION_LENGTH: usize = 506;
+    pub(super) const KEY_EXPRESSION_POSITION: usize = 508;
+    pub(super) const KEY_EXPRESSION_LENGTH: usize = 510;
+    pub(super) const EXPRESSION_POOL: usize = 512;
+}
+
+/// Bits of the index options
+mod options {
+    pub(super) const UNIQUE: u8 = 0x01;
+    pub(super) const FOR_CLAUSE: u8 = 0x08;
+    pub(super) const COMPACT: u8 = 0x20;
+    pub(super) const COMPOUND: u8 = 0x40;
+    pub(super) const STRUCTURE: u8 = 0x80;
+}
+
+/// Bits of the node attributes
+mod attributes {
+    pub(super) const ROOT: u16 = 0x01;
+    pub(super) const LEAF: u16 = 0x02;
+}
+
+/// Positions in the header of the nodes
+mod node {
+    pub(super) const ATTRIBUTES: usize = 0;
+    pub(super) const NUM_KEYS: usize = 2;
+    pub(super) const LEFT_SIBLING: usize = 4;
+    pub(super) const RIGHT_SIBLING: usize = 8;
+    pub(super) const INTERIOR_KEYS: usize = 12;
+    pub(super) const FREE_SPACE: usize = 12;
+    pub(super) const RECORD_NUMBER_MASK: usize = 14;
+    pub(super) const DUPLICATE_COUNT_MASK: usize = 18;
+    pub(super) const TRAILING_COUNT_MASK: usize = 19;
+    pub(super) const RECORD_NUMBER_BITS: usize = 20;
+    pub(super) const DUPLICATE_COUNT_BITS: usize = 21;
+    pub(super) const TRAILING_COUNT_BITS: usize = 22;
+    pub(super) const ENTRY_SIZE: usize = 23;
+    pub(super) const LEAF_KEYS: usize = 24;
+}
+
+/// A tag of a compound index
+#[derive(Debug, Clone)]
+pub struct CdxTag {
+    name: String,
+    key_expression: String,
+    filter_expression: Option<String>,
+    key_length: usize,
+    key_type: KeyType,
+    unique: bool,
+    descending: bool,
+    root: u32,
+}
+
+impl CdxTag {
+    /// Returns the name of the tag
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the expression evaluated to compute the keys (e.g. `UPPER(NAME)`)
+    pub fn key_expression(&self) -> &str {
+        &self.key_expression
+    }
+
+    /// Returns the FOR expression of the tag, only the records for which
+    /// it is true are in the index
+    pub fn filter_expression(&self) -> Option<&str> {
+        self.filter_expression.as_deref()
+    }
+
+    /// Returns the length in bytes of the keys
+    pub fn key_length(&self) -> usize {
+        self.key_length
+    }
+
+    /// Returns the type of the keys
+    ///
+    /// The type is not stored in the file, it is [KeyType::Character] unless
+    /// [CdxIndex::with_table_fields] found out the key expression is a numeric or date field.
+    pub fn key_type(&self) -> KeyType {
+        self.key_type
+    }
+
+    /// Returns whether the index only holds the first record of each key
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
+    /// Returns whether the keys are sorted from the highest to the lowest
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+
+    /// Byte that leaf nodes strip from the end of the keys
+    fn trailing_byte(&self) -> u8 {
+        match self.key_type {
+            KeyType::Character => b' ',
+            KeyType::Numeric | KeyType::Date => 0,
+        }
+    }
+
+    /// Returns the key as stored in the index,
+    /// character keys are padded to the key length when `exact` is true
+    fn encode_key(&self, key: &IndexKey, exact: bool) -> Result<Vec<u8>, Error> {
+        key.check_compatible_with(self.key_type)
+            .map_err(index_error)?;
+        match (key, key.as_number()) {
+            (IndexKey::Character(bytes), _) => {
+                let mut bytes = bytes.clone();
+                if exact && bytes.len() < self.key_length {
+                    bytes.resize(self.key_length, b' ');
+                }
+                Ok(bytes)
+            }
+            (_, Some(number)) if self.key_length == 4 => {
+                Ok(dbase7_long_bytes(number as i32).to_vec())
+            }
+            (_, Some(number)) => Ok(dbase7_double_bytes(number).to_vec()),
+            (_, None) => Err(index_error(ErrorKind::IncompatibleType)),
+        }
+    }
+
+    /// Compares a stored key with an encoded one, in the order of the tree
+    fn compare(&self, stored: &[u8], searched: &[u8]) -> Ordering {
+        let ordering = match self.key_type {
+            KeyType::Character => compare_character_key(stored, searched),
+            KeyType::Numeric | KeyType::Date => stored.cmp(searched),
+        };
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+/// A FoxPro compound index file
+pub struct CdxIndex<T: Read + Seek> {
+    source: T,
+    tags: Vec<CdxTag>,
+    /// Number of nodes the file can hold, to detect cycles
+    num_nodes: usize,
+}
+
+impl<T: Read + Seek> CdxIndex<T> {
+    /// Reads the tags of the index from the source
+    pub fn new(mut source: T) -> Result<Self, Error> {
+        let file_size = source
+            .seek(SeekFrom::End(0))
+            .map_err(|error| Error::io_error(error, 0))?;
+        let mut index = Self {
+            source,
+            tags: vec![],
+            num_nodes: file_size as usize / NODE_SIZE,
+        };
+        let directory = index.read_tag_header(0, String::new())?;
+        let mut tag_offsets = vec![];
+        let mut cursor = index.find_first_leaf(&directory, Bound::Unbounded)?;
+        while let Some(entry) = index.next_leaf_entry(&directory, &mut cursor)? {
+            let name = String::from_utf8_lossy(&entry.key)
+                .trim_end_matches(['\0', ' '])
+                .to_string();
+            tag_offsets.push((entry.record_number, name));
+        }
+        // Keep the order in which the tags were created
+        tag_offsets.sort_by_key(|(offset, _)| *offset);
+        for (offset, name) in tag_offsets {
+            let tag = index.read_tag_header(offset, name)?;
+            index.tags.push(tag);
+        }
+        Ok(index)
+    }
+
+    /// Returns the tags of the index, in the order they were created
+    pub fn tags(&self) -> &[CdxTag] {
+        &self.tags
+    }
+
+    /// Returns the tag with the given name (case insensitive)
+    pub fn tag(&self, name: &str) -> Option<&CdxTag> {
+        self.tags
+            .iter()
+            .find(|tag| tag.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Uses the fields of the indexed table to find the type of the keys of the tags
+    ///
+    /// The type is known when the key expression is the name of a field.
+    pub fn with_table_fields(mut self, fields: &[FieldInfo]) -> Self {
+        for tag in &mut self.tags {
+            if let Some(key_type) = key_type_of_expression(&tag.key_expression, fields) {
+                tag.key_type = key_type;
+            }
+        }
+        self
+    }
+
+    /// Returns the index of the first record (in the order of the tag)
+    /// whose key starts with the given one
+    ///
+    /// The returned index can be given to [Reader::seek](crate::Reader::seek).
+    pub fn seek<K: Into<IndexKey>>(&mut self, tag: &str, key: K) -> Result<Option<usize>, Error> {
+        let key = key.into();
+        self.range(tag, key.clone()..=key)?.next().transpose()
+    }
+
+    /// Returns the index of the first record (in the order of the tag)
+    /// whose key is exactly the given one
+    pub fn seek_exact<K: Into<IndexKey>>(
+        &mut self,
+        tag: &str,
+        key: K,
+    ) -> Result<Option<usize>, Error> {
+        let tag = self.find_tag(tag)?;
+        let key = tag.encode_key(&key.into(), true)?;
+        self.iter_encoded(tag, Bound::Included(key.clone()), Bound::Included(key))?
+            .next()
+            .transpose()
+    }
+
+    /// Returns an iterator over the indices of the records whose keys are in the range,
+    /// in the order of the tag
+    ///
+    /// As for [seek](Self::seek), a character key matches the keys that start with it.
+    pub fn range<K, R>(&mut self, tag: &str, range: R) -> Result<CdxIter<'_, T>, Error>
+    where
+        K: Into<IndexKey> + Clone,
+        R: RangeBounds<K>,
+    {
+        let tag = self.find_tag(tag)?;
+        let encode = |bound: Bound<&K>| -> Result<Bound<Vec<u8>>, Error> {
+            Ok(match bound {
+                Bound::Included(key) => {
+                    Bound::Included(tag.encode_key(&key.clone().into(), false)?)
+                }
+                Bound::Excluded(key) => {
+                    Bound::Excluded(tag.encode_key(&key.clone().into(), false)?)
+                }
+                Bound::Unbounded => Bound::Unbounded,
+            })
+        };
+        let start = encode(range.start_bound())?;
+        let end = encode(range.end_bound())?;
+        // The tree of descending tags goes from the highest to the lowest key
+        if tag.descending {
+            self.iter_encoded(tag, end, start)
+        } else {
+            self.iter_encoded(tag, start, end)
+        }
+    }
+
+    /// Returns an iterator over the indices of all the records, in the order of the tag
+    pub fn iter(&mut self, tag: &str) -> Result<CdxIter<'_, T>, Error> {
+        let tag = self.find_tag(tag)?;
+        self.iter_encoded(tag, Bound::Unbounded, Bound::Unbounded)
+    }
+
+    fn find_tag(&self, name: &str) -> Result<CdxTag, Error> {
+        self.tag(name)
+            .cloned()
+            .ok_or_else(|| index_error(ErrorKind::TagNotFound(name.to_string())))
+    }
+
+    /// `start` and `end` are in the order of the tree
+    fn iter_encoded(
+        &mut self,
+        tag: CdxTag,
+        start: Bound<Vec<u8>>,
+        end: Bound<Vec<u8>>,
+    ) -> Result<CdxIter<'_, T>, Error> {
+        let cursor = self.find_first_leaf(&tag, start.as_ref())?;
+        Ok(CdxIter {
+            index: self,
+            tag,
+            cursor,
+            end,
+        })
+    }
+
+    fn read_tag_header(&mut self, offset: u32, name: String) -> Result<CdxTag, Error> {
+        let mut data = [0u8; HEADER_SIZE];
+        self.source
+            .seek(SeekFrom::Start(u64::from(offset)))
+            .and_then(|_| self.source.read_exact(&mut data))
+            .map_err(|error| Error::io_error(error, 0))?;
+
+        let key_length = LittleEndian::read_u16(&data[header::KEY_LENGTH..]) as usize;
+        if key_length == 0 || key_length + 8 > NODE_SIZE - node::INTERIOR_KEYS {
+            return Err(invalid_index("unexpected key length"));
+        }
+        let tag_options = data[header::OPTIONS];
+        let expression_at = |position: usize, length: usize| {
+            let pool = &data[header::EXPRESSION_POOL..];
+            let start = LittleEndian::read_u16(&data[position..]) as usize;
+            let length = LittleEndian::read_u16(&data[length..]) as usize;
+            let bytes = pool
+                .get(start..(start + length).min(pool.len()))
+                .unwrap_or(&[]);
+            let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+        };
+        let key_expression = expression_at(
+            header::KEY_EXPRESSION_POSITION,
+            header::KEY_EXPRESSION_LENGTH,
+        );
+        let filter_expression = Some(expression_at(
+            header::FOR_EXPRESSION_POSITION,
+            header::FOR_EXPRESSION_LENGTH,
+        ))
+        .filter(|expression| tag_options & options::FOR_CLAUSE != 0 && !expression.is_empty());
+
+        Ok(CdxTag {
+            name,
+            key_expression,
+            filter_expression,
+            key_length,
+            key_type: KeyType::Character,
+            unique: tag_options & options::UNIQUE != 0,
+            descending: LittleEndian::read_u16(&data[header::DESCENDING..]) != 0,
+            root: LittleEndian::read_u32(&data[header::ROOT..]),
+        })
+    }
+
+    /// Goes down the tree to the leaf holding the first key above the lower bound
+    fn find_first_leaf(
+        &mut self,
+        tag: &CdxTag,
+        start: Bound<&Vec<u8>>,
+    ) -> Result<Option<LeafCursor>, Error> {
+        let mut offset = tag.root;
+        for _ in 0..MAX_DEPTH {
+            let node = self.read_node(tag, offset)?;
+            let first_above = node
+                .entries
+                .iter()
+                .position(|entry| is_above_lower_bound(start, |key| tag.compare(&entry.key, key)));
+            if node.is_leaf {
+                let position = first_above.unwrap_or(node.entries.len());
+                return Ok(Some(LeafCursor {
+                    node,
+                    position,
+                    num_visited: 1,
+                }));
+            }
+            // Interior keys are the highest key of their child
+            let child = first_above
+                .and_then(|position| node.entries.get(position))
+                .or(node.entries.last());
+            match child {
+                Some(entry) => offset = entry.child,
+                None => return Ok(None),
+            }
+        }
+        Err(invalid_index("the tree is too deep"))
+    }
+
+    fn next_leaf_entry(
+        &mut self,
+        tag: &CdxTag,
+        cursor: &mut Option<LeafCursor>,
+    ) -> Result<Option<Entry>, Error> {
+        loop {
+            let Some(current) = cursor else {
+                return Ok(None);
+            };
+            if let Some(entry) = current.node.entries.get(current.position) {
+                current.position += 1;
+                return Ok(Some(entry.clone()));
+            }
+            let right_sibling = current.node.right_sibling;
+            if right_sibling == NO_NODE || right_sibling == 0 {
+                *cursor = None;
+                return Ok(None);
+            }
+            if current.num_visited > self.num_nodes {
+                return Err(invalid_index("the leaf nodes form a cycle"));
+            }
+            let node = self.read_node(tag, right_sibling)?;
+            if !node.is_leaf {
+                return Err(invalid_index("the sibling of a leaf is not a leaf"));
+            }
+            current.node = node;
+            current.position = 0;
+            current.num_visited += 1;
+        }
+    }
+
+    fn read_node(&mut self, tag: &CdxTag, offset: u32) -> Result<Node, Error> {
+        if (offset as usize) < HEADER_SIZE {
+            return Err(invalid_index("a node points to the file header"));
+        }
+        let mut data = [0u8; NODE_SIZE];
+        self.source
+            .seek(SeekFrom::Start(u64::from(offset)))
+            .and_then(|_| self.source.read_exact(&mut data))
+            .map_err(|error| Error::io_error(error, 0))?;
+
+        let node_attributes = LittleEndian::read_u16(&data[node::ATTRIBUTES..]);
+        let num_keys = LittleEndian::read_u16(&data[node::NUM_KEYS..]) as usize;
+        let right_sibling = LittleEndian::read_u32(&data[node::RIGHT_SIBLING..]);
+        let is_leaf = node_attributes & attributes::LEAF != 0;
+        let entries = if is_leaf {
+            decode_leaf_entries(&data, num_keys, tag.key_length, tag.trailing_byte())?
+        } else {
+            decode_interior_entries(&data, num_keys, tag.key_length)?
+        };
+        Ok(Node {
+            is_leaf,
+            right_sibling,
+            entries,
+        })
+    }
+}
+
+impl CdxIndex<BufReader<File>> {
+    /// Opens the index file at the given path
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path).map_err(|error| Error::io_error(error, 0))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+fn decode_interior_entries(
+    data: &[u8; NODE_SIZE],
+    num_keys: usize,
+    key_length: usize,
+) -> Result<Vec<Entry>, Error> {
+    let entry_size = key_length + 8;
+    if node::INTERIOR_KEYS + num_keys * entry_size > NODE_SIZE {
+        return Err(invalid_index("a node holds too many keys"));
+    }
+    Ok(data[node::INTERIOR_KEYS..]
+        .chunks_exact(entry_size)
+        .take(num_keys)
+        .map(|entry| Entry {
+            key: entry[..key_length].to_vec(),
+            record_number: BigEndian::read_u32(&entry[key_length..]),
+            child: BigEndian::read_u32(&entry[key_length + 4..]),
+        })
+        .collect())
+}
+
+/// Leaf nodes store, after their header, the record number, the number of bytes shared with
+/// the previous key and the number of trailing bytes of each key, packed in a few bytes.
+/// The remaining bytes of the keys are stored from the end of the node.
+fn decode_leaf_entries(
+    data: &[u8; NODE_SIZE],
+    num_keys: usize,
+    key_length: usize,
+    trailing_byte: u8,
+) -> Result<Vec<Entry>, Error> {
+    let record_number_mask = u64::from(LittleEndian::read_u32(&data[node::RECORD_NUMBER_MASK..]));
+    let duplicate_count_mask = u64::from(data[node::DUPLICATE_COUNT_MASK]);
+    let trailing_count_mask = u64::from(data[node::TRAILING_COUNT_MASK]);
+    let record_number_bits = u32::from(data[node::RECORD_NUMBER_BITS]);
+    let duplicate_count_bits = u32::from(data[node::DUPLICATE_COUNT_BITS]);
+    let entry_size = data[node::ENTRY_SIZE] as usize;
+    let end_of_entries = node::LEAF_KEYS + num_keys * entry_size;
+    if (num_keys > 0 && !(1..=8).contains(&entry_size))
+        || end_of_entries > NODE_SIZE
+        || record_number_bits + duplicate_count_bits >= 64
+    {
+        return Err(invalid_index("unexpected leaf node layout"));
+    }
+
+    let mut entries: Vec<Entry> = Vec::with_capacity(num_keys);
+    let mut end_of_key = NODE_SIZE;
+    for packed in data[node::LEAF_KEYS..end_of_entries].chunks_exact(entry_size.max(1)) {
+        let packed = LittleEndian::read_uint(packed, packed.len());
+        let record_number = (packed & record_number_mask) as u32;
+        let duplicate_count = ((packed >> record_number_bits) & duplicate_count_mask) as usize;
+        let trailing_count = ((packed >> (record_number_bits + duplicate_count_bits))
+            & trailing_count_mask) as usize;
+
+        let previous_key = entries.last().map_or(&[][..], |entry| entry.key.as_slice());
+        let num_new_bytes = key_length
+            .checked_sub(duplicate_count + trailing_count)
+            .filter(|_| duplicate_count <= previous_key.len())
+            .ok_or_else(|| invalid_index("unexpected leaf key compression"))?;
+        let start_of_key = end_of_key
+            .checked_sub(num_new_bytes)
+            .filter(|start| *start >= end_of_entries)
+            .ok_or_else(|| invalid_index("leaf keys overlap"))?;
+
+        let mut key = Vec::with_capacity(key_length);
+        key.extend_from_slice(&previous_key[..duplicate_count]);
+        key.extend_from_slice(&data[start_of_key..end_of_key]);
+        key.resize(key_length, trailing_byte);
+        end_of_key = start_of_key;
+        entries.push(Entry {
+            key,
+            record_number,
+            child: 0,
+        });
+    }
+    Ok(entries)
+}
+
+/// Iterator over the indices of the records, in the order of a [CdxTag]
+///
+/// The indices can be given to [Reader::seek](crate::Reader::seek).
+pub struct CdxIter<'a, T: Read + Seek> {
+    index: &'a mut CdxIndex<T>,
+    tag: CdxTag,
+    cursor: Option<LeafCursor>,
+    /// Upper bound, in the order of the tree
+    end: Bound<Vec<u8>>,
+}
+
+impl<T: Read + Seek> Iterator for CdxIter<'_, T> {
+    type Item = Result<usize, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = match self.index.next_leaf_entry(&self.tag, &mut self.cursor) {
+            Ok(entry) => entry?,
+            Err(error) => {
+                self.cursor = None;
+                return Some(Err(error));
+            }
+        };
+        let tag = &self.tag;
+        if !is_below_upper_bound(self.end.as_ref(), |key| tag.compare(&entry.key, key)) {
+            self.cursor = None;
+            return None;
+        }
+        match entry.record_number.checked_sub(1) {
+            Some(record_index) => Some(Ok(record_index as usize)),
+            None => {
+                self.cursor = None;
+                Some(Err(invalid_index("a key points to record 0")))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    key: Vec<u8>,
+    /// Number of the record, starting at 1 (in the tags directory, offset of the tag header)
+    record_number: u32,
+    /// Offset of the node of the keys lower or equal to this one, 0 in leaf nodes
+    child: u32,
+}
+
+struct Node {
+    is_leaf: bool,
+    right_sibling: u32,
+    entries: Vec<Entry>,
+}
+
+/// Position in the leaf nodes
+struct LeafCursor {
+    node: Node,
+    position: usize,
+    num_visited: usize,
+}
+
+/// The longest key FoxPro supports
+pub(crate) const MAX_KEY_LENGTH: usize = 240;
+/// Length of the keys of the tags directory
+const TAG_NAME_LENGTH: usize = 10;
+
+/// A tag of a compound index written by the crate
+#[derive(Debug, Clone)]
+pub(crate) struct TagDefinition {
+    pub(crate) name: String,
+    pub(crate) expression: KeyExpression,
+    pub(crate) filter: Option<TagFilter>,
+    pub(crate) unique: bool,
+    pub(crate) descending: bool,
+}
+
+/// The FOR expression of a tag, only the records for which it is true are indexed
+#[derive(Debug, Clone)]
+pub(crate) struct TagFilter {
+    pub(crate) text: String,
+    pub(crate) expression: Expression,
+}
+
+impl TagDefinition {
+    fn trailing_byte(&self) -> u8 {
+        if self.expression.is_character() {
+            b' '
+        } else {
+            0
+        }
+    }
+}
+
+/// Collects the keys of the records written to a table,
+/// and writes the compound index once all of them are known
+pub(crate) struct CdxWriter<W: Write> {
+    dst: W,
+    tags: Vec<TagDefinition>,
+    /// The keys of each tag, with the number of their record (starting at 1)
+    keys: Vec<Vec<(Vec<u8>, u32)>>,
+}
+
+impl<W: Write> CdxWriter<W> {
+    pub(crate) fn new(dst: W, tags: Vec<TagDefinition>) -> Self {
+        let keys = vec![vec![]; tags.len()];
+        Self { dst, tags, keys }
+    }
+
+    /// Returns whether a key or FOR expression uses the field
+    pub(crate) fn uses_field(&self, name: &str) -> bool {
+        self.tags
+            .iter()
+            .flat_map(|tag| {
+                let filter_names = tag
+                    .filter
+                    .iter()
+                    .flat_map(|filter| filter.expression.field_names());
+                tag.expression.field_names().into_iter().chain(filter_names)
+            })
+            .any(|field_name| field_name.eq_ignore_ascii_case(name))
+    }
+
+    /// Computes the keys of the record, whose values must contain the fields used by the tags,
+    /// the character ones being padded with [pad_character_values]
+    pub(crate) fn add_record<E: Encoding>(
+        &mut self,
+        record: &Record,
+        meta: RecordMeta,
+        encoding: &E,
+    ) -> Result<(), ErrorKind> {
+        let record_number = meta.index as u32 + 1;
+        for (tag, keys) in self.tags.iter().zip(self.keys.iter_mut()) {
+            if let Some(filter) = &tag.filter {
+                if !filter
+                    .expression
+                    .matches(record, meta)
+                    .map_err(|error| error.kind)?
+                {
+                    continue;
+                }
+            }
+            keys.push((
+                tag.expression.compute(record, meta, encoding)?,
+                record_number,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sorts the keys and writes the index
+    pub(crate) fn close(&mut self) -> std::io::Result<()> {
+        // The directory header comes first, the tags follow it
+        let mut data = vec![0u8; HEADER_SIZE];
+        let mut directory = Vec::with_capacity(self.tags.len());
+        for (tag, mut keys) in self
+            .tags
+            .iter()
+            .zip(self.keys.iter_mut().map(std::mem::take))
+        {
+            // Sorting is stable, equal keys stay in the order of the records
+            if tag.descending {
+                keys.sort_by(|(a, _), (b, _)| b.cmp(a));
+            } else {
+                keys.sort_by(|(a, _), (b, _)| a.cmp(b));
+            }
+            if tag.unique {
+                keys.dedup_by(|(key, _), (previous, _)| key == previous);
+            }
+            let header_offset = data.len();
+            data.resize(header_offset + HEADER_SIZE, 0);
+            let key_length = tag.expression.key_length();
+            let root = write_tree(&mut data, &keys, key_length, tag.trailing_byte());
+            let tag_options = if tag.unique { options::UNIQUE } else { 0 };
+            data[header_offset..header_offset + HEADER_SIZE].copy_from_slice(&encode_tag_header(
+                root,
+                key_length,
+                &tag.expression.to_string(),
+                tag.filter.as_ref().map(|filter| filter.text.as_str()),
+                tag_options,
+                tag.descending,
+            ));
+
+            let mut name = tag.name.to_ascii_uppercase().into_bytes();
+            name.resize(TAG_NAME_LENGTH, b' ');
+            directory.push((name, header_offset as u32));
+        }
+        directory.sort();
+        let root = write_tree(&mut data, &directory, TAG_NAME_LENGTH, b' ');
+        data[..HEADER_SIZE].copy_from_slice(&encode_tag_header(
+            root,
+            TAG_NAME_LENGTH,
+            "",
+            None,
+            options::STRUCTURE,
+            false,
+        ));
+        self.dst.write_all(&data)?;
+        self.dst.flush()
+    }
+}
+
+/// Rebuilds the structural compound index (.cdx) of the table at `path`
+///
+/// The tags of the existing index are kept, and their keys are computed again
+/// from the records of the table. The structural index flag of the table is set.
+///
+/// The key and FOR expressions are evaluated with [Expression], tags using functions
+/// it does not provide, or whose keys would be empty for a blank record (`TRIM(NAME)`),
+/// give an [UnsupportedKeyExpression](ErrorKind::UnsupportedKeyExpression) error.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> Result<(), dbase::Error> {
+/// let mut table = dbase::Table::open("customers.dbf")?;
+/// table.update_field(0, "NAME", &String::from("Lovelace"))?;
+/// drop(table);
+///
+/// dbase::index::cdx::reindex("customers.dbf")?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn reindex<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+    let path = path.as_ref();
+    let index_path = path.with_extension("cdx");
+    let mut reader = Reader::from_path(path)?;
+    let fields = reader.fields().to_vec();
+    let tags = CdxIndex::from_path(&index_path)?
+        .tags()
+        .iter()
+        .map(|tag| {
+            let filter = match tag.filter_expression() {
+                Some(text) => {
+                    let expression = Expression::parse(text)?;
+                    expression.check_fields(&fields)?;
+                    Some(TagFilter {
+                        text: text.to_string(),
+                        expression,
+                    })
+                }
+                None => None,
+            };
+            Ok(TagDefinition {
+                name: tag.name.clone(),
+                expression: KeyExpression::parse(&tag.key_expression, &fields)
+                    .map_err(index_error)?,
+                filter,
+                unique: tag.unique,
+                descending: tag.descending,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    // Deleted records are indexed as well, unless a FOR expression excludes them,
+    // the record numbers are their position in the table
+    reader.set_deletion_policy(DeletionPolicy::Include);
+    let encoding = reader.encoding.clone();
+    let mut records = Vec::with_capacity(reader.header().num_records as usize);
+    for record in reader.iter_records_with_meta_as::<Record>() {
+        let (meta, mut record) = record?;
+        pad_character_values(&mut record, &fields);
+        records.push((meta, record));
+    }
+
+    // The index is only replaced once all the keys are computed
+    let mut index_writer = CdxWriter::new(Vec::new(), tags);
+    for (meta, record) in &records {
+        index_writer
+            .add_record(record, *meta, &encoding)
+            .map_err(|kind| Error {
+                record_num: meta.index,
+                field: None,
+                kind,
+            })?;
+    }
+    index_writer
+        .close()
+        .and_then(|_| std::fs::write(&index_path, &index_writer.dst))
+        .map_err(|error| Error::io_error(error, 0))?;
+
+    let mut table = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|error| Error::io_error(error, 0))?;
+    let mut header = Header::read_from(&mut table).map_err(|error| Error::io_error(error, 0))?;
+    header.table_flags.set_has_structural_cdx(true);
+    table
+        .seek(SeekFrom::Start(0))
+        .and_then(|_| header.write_to(&mut table))
+        .map_err(|error| Error::io_error(error, 0))
+}
+
+/// Rebuilds the structural index of the table at `path` after its records were modified
+///
+/// Returns `false` when the table has no structural .cdx (its production index being
+/// a dBase .mdx for example), or when the keys of its tags cannot be computed,
+/// in which case the caller clears the structural index flag of the table.
+pub(crate) fn update_structural_index(path: &Path) -> Result<bool, Error> {
+    if !path.with_extension("cdx").is_file() {
+        return Ok(false);
+    }
+    match reindex(path) {
+        Ok(()) => Ok(true),
+        Err(error) => match error.kind() {
+            ErrorKind::UnsupportedKeyExpression(_)
+            | ErrorKind::InvalidExpression(_)
+            | ErrorKind::FieldNotFound(_)
+            | ErrorKind::IncompatibleType
+            | ErrorKind::InvalidIndex(_) => Ok(false),
+            _ => Err(error),
+        },
+    }
+}
+
+/// Layout of the entries of the leaf nodes of a tree
+struct LeafLayout {
+    record_number_bits: u32,
+    count_bits: u32,
+    entry_size: usize,
+}
+
+impl LeafLayout {
+    fn new(key_length: usize, max_record_number: u32) -> Self {
+        let bits_for = |value: u32| (u32::BITS - value.leading_zeros()).max(1);
+        // The duplicate and trailing counts use the same number of bits
+        let count_bits = bits_for(key_length as u32);
+        let needed_bits = bits_for(max_record_number) + 2 * count_bits;
+        let entry_size = (needed_bits as usize).div_ceil(8).max(3);
+        let record_number_bits = (entry_size as u32 * 8 - 2 * count_bits).min(u32::BITS);
+        Self {
+            record_number_bits,
+            count_bits,
+            entry_size,
+        }
+    }
+
+    /// Returns the number of bytes shared with the previous key, and of trailing bytes
+    fn compress(previous: Option<&[u8]>, key: &[u8], trailing_byte: u8) -> (usize, usize) {
+        let duplicate_count = previous.map_or(0, |previous| {
+            key.iter().zip(previous).take_while(|(a, b)| a == b).count()
+        });
+        let trailing_count = key[duplicate_count..]
+            .iter()
+            .rev()
+            .take_while(|byte| **byte == trailing_byte)
+            .count();
+        (duplicate_count, trailing_count)
+    }
+
+    fn encode_leaf(
+        &self,
+        entries: &[(Vec<u8>, u32)],
+        node_attributes: u16,
+        left_sibling: u32,
+        right_sibling: u32,
+        trailing_byte: u8,
+    ) -> [u8; NODE_SIZE] {
+        let mut data = [0u8; NODE_SIZE];
+        let mask = |bits: u32| ((1u64 << bits) - 1) as u32;
+        LittleEndian::write_u16(&mut data[node::ATTRIBUTES..], node_attributes);
+        LittleEndian::write_u16(&mut data[node::NUM_KEYS..], entries.len() as u16);
+        LittleEndian::write_u32(&mut data[node::LEFT_SIBLING..], left_sibling);
+        LittleEndian::write_u32(&mut data[node::RIGHT_SIBLING..], right_sibling);
+        LittleEndian::write_u32(
+            &mut data[node::RECORD_NUMBER_MASK..],
+            mask(self.record_number_bits),
+        );
+        data[node::DUPLICATE_COUNT_MASK] = mask(self.count_bits) as u8;
+        data[node::TRAILING_COUNT_MASK] = mask(self.count_bits) as u8;
+        data[node::RECORD_NUMBER_BITS] = self.record_number_bits as u8;
+        data[node::DUPLICATE_COUNT_BITS] = self.count_bits as u8;
+        data[node::TRAILING_COUNT_BITS] = self.count_bits as u8;
+        data[node::ENTRY_SIZE] = self.entry_size as u8;
+
+        let mut previous = None;
+        let mut end_of_key = NODE_SIZE;
+        for (i, (key, record_number)) in entries.iter().enumerate() {
+            let (duplicate_count, trailing_count) = Self::compress(previous, key, trailing_byte);
+            let packed = u64::from(*record_number)
+                | (duplicate_count as u64) << self.record_number_bits
+                | (trailing_count as u64) << (self.record_number_bits + self.count_bits);
+            let position = node::LEAF_KEYS + i * self.entry_size;
+            LittleEndian::write_uint(
+                &mut data[position..position + self.entry_size],
+                packed,
+                self.entry_size,
+            );
+            let new_bytes = &key[duplicate_count..key.len() - trailing_count];
+            data[end_of_key - new_bytes.len()..end_of_key].copy_from_slice(new_bytes);
+            end_of_key -= new_bytes.len();
+            previous = Some(key.as_slice());
+        }
+        let free_space = end_of_key - (node::LEAF_KEYS + entries.len() * self.entry_size);
+        LittleEndian::write_u16(&mut data[node::FREE_SPACE..], free_space as u16);
+        data
+    }
+}
+
+/// Appends the nodes of a tree holding the entries, sorted in the order of the tree,
+/// to the data, and returns the offset of its root
+fn write_tree(
+    data: &mut Vec<u8>,
+    entries: &[(Vec<u8>, u32)],
+    key_length: usize,
+    trailing_byte: u8,
+) -> u32 {
+    let max_record_number = entries.iter().map(|(_, number)| *number).max();
+    let layout = LeafLayout::new(key_length, max_record_number.unwrap_or(0));
+
+    // Fill the leaves as much as possible
+    let mut leaves = vec![];
+    let mut start = 0;
+    let mut used = node::LEAF_KEYS;
+    for (i, (key, _)) in entries.iter().enumerate() {
+        let previous = Some(&entries[..i])
+            .filter(|_| i > start)
+            .and_then(|previous| previous.last())
+            .map(|(key, _)| key.as_slice());
+        let (duplicate_count, trailing_count) = LeafLayout::compress(previous, key, trailing_byte);
+        let size = layout.entry_size + key_length - duplicate_count - trailing_count;
+        if i > start && used + size > NODE_SIZE {
+            leaves.push(&entries[start..i]);
+            start = i;
+            let (_, trailing_count) = LeafLayout::compress(None, key, trailing_byte);
+            used = node::LEAF_KEYS + layout.entry_size + key_length - trailing_count;
+        } else {
+            used += size;
+        }
+    }
+    leaves.push(&entries[start..]);
+
+    let first_leaf = data.len() as u32;
+    let offset_of = |first: u32, i: usize| first + (i * NODE_SIZE) as u32;
+    let num_leaves = leaves.len();
+    let mut children = Vec::with_capacity(num_leaves);
+    for (i, leaf) in leaves.iter().enumerate() {
+        let node_attributes = if num_leaves == 1 {
+            attributes::ROOT | attributes::LEAF
+        } else {
+            attributes::LEAF
+        };
+        let left_sibling = i
+            .checked_sub(1)
+            .map_or(NO_NODE, |i| offset_of(first_leaf, i));
+        let right_sibling = Some(i + 1)
+            .filter(|next| *next < num_leaves)
+            .map_or(NO_NODE, |next| offset_of(first_leaf, next));
+        data.extend_from_slice(&layout.encode_leaf(
+            leaf,
+            node_attributes,
+            left_sibling,
+            right_sibling,
+            trailing_byte,
+        ));
+        if let Some((key, record_number)) = leaf.last() {
+            children.push((key.clone(), *record_number, offset_of(first_leaf, i)));
+        }
+    }
+    if num_leaves == 1 {
+        return first_leaf;
+    }
+
+    // Each interior entry holds the highest key of its child
+    let entries_per_node = (NODE_SIZE - node::INTERIOR_KEYS) / (key_length + 8);
+    loop {
+        let first_node = data.len() as u32;
+        let nodes = children.chunks(entries_per_node).collect::<Vec<_>>();
+        let num_nodes = nodes.len();
+        let mut parents = Vec::with_capacity(num_nodes);
+        for (i, node_entries) in nodes.into_iter().enumerate() {
+            let mut node_data = [0u8; NODE_SIZE];
+            let node_attributes = if num_nodes == 1 { attributes::ROOT } else { 0 };
+            let left_sibling = i
+                .checked_sub(1)
+                .map_or(NO_NODE, |i| offset_of(first_node, i));
+            let right_sibling = Some(i + 1)
+                .filter(|next| *next < num_nodes)
+                .map_or(NO_NODE, |next| offset_of(first_node, next));
+            LittleEndian::write_u16(&mut node_data[node::ATTRIBUTES..], node_attributes);
+            LittleEndian::write_u16(&mut node_data[node::NUM_KEYS..], node_entries.len() as u16);
+            LittleEndian::write_u32(&mut node_data[node::LEFT_SIBLING..], left_sibling);
+            LittleEndian::write_u32(&mut node_data[node::RIGHT_SIBLING..], right_sibling);
+            for (j, (key, record_number, child)) in node_entries.iter().enumerate() {
+                let position = node::INTERIOR_KEYS + j * (key_length + 8);
+                node_data[position..position + key_length].copy_from_slice(key);
+                BigEndian::write_u32(&mut node_data[position + key_length..], *record_number);
+                BigEndian::write_u32(&mut node_data[position + key_length + 4..], *child);
+            }
+            data.extend_from_slice(&node_data);
+            let (key, record_number, _) = &node_entries[node_entries.len() - 1];
+            parents.push((key.clone(), *record_number, offset_of(first_node, i)));
+        }
+        if num_nodes == 1 {
+            return first_node;
+        }
+        children = parents;
+    }
+}
+
+fn encode_tag_header(
+    root: u32,
+    key_length: usize,
+    key_expression: &str,
+    filter_expression: Option<&str>,
+    tag_options: u8,
+    descending: bool,
+) -> [u8; HEADER_SIZE] {
+    let mut data = [0u8; HEADER_SIZE];
+    LittleEndian::write_u32(&mut data[header::ROOT..], root);
+    LittleEndian::write_u32(&mut data[header::FREE_LIST..], NO_NODE);
+    LittleEndian::write_u16(&mut data[header::KEY_LENGTH..], key_length as u16);
+    data[header::OPTIONS] = tag_options
+        | options::COMPACT
+        | options::COMPOUND
+        | filter_expression.map_or(0, |_| options::FOR_CLAUSE);
+    data[header::SIGNATURE] = 1;
+    LittleEndian::write_u16(&mut data[header::DESCENDING..], u16::from(descending));
+
+    // The expressions are stored null terminated, the key expression first
+    let filter_expression = filter_expression.unwrap_or("");
+    let key_expression_length = key_expression.len() + 1;
+    LittleEndian::write_u16(&mut data[header::KEY_EXPRESSION_POSITION..], 0);
+    LittleEndian::write_u16(
+        &mut data[header::KEY_EXPRESSION_LENGTH..],
+        key_expression_length as u16,
+    );
+    LittleEndian::write_u16(
+        &mut data[header::FOR_EXPRESSION_POSITION..],
+        key_expression_length as u16,
+    );
+    LittleEndian::write_u16(
+        &mut data[header::FOR_EXPRESSION_LENGTH..],
+        filter_expression.len() as u16 + 1,
+    );
+    let pool = &mut data[header::EXPRESSION_POOL..];
+    pool[..key_expression.len()].copy_from_slice(key_expression.as_bytes());
+    pool[key_expression_length..key_expression_length + filter_expression.len()]
+        .copy_from_slice(filter_expression.as_bytes());
+    data
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::record::field::Date;
+    use crate::{FieldName, FieldType, FieldValue};
+    use std::convert::TryFrom;
+    use std::io::Cursor;
+
+    const ROOT: u16 = 0x01;
+    const LEAF: u16 = 0x02;
+
+    /// Encodes a leaf node, with 16 bits for the record number
+    /// and 8 bits for both the duplicate and trailing counts
+    fn leaf_node(
+        entries: &[(Vec<u8>, u32)],
+        attributes: u16,
+        right_sibling: u32,
+        trailing_byte: u8,
+    ) -> Vec<u8> {
+        let mut data = vec![0u8; NODE_SIZE];
+        LittleEndian::write_u16(&mut data[0..], attributes);
+        LittleEndian::write_u16(&mut data[2..], entries.len() as u16);
+        LittleEndian::write_u32(&mut data[4..], NO_NODE);
+        LittleEndian::write_u32(&mut data[8..], right_sibling);
+        LittleEndian::write_u32(&mut data[14..], 0xFFFF);
+        data[18] = 0xFF;
+        data[19] = 0xFF;
+        data[20] = 16;
+        data[21] = 8;
+        data[22] = 8;
+        data[23] = 4;
+        let mut previous: &[u8] = &[];
+        let mut end_of_key = NODE_SIZE;
+        for (i, (key, record_number)) in entries.iter().enumerate() {
+            let duplicate_count = key.iter().zip(previous).take_while(|(a, b)| a == b).count();
+            let trailing_count = key[duplicate_count..]
+                .iter()
+                .rev()
+                .take_while(|b| **b == trailing_byte)
+                .count();
+            let packed =
+                *record_number | ((duplicate_count as u32) << 16) | ((trailing_count as u32) << 24);
+            LittleEndian::write_u32(&mut data[24 + i * 4..], packed);
+            let new_bytes = &key[duplicate_count..key.len() - trailing_count];
+            data[end_of_key - new_bytes.len()..end_of_key].copy_from_slice(new_bytes);
+            end_of_key -= new_bytes.len();
+            previous = key;
+        }
+        data
+    }
+
+    fn interior_node(entries: &[(Vec<u8>, u32, u32)]) -> Vec<u8> {
+        let mut data = vec![0u8; NODE_SIZE];
+        LittleEndian::write_u16(&mut data[0..], ROOT);
+        LittleEndian::write_u16(&mut data[2..], entries.len() as u16);
+        LittleEndian::write_u32(&mut data[4..], NO_NODE);
+        LittleEndian::write_u32(&mut data[8..], NO_NODE);
+        let mut position = 12;
+        for (key, record_number, child) in entries {
+            data[position..position + key.len()].copy_from_slice(key);
+            position += key.len();
+            BigEndian::write_u32(&mut data[position..], *record_number);
+            BigEndian::write_u32(&mut data[position + 4..], *child);
+            position += 8;
+        }
+        data
+    }
+
+    struct TagFixture {
+        name: &'static str,
+        key_expression: &'static str,
+        filter: Option<&'static str>,
+        descending: bool,
+        /// Keys in the order of the tree, each leaf in its own Vec
+        leaves: Vec<Vec<(Vec<u8>, u32)>>,
+        trailing_byte: u8,
+    }
+
+    /// Builds a compound index, tags with more than one leaf get an interior root node
+    fn build_cdx(tags: &[TagFixture]) -> Cursor<Vec<u8>> {
+        let mut data = vec![0u8; HEADER_SIZE + NODE_SIZE];
+        let mut directory = vec![];
+        for tag in tags {
+            let header_offset = data.len() as u32;
+            data.resize(data.len() + HEADER_SIZE, 0);
+            let first_leaf = data.len() as u32;
+            let num_leaves = tag.leaves.len() as u32;
+            let mut interior_entries = vec![];
+            for (i, leaf) in tag.leaves.iter().enumerate() {
+                let offset = first_leaf + i as u32 * NODE_SIZE as u32;
+                let is_last = i as u32 + 1 == num_leaves;
+                let right_sibling = if is_last {
+                    NO_NODE
+                } else {
+                    offset + NODE_SIZE as u32
+                };
+                let attributes = if num_leaves == 1 { ROOT | LEAF } else { LEAF };
+                data.extend(leaf_node(
+                    leaf,
+                    attributes,
+                    right_sibling,
+                    tag.trailing_byte,
+                ));
+                let (last_key, last_record) = leaf.last().unwrap().clone();
+                interior_entries.push((last_key, last_record, offset));
+            }
+            let key_length = tag.leaves[0][0].0.len();
+            let root = if num_leaves == 1 {
+                first_leaf
+            } else {
+                data.extend(interior_node(&interior_entries));
+                data.len() as u32 - NODE_SIZE as u32
+            };
+            let header = encode_tag_header(
+                root,
+                key_length,
+                tag.key_expression,
+                tag.filter,
+                0,
+                tag.descending,
+            );
+            let start = header_offset as usize;
+            data[start..start + HEADER_SIZE].copy_from_slice(&header);
+            let mut name = tag.name.as_bytes().to_vec();
+            name.resize(10, 0);
+            directory.push((name, header_offset));
+        }
+        directory.sort();
+        data[..HEADER_SIZE].copy_from_slice(&encode_tag_header(
+            HEADER_SIZE as u32,
+            TAG_NAME_LENGTH,
+            "",
+            None,
+            options::STRUCTURE,
+            false,
+        ));
+        data[HEADER_SIZE..HEADER_SIZE + NODE_SIZE].copy_from_slice(&leaf_node(
+            &directory,
+            ROOT | LEAF,
+            NO_NODE,
+            0,
+        ));
+        Cursor::new(data)
+    }
+
+    fn character_keys(names: &[(&str, u32)]) -> Vec<(Vec<u8>, u32)> {
+        names
+            .iter()
+            .map(|(name, record_number)| (format!("{:<10}", name).into_bytes(), *record_number))
+            .collect()
+    }
+
+    fn numeric_keys(values: &[(f64, u32)]) -> Vec<(Vec<u8>, u32)> {
+        values
+            .iter()
+            .map(|(value, record_number)| (dbase7_double_bytes(*value).to_vec(), *record_number))
+            .collect()
+    }
+
+    fn customers_index() -> CdxIndex<Cursor<Vec<u8>>> {
+        let tags = [
+            TagFixture {
+                name: "NAME",
+                key_expression: "UPPER(NAME)",
+                filter: None,
+                descending: false,
+                leaves: vec![
+                    character_keys(&[("ALEX", 2), ("FERRYS", 5), ("JAMIE", 1)]),
+                    character_keys(&[("JAMIE", 4), ("JAMIESON", 6), ("SAM", 3)]),
+                ],
+                trailing_byte: b' ',
+            },
+            TagFixture {
+                name: "CUSTNO",
+                key_expression: "CUSTNO",
+                filter: Some("!DELETED()"),
+                descending: false,
+                leaves: vec![numeric_keys(&[
+                    (-12.5, 4),
+                    (7.0, 1),
+                    (42.0, 3),
+                    (1000.0, 2),
+                ])],
+                trailing_byte: 0,
+            },
+            TagFixture {
+                name: "SINCE",
+                key_expression: "SINCE",
+                filter: None,
+                descending: true,
+                leaves: vec![
+                    numeric_keys(&[(2_459_000.0, 3), (2_458_000.0, 1)]),
+                    numeric_keys(&[(2_457_000.0, 2)]),
+                ],
+                trailing_byte: 0,
+            },
+        ];
+        let fields = [
+            FieldInfo::new(
+                FieldName::try_from("NAME").unwrap(),
+                FieldType::Character,
+                10,
+            ),
+            FieldInfo::new(
+                FieldName::try_from("CUSTNO").unwrap(),
+                FieldType::Numeric,
+                8,
+            ),
+            FieldInfo::new(FieldName::try_from("SINCE").unwrap(), FieldType::Date, 8),
+        ];
+        CdxIndex::new(build_cdx(&tags))
+            .unwrap()
+            .with_table_fields(&fields)
+    }
+
+    fn collect<T: Read + Seek>(iter: Result<CdxIter<'_, T>, Error>) -> Vec<usize> {
+        iter.unwrap().collect::<Result<Vec<_>, _>>().unwrap()
+    }
+
+    #[test]
+    fn enumerate_tags() {
+        let index = customers_index();
+        let names = index.tags().iter().map(CdxTag::name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["NAME", "CUSTNO", "SINCE"]);
+
+        let name = index.tag("name").unwrap();
+        assert_eq!(name.key_expression(), "UPPER(NAME)");
+        assert_eq!(name.filter_expression(), None);
+        assert_eq!(name.key_type(), KeyType::Character);
+        assert_eq!(name.key_length(), 10);
+        assert!(!name.is_descending());
+
+        let custno = index.tag("CUSTNO").unwrap();
+        assert_eq!(custno.filter_expression(), Some("!DELETED()"));
+        assert_eq!(custno.key_type(), KeyType::Numeric);
+
+        let since = index.tag("SINCE").unwrap();
+        assert!(since.is_descending());
+        assert_eq!(since.key_type(), KeyType::Date);
+        assert!(index.tag("UNKNOWN").is_none());
+    }
+
+    #[test]
+    fn iterate_in_tag_order() {
+        let mut index = customers_index();
+        assert_eq!(collect(index.iter("NAME")), vec![1, 4, 0, 3, 5, 2]);
+        // Records excluded by the FOR expression are not in the index
+        assert_eq!(collect(index.iter("CUSTNO")), vec![3, 0, 2, 1]);
+        assert_eq!(collect(index.iter("SINCE")), vec![2, 0, 1]);
+        assert!(matches!(
+            index.iter("UNKNOWN").err().unwrap().kind(),
+            ErrorKind::TagNotFound(_)
+        ));
+    }
+
+    #[test]
+    fn seek_exact_and_prefix() {
+        let mut index = customers_index();
+        assert_eq!(index.seek("NAME", "JAMIE").unwrap(), Some(0));
+        assert_eq!(index.seek("NAME", "JAMIES").unwrap(), Some(5));
+        assert_eq!(index.seek("NAME", "FE").unwrap(), Some(4));
+        assert_eq!(index.seek_exact("NAME", "FE").unwrap(), None);
+        assert_eq!(index.seek_exact("NAME", "JAMIESON").unwrap(), Some(5));
+        assert_eq!(index.seek("NAME", "ZED").unwrap(), None);
+        assert_eq!(index.seek("CUSTNO", 42).unwrap(), Some(2));
+        assert_eq!(index.seek("CUSTNO", -12.5).unwrap(), Some(3));
+        assert_eq!(index.seek("CUSTNO", 43).unwrap(), None);
+        assert!(index.seek("CUSTNO", "42").is_err());
+    }
+
+    #[test]
+    fn ranges() {
+        let mut index = customers_index();
+        assert_eq!(collect(index.range("NAME", "B".."K")), vec![4, 0, 3, 5]);
+        assert_eq!(
+            collect(index.range("NAME", "JAMIE"..="JAMIE")),
+            vec![0, 3, 5]
+        );
+        assert_eq!(collect(index.range("CUSTNO", 0.0..100.0)), vec![0, 2]);
+        // Descending tags give the keys from the highest to the lowest
+        let since = Date::julian_day_number_to_gregorian_date(2_458_000);
+        assert_eq!(collect(index.range("SINCE", since..)), vec![2, 0]);
+        assert_eq!(collect(index.range("SINCE", ..since)), vec![1]);
+        assert_eq!(index.seek("SINCE", since).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn write_deep_unique_and_descending_tags() {
+        let fields = [FieldInfo::new(
+            FieldName::try_from("NAME").unwrap(),
+            FieldType::Character,
+            100,
+        )];
+        let tag = |name: &str, unique, descending| TagDefinition {
+            name: name.to_string(),
+            expression: KeyExpression::from_fields(&["NAME"], &fields).unwrap(),
+            filter: None,
+            unique,
+            descending,
+        };
+        let mut data = Cursor::new(vec![]);
+        let mut writer = CdxWriter::new(
+            &mut data,
+            vec![
+                tag("ASCENDING", false, false),
+                tag("UNIQUE", true, false),
+                tag("DESCENDING", false, true),
+            ],
+        );
+        // Long keys leave room for a few keys per node, the trees have several levels
+        let num_records = 300;
+        for i in 0..num_records {
+            let mut record = Record::default();
+            let name = format!("{:03}{}", (i * 7) % 150, "X".repeat(90));
+            record.insert("NAME".to_string(), FieldValue::Character(Some(name)));
+            let meta = RecordMeta {
+                index: i,
+                is_deleted: false,
+            };
+            writer
+                .add_record(&record, meta, &crate::UnicodeLossy)
+                .unwrap();
+        }
+        writer.close().unwrap();
+
+        let mut index = CdxIndex::new(data).unwrap();
+        let ascending = collect(index.iter("ASCENDING"));
+        assert_eq!(ascending.len(), num_records);
+        // Records 0 and 150 have the same name, they keep their order
+        assert_eq!(&ascending[..2], &[0, 150]);
+        assert_eq!(collect(index.iter("UNIQUE")).len(), 150);
+        assert_eq!(&collect(index.iter("UNIQUE"))[..2], &[0, 43]);
+        let mut descending = collect(index.iter("DESCENDING"));
+        assert_eq!(
+            index.seek("DESCENDING", "149").unwrap(),
+            Some(descending[0])
+        );
+        assert_eq!(index.seek("ASCENDING", "001").unwrap(), Some(43));
+        descending.reverse();
+        let keys = |records: &[usize]| records.iter().map(|i| (i * 7) % 150).collect::<Vec<_>>();
+        assert_eq!(keys(&descending), keys(&ascending));
+    }
+
+    #[test]
+    fn reindex_tags_with_for_expressions() {
+        let dbf_path = std::env::temp_dir().join("reindex_for_expressions.dbf");
+        let cdx_path = dbf_path.with_extension("cdx");
+        let mut writer = crate::TableWriterBuilder::new()
+            .add_character_field(FieldName::try_from("NAME").unwrap(), 10)
+            .add_numeric_field(FieldName::try_from("CUSTNO").unwrap(), 8, 0)
+            .build_with_file_dest(&dbf_path)
+            .unwrap();
+        for (name, custno) in [("Jamie", 7.0), ("Alex", 1000.0), ("Sam", 42.0)] {
+            let mut record = Record::default();
+            record.insert(
+                "NAME".to_string(),
+                FieldValue::Character(Some(name.to_string())),
+            );
+            record.insert("CUSTNO".to_string(), FieldValue::Numeric(Some(custno)));
+            writer.write_record(&record).unwrap();
+        }
+        writer.close().unwrap();
+        drop(writer);
+        let mut table = crate::Table::open(&dbf_path).unwrap();
+        table.delete(2).unwrap();
+        drop(table);
+
+        // An index with the tags of the fixture, but no keys yet
+        let fields = Reader::from_path(&dbf_path).unwrap().fields().to_vec();
+        let tag = |name: &str, key_expression: &str, filter: Option<&str>| TagDefinition {
+            name: name.to_string(),
+            expression: KeyExpression::parse(key_expression, &fields).unwrap(),
+            filter: filter.map(|text| TagFilter {
+                text: text.to_string(),
+                expression: Expression::parse(text).unwrap(),
+            }),
+            unique: false,
+            descending: false,
+        };
+        let file = File::create(&cdx_path).unwrap();
+        CdxWriter::new(
+            file,
+            vec![
+                tag("NAME", "UPPER(NAME)", None),
+                tag("CUSTNO", "CUSTNO", Some("!DELETED()")),
+            ],
+        )
+        .close()
+        .unwrap();
+
+        reindex(&dbf_path).unwrap();
+        let mut index = CdxIndex::from_path(&cdx_path)
+            .unwrap()
+            .with_table_fields(&fields);
+        assert_eq!(
+            index.tag("CUSTNO").unwrap().filter_expression(),
+            Some("!DELETED()")
+        );
+        assert_eq!(collect(index.iter("NAME")), vec![1, 0, 2]);
+        // The deleted record is not in the filtered tag
+        assert_eq!(collect(index.iter("CUSTNO")), vec![0, 1]);
+        assert_eq!(index.seek("NAME", "SAM").unwrap(), Some(2));
+
+        let _ = std::fs::remove_file(&dbf_path);
+        let _ = std::fs::remove_file(&cdx_path);
+    }
+}
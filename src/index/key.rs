@@ -0,0 +1,328 @@
+//! Computation of the keys of the indexes that the crate writes
+//!
+//! Keys are computed by evaluating the key expression of the tag with [Expression].
+//! Numeric and date keys are stored in binary form, character keys are padded
+//! with blanks to the length of the key, which is the length of the key of a blank record.
+use super::KeyType;
+use crate::expression::Expression;
+use crate::record::field::{dbase7_double_bytes, dbase7_long_bytes};
+use crate::{Encoding, ErrorKind, FieldInfo, FieldType, FieldValue, Record, RecordMeta};
+
+/// A parsed key expression
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct KeyExpression {
+    text: String,
+    expression: Expression,
+    key_type: KeyType,
+    /// Number of bytes of the keys
+    key_length: usize,
+    /// The field, when the expression is only the name of one,
+    /// binary keys are computed from its value to keep the time of DateTime fields
+    field: Option<String>,
+}
+
+impl KeyExpression {
+    /// Returns the expression dBase would generate to index the fields
+    ///
+    /// A single numeric or date field is indexed in binary form,
+    /// otherwise the fields are converted to strings and concatenated.
+    pub(crate) fn from_fields(names: &[&str], fields: &[FieldInfo]) -> Result<Self, ErrorKind> {
+        let mut terms = Vec::with_capacity(names.len());
+        for name in names {
+            let field = fields
+                .iter()
+                .find(|field| !field.is_hidden() && field.name.eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| ErrorKind::FieldNotFound(name.to_string()))?;
+            // Expressions cannot refer to fields whose name is not an identifier
+            if !field
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                return Err(unsupported(name));
+            }
+            let is_binary = matches!(
+                field.field_type,
+                FieldType::Numeric
+                    | FieldType::Float
+                    | FieldType::Integer
+                    | FieldType::Double
+                    | FieldType::Currency
+                    | FieldType::Date
+                    | FieldType::DateTime
+            );
+            let name = field.name.to_ascii_uppercase();
+            if names.len() == 1 && is_binary {
+                return Self::parse(&name, fields);
+            }
+            terms.push(match field.field_type {
+                FieldType::Character | FieldType::Varchar => name,
+                FieldType::Logical => format!("IIF({},'T','F')", name),
+                FieldType::Numeric | FieldType::Float => {
+                    format!(
+                        "STR({},{},{})",
+                        name, field.field_length, field.num_decimal_places
+                    )
+                }
+                FieldType::Integer => format!("STR({},11,0)", name),
+                FieldType::Date => format!("DTOS({})", name),
+                _ => return Err(unsupported(&name)),
+            });
+        }
+        if terms.is_empty() {
+            return Err(unsupported(""));
+        }
+        Self::parse(&terms.join("+"), fields)
+    }
+
+    /// Parses an expression, such as the ones stored in index files
+    ///
+    /// The type and the length of the keys are the ones of the key of a blank record,
+    /// expressions whose keys would be empty (`TRIM(NAME)`) are not supported.
+    pub(crate) fn parse(text: &str, fields: &[FieldInfo]) -> Result<Self, ErrorKind> {
+        let expression = Expression::parse(text).map_err(|_| unsupported(text))?;
+        expression
+            .check_fields(fields)
+            .map_err(|error| error.kind)?;
+        let field = fields
+            .iter()
+            .find(|field| !field.is_hidden() && field.name.eq_ignore_ascii_case(text.trim()));
+        let meta = RecordMeta {
+            index: 0,
+            is_deleted: false,
+        };
+        let (key_type, key_length) = match expression
+            .evaluate(&blank_record(fields), meta)
+            .map_err(|_| unsupported(text))?
+        {
+            FieldValue::Character(text) => (
+                KeyType::Character,
+                text.map_or(0, |text| text.chars().count()),
+            ),
+            FieldValue::Logical(_) => (KeyType::Character, 1),
+            FieldValue::Date(_) => (KeyType::Date, 8),
+            _ if field.is_some_and(|field| field.field_type == FieldType::Integer) => {
+                (KeyType::Numeric, 4)
+            }
+            _ => (KeyType::Numeric, 8),
+        };
+        if key_length == 0 {
+            return Err(unsupported(text));
+        }
+        Ok(Self {
+            text: text.to_string(),
+            expression,
+            key_type,
+            key_length,
+            field: field.map(|field| field.name.clone()),
+        })
+    }
+
+    /// Returns the number of bytes of the keys
+    pub(crate) fn key_length(&self) -> usize {
+        self.key_length
+    }
+
+    /// Returns whether the keys are character strings, whose trailing blanks are not stored
+    pub(crate) fn is_character(&self) -> bool {
+        self.key_type == KeyType::Character
+    }
+
+    /// Returns the names of the fields used by the expression
+    pub(crate) fn field_names(&self) -> Vec<&str> {
+        self.expression.field_names()
+    }
+
+    /// Computes the key of the record, as stored in FoxPro indexes
+    pub(crate) fn compute<E: Encoding>(
+        &self,
+        record: &Record,
+        meta: RecordMeta,
+        encoding: &E,
+    ) -> Result<Vec<u8>, ErrorKind> {
+        if let Some(field) = self.field.as_ref().filter(|_| !self.is_character()) {
+            let value = record
+                .get(field)
+                .ok_or_else(|| ErrorKind::FieldNotFound(field.to_string()))?;
+            return binary_key(value);
+        }
+        match self
+            .expression
+            .evaluate(record, meta)
+            .map_err(|error| error.kind)?
+        {
+            FieldValue::Character(text) => {
+                let mut key = encoding.encode(text.as_deref().unwrap_or(""))?.into_owned();
+                key.resize(self.key_length, b' ');
+                Ok(key)
+            }
+            FieldValue::Logical(value) => Ok(vec![if value == Some(true) { b'T' } else { b'F' }]),
+            value => binary_key(&value),
+        }
+    }
+}
+
+impl std::fmt::Display for KeyExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// Pads the character values of the record with blanks to the length of their field,
+/// as they are when xBase evaluates expressions
+pub(crate) fn pad_character_values(record: &mut Record, fields: &[FieldInfo]) {
+    for field in fields {
+        if let Some(FieldValue::Character(Some(text)) | FieldValue::Varchar(Some(text))) =
+            record.get_mut(&field.name)
+        {
+            let length = usize::from(field.field_length);
+            let num_chars = text.chars().count();
+            if num_chars < length {
+                text.extend(std::iter::repeat_n(' ', length - num_chars));
+            }
+        }
+    }
+}
+
+/// Returns a record whose character fields are filled with blanks and the others are empty
+fn blank_record(fields: &[FieldInfo]) -> Record {
+    let mut record = Record::default();
+    for field in fields.iter().filter(|field| !field.is_hidden()) {
+        let value = match field.field_type {
+            FieldType::Character | FieldType::Varchar => {
+                FieldValue::Character(Some(" ".repeat(usize::from(field.field_length))))
+            }
+            FieldType::NullFlags => continue,
+            field_type => FieldValue::null(field_type),
+        };
+        record.insert(field.name.clone(), value);
+    }
+    record
+}
+
+/// Numbers are stored so that their bytes sort like them, dates as julian days
+fn binary_key(value: &FieldValue) -> Result<Vec<u8>, ErrorKind> {
+    let key = match value {
+        FieldValue::Integer(value) => dbase7_long_bytes(value.unwrap_or(0)).to_vec(),
+        FieldValue::Numeric(value) | FieldValue::Double(value) | FieldValue::Currency(value) => {
+            dbase7_double_bytes(value.unwrap_or(0.0)).to_vec()
+        }
+        FieldValue::Float(value) => dbase7_double_bytes(value.map_or(0.0, f64::from)).to_vec(),
+        FieldValue::Date(date) => {
+            let julian_day = date.map_or(0, |date| date.to_julian_day_number());
+            dbase7_double_bytes(f64::from(julian_day)).to_vec()
+        }
+        FieldValue::DateTime(date_time) => {
+            let julian_day = date_time.map_or(0.0, |date_time| {
+                let time = date_time.time();
+                let seconds = time.hours() * 3600 + time.minutes() * 60 + time.seconds();
+                f64::from(date_time.date().to_julian_day_number()) + f64::from(seconds) / 86_400.0
+            });
+            dbase7_double_bytes(julian_day).to_vec()
+        }
+        _ => return Err(ErrorKind::IncompatibleType),
+    };
+    Ok(key)
+}
+
+fn unsupported(expression: &str) -> ErrorKind {
+    ErrorKind::UnsupportedKeyExpression(expression.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::record::field::Date;
+    use crate::{FieldName, UnicodeLossy};
+    use std::convert::TryFrom;
+
+    fn fields() -> Vec<FieldInfo> {
+        let mut amount = FieldInfo::new(
+            FieldName::try_from("AMOUNT").unwrap(),
+            FieldType::Numeric,
+            8,
+        );
+        amount.num_decimal_places = 2;
+        vec![
+            FieldInfo::new(
+                FieldName::try_from("NAME").unwrap(),
+                FieldType::Character,
+                6,
+            ),
+            amount,
+            FieldInfo::new(FieldName::try_from("SINCE").unwrap(), FieldType::Date, 8),
+        ]
+    }
+
+    const META: RecordMeta = RecordMeta {
+        index: 0,
+        is_deleted: false,
+    };
+
+    fn record() -> Record {
+        let mut record = Record::default();
+        // Padded like the records given to the index writer
+        record.insert(
+            "NAME".to_string(),
+            FieldValue::Character(Some("Jamie ".to_string())),
+        );
+        record.insert("AMOUNT".to_string(), FieldValue::Numeric(Some(12.5)));
+        record.insert(
+            "SINCE".to_string(),
+            FieldValue::Date(Some(Date::new(3, 7, 2021))),
+        );
+        record
+    }
+
+    #[test]
+    fn compound_keys() {
+        let expression =
+            KeyExpression::from_fields(&["name", "Amount", "SINCE"], &fields()).unwrap();
+        assert_eq!(expression.to_string(), "NAME+STR(AMOUNT,8,2)+DTOS(SINCE)");
+        assert_eq!(expression.key_length(), 22);
+        assert_eq!(
+            expression.compute(&record(), META, &UnicodeLossy).unwrap(),
+            b"Jamie    12.5020210703".to_vec()
+        );
+        assert_eq!(
+            KeyExpression::parse("NAME+STR(AMOUNT,8,2)+DTOS(SINCE)", &fields()).unwrap(),
+            expression
+        );
+    }
+
+    #[test]
+    fn single_field_keys() {
+        let expression = KeyExpression::from_fields(&["AMOUNT"], &fields()).unwrap();
+        assert_eq!(expression.to_string(), "AMOUNT");
+        assert!(!expression.is_character());
+        assert_eq!(
+            expression.compute(&record(), META, &UnicodeLossy).unwrap(),
+            dbase7_double_bytes(12.5).to_vec()
+        );
+
+        let expression = KeyExpression::parse("upper(name)", &fields()).unwrap();
+        assert_eq!(
+            expression.compute(&record(), META, &UnicodeLossy).unwrap(),
+            b"JAMIE ".to_vec()
+        );
+        let expression = KeyExpression::parse("SUBSTR(NAME, 2, 3)", &fields()).unwrap();
+        assert_eq!(expression.key_length(), 3);
+        assert_eq!(
+            expression.compute(&record(), META, &UnicodeLossy).unwrap(),
+            b"ami".to_vec()
+        );
+        // The keys of a blank record would be empty
+        assert!(matches!(
+            KeyExpression::parse("TRIM(NAME)", &fields()),
+            Err(ErrorKind::UnsupportedKeyExpression(_))
+        ));
+        assert!(matches!(
+            KeyExpression::parse("NAME + AMOUNT", &fields()),
+            Err(ErrorKind::UnsupportedKeyExpression(_))
+        ));
+        assert!(matches!(
+            KeyExpression::from_fields(&["UNKNOWN"], &fields()),
+            Err(ErrorKind::FieldNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,737 @@
+//! dBase IV multiple index files (.mdx)
+//!
+//! A .mdx file holds up to 47 indexes, called tags. The header of the file lists the tags,
+//! each one pointing to a header describing a B-tree. The nodes of the trees hold the keys along
+//! with the record having this key (leaf nodes) or the node of the sub-tree
+//! containing the keys lower or equal to it (interior nodes).
+//!
+//! Numeric and date keys are stored as 12 bytes binary coded decimals,
+//! dates being converted to julian day numbers.
+//!
+//! When the `has_structural_cdx` table flag of the header of a dBase IV table is set,
+//! the table has a production .mdx with the same name,
+//! which [Reader::iter_records_by_tag](crate::Reader::iter_records_by_tag) uses.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # fn main() -> Result<(), dbase::Error> {
+//! use dbase::index::mdx::MdxIndex;
+//!
+//! let mut reader = dbase::Reader::from_path("customers.dbf")?;
+//! let mut index = MdxIndex::from_path("customers.mdx")?;
+//!
+//! for tag in index.tags() {
+//!     println!("{}: {}", tag.name(), tag.key_expression());
+//! }
+//!
+//! if let Some(record_index) = index.seek("CUSTNO", 1234)? {
+//!     reader.seek(record_index)?;
+//!     let customer = reader.iter_records().next().unwrap()?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use super::{
+    compare_character_key, index_error, invalid_index, is_above_lower_bound, is_below_upper_bound,
+    IndexKey, KeyType,
+};
+use crate::{Error, ErrorKind};
+
+/// Pointers to the blocks of the file count 512 bytes pages
+const PAGE_SIZE: usize = 512;
+/// The tag table follows the header of the file
+const TAG_TABLE_OFFSET: usize = 544;
+const TAG_ENTRY_SIZE: usize = 32;
+const MAX_TAGS: usize = 47;
+/// Nodes store the number of keys they hold and a reserved pointer, then the keys
+const NODE_HEADER_SIZE: usize = 8;
+/// Numeric and date keys are binary coded decimals
+const NUMERIC_KEY_LENGTH: usize = 12;
+/// Guards against cycles in corrupted files
+const MAX_DEPTH: usize = 64;
+
+/// Positions in the header of the file
+mod file_header {
+    pub(super) const BLOCK_LENGTH: usize = 22;
+    pub(super) const NUM_TAGS: usize = 28;
+}
+
+/// Positions in the entries of the tag table
+mod tag_entry {
+    pub(super) const HEADER_PAGE: usize = 0;
+    pub(super) const NAME: usize = 4;
+    pub(super) const NAME_LENGTH: usize = 11;
+}
+
+/// Positions in the header of a tag
+mod tag_header {
+    pub(super) const ROOT_PAGE: usize = 0;
+    pub(super) const KEY_FORMAT: usize = 8;
+    pub(super) const KEY_TYPE: usize = 9;
+    pub(super) const KEY_LENGTH: usize = 12;
+    pub(super) const KEY_ITEM_LENGTH: usize = 18;
+    pub(super) const UNIQUE: usize = 23;
+    pub(super) const KEY_EXPRESSION: usize = 24;
+    pub(super) const KEY_EXPRESSION_LENGTH: usize = 220;
+}
+
+/// Bits of the key format
+mod key_format {
+    pub(super) const DESCENDING: u8 = 0x08;
+    pub(super) const UNIQUE: u8 = 0x40;
+}
+
+/// A tag of a multiple index file
+#[derive(Debug, Clone)]
+pub struct MdxTag {
+    name: String,
+    key_expression: String,
+    key_length: usize,
+    key_type: KeyType,
+    unique: bool,
+    descending: bool,
+    root_page: u32,
+    /// Size of the entries of the nodes: the pointer and the key, aligned on 4 bytes
+    key_item_length: usize,
+}
+
+impl MdxTag {
+    /// Returns the name of the tag
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the expression evaluated to compute the keys (e.g. `UPPER(NAME)`)
+    pub fn key_expression(&self) -> &str {
+        &self.key_expression
+    }
+
+    /// Returns the length in bytes of the keys
+    pub fn key_length(&self) -> usize {
+        self.key_length
+    }
+
+    /// Returns the type of the keys
+    pub fn key_type(&self) -> KeyType {
+        self.key_type
+    }
+
+    /// Returns whether the index only holds the first record of each key
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
+    /// Returns whether the keys are sorted from the highest to the lowest
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+
+    /// Compares a stored key with a searched one, in the order of the tree
+    fn compare(&self, stored: &[u8], key: &IndexKey) -> Ordering {
+        let ordering = match key {
+            IndexKey::Character(searched) => compare_character_key(stored, searched),
+            _ => {
+                let stored = decode_bcd_number(stored);
+                let searched = key.as_number().unwrap_or_default();
+                stored.partial_cmp(&searched).unwrap_or(Ordering::Equal)
+            }
+        };
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+/// A dBase IV multiple index file
+pub struct MdxIndex<T: Read + Seek> {
+    source: T,
+    /// Size in bytes of the nodes
+    block_length: usize,
+    tags: Vec<MdxTag>,
+}
+
+impl<T: Read + Seek> MdxIndex<T> {
+    /// Reads the tags of the index from the source
+    pub fn new(mut source: T) -> Result<Self, Error> {
+        let mut header = [0u8; TAG_TABLE_OFFSET + MAX_TAGS * TAG_ENTRY_SIZE];
+        source
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| source.read_exact(&mut header))
+            .map_err(|error| Error::io_error(error, 0))?;
+
+        let block_length = LittleEndian::read_u16(&header[file_header::BLOCK_LENGTH..]) as usize;
+        let num_tags = LittleEndian::read_u16(&header[file_header::NUM_TAGS..]) as usize;
+        if block_length < PAGE_SIZE
+            || !block_length.is_multiple_of(PAGE_SIZE)
+            || num_tags > MAX_TAGS
+        {
+            return Err(invalid_index("unexpected header values"));
+        }
+
+        let mut index = Self {
+            source,
+            block_length,
+            tags: Vec::with_capacity(num_tags),
+        };
+        for entry in header[TAG_TABLE_OFFSET..]
+            .chunks_exact(TAG_ENTRY_SIZE)
+            .take(num_tags)
+        {
+            let name = &entry[tag_entry::NAME..tag_entry::NAME + tag_entry::NAME_LENGTH];
+            let name_end = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+            let name = String::from_utf8_lossy(&name[..name_end])
+                .trim()
+                .to_string();
+            let header_page = LittleEndian::read_u32(&entry[tag_entry::HEADER_PAGE..]);
+            let tag = index.read_tag_header(header_page, name)?;
+            index.tags.push(tag);
+        }
+        Ok(index)
+    }
+
+    /// Returns the tags of the index
+    pub fn tags(&self) -> &[MdxTag] {
+        &self.tags
+    }
+
+    /// Returns the tag with the given name (case insensitive)
+    pub fn tag(&self, name: &str) -> Option<&MdxTag> {
+        self.tags
+            .iter()
+            .find(|tag| tag.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the index of the first record (in the order of the tag)
+    /// whose key matches the given one
+    ///
+    /// Like the `SEEK` command of dBase, a character key matches the keys that start with it.
+    /// The returned index can be given to [Reader::seek](crate::Reader::seek).
+    pub fn seek<K: Into<IndexKey>>(&mut self, tag: &str, key: K) -> Result<Option<usize>, Error> {
+        let key = key.into();
+        self.range(tag, key.clone()..=key)?.next().transpose()
+    }
+
+    /// Returns an iterator over the indices of the records whose keys are in the range,
+    /// in the order of the tag
+    pub fn range<K, R>(&mut self, tag: &str, range: R) -> Result<MdxIter<'_, T>, Error>
+    where
+        K: Into<IndexKey> + Clone,
+        R: RangeBounds<K>,
+    {
+        let tag = self.find_tag(tag)?;
+        let start = range.start_bound().cloned().map(Into::into);
+        let end = range.end_bound().cloned().map(Into::into);
+        for key in [&start, &end] {
+            if let Bound::Included(key) | Bound::Excluded(key) = key {
+                key.check_compatible_with(tag.key_type)
+                    .map_err(index_error)?;
+            }
+        }
+        // The tree of descending tags goes from the highest to the lowest key
+        let (start, end) = if tag.descending {
+            (end, start)
+        } else {
+            (start, end)
+        };
+        let stack = self.find_lower_bound(&tag, start.as_ref())?;
+        Ok(MdxIter {
+            index: self,
+            tag,
+            stack,
+            end,
+        })
+    }
+
+    /// Returns an iterator over the indices of all the records, in the order of the tag
+    pub fn iter(&mut self, tag: &str) -> Result<MdxIter<'_, T>, Error> {
+        self.range::<IndexKey, _>(tag, ..)
+    }
+
+    fn find_tag(&self, name: &str) -> Result<MdxTag, Error> {
+        self.tag(name)
+            .cloned()
+            .ok_or_else(|| index_error(ErrorKind::TagNotFound(name.to_string())))
+    }
+
+    fn read_tag_header(&mut self, page: u32, name: String) -> Result<MdxTag, Error> {
+        let mut data = [0u8; PAGE_SIZE];
+        self.read_at(page, &mut data)?;
+
+        let key_length = LittleEndian::read_u16(&data[tag_header::KEY_LENGTH..]) as usize;
+        let key_item_length = LittleEndian::read_u16(&data[tag_header::KEY_ITEM_LENGTH..]) as usize;
+        let key_type = match data[tag_header::KEY_TYPE] {
+            b'C' => KeyType::Character,
+            b'N' | b'F' => KeyType::Numeric,
+            b'D' => KeyType::Date,
+            _ => return Err(invalid_index("unexpected key type")),
+        };
+        if key_length == 0
+            || key_item_length < key_length + 4
+            || NODE_HEADER_SIZE + key_item_length + 4 > self.block_length
+            || (key_type != KeyType::Character && key_length != NUMERIC_KEY_LENGTH)
+        {
+            return Err(invalid_index("unexpected key length"));
+        }
+
+        let expression = &data[tag_header::KEY_EXPRESSION
+            ..tag_header::KEY_EXPRESSION + tag_header::KEY_EXPRESSION_LENGTH];
+        let expression_end = expression
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(expression.len());
+        let key_format = data[tag_header::KEY_FORMAT];
+
+        Ok(MdxTag {
+            name,
+            key_expression: String::from_utf8_lossy(&expression[..expression_end])
+                .trim()
+                .to_string(),
+            key_length,
+            key_type,
+            unique: data[tag_header::UNIQUE] != 0 || key_format & key_format::UNIQUE != 0,
+            descending: key_format & key_format::DESCENDING != 0,
+            root_page: LittleEndian::read_u32(&data[tag_header::ROOT_PAGE..]),
+            key_item_length,
+        })
+    }
+
+    /// Goes down the tree to the first key above the lower bound,
+    /// returns the path to it
+    fn find_lower_bound(
+        &mut self,
+        tag: &MdxTag,
+        start: Bound<&IndexKey>,
+    ) -> Result<Vec<Cursor>, Error> {
+        let mut stack = Vec::new();
+        let mut page = tag.root_page;
+        loop {
+            if stack.len() > MAX_DEPTH {
+                return Err(invalid_index("the tree is too deep"));
+            }
+            let node = self.read_node(tag, page)?;
+            let position = node
+                .keys()
+                .position(|stored| is_above_lower_bound(start, |key| tag.compare(stored, key)))
+                .unwrap_or(node.num_keys());
+            let child = node.entries.get(position).map(|entry| entry.pointer);
+            let is_leaf = node.is_leaf;
+            stack.push(Cursor { node, position });
+            match child {
+                Some(child) if !is_leaf => page = child,
+                _ => return Ok(stack),
+            }
+        }
+    }
+
+    fn read_node(&mut self, tag: &MdxTag, page: u32) -> Result<Node, Error> {
+        if page == 0 {
+            return Err(invalid_index("a node points to the header"));
+        }
+        let mut data = vec![0u8; self.block_length];
+        self.read_at(page, &mut data)?;
+
+        let num_keys = LittleEndian::read_u32(&data) as usize;
+        let max_keys = (self.block_length - NODE_HEADER_SIZE - 4) / tag.key_item_length;
+        if num_keys > max_keys {
+            return Err(invalid_index("a node holds too many keys"));
+        }
+        let entry_at = |i: usize| {
+            let start = NODE_HEADER_SIZE + i * tag.key_item_length;
+            Entry {
+                pointer: LittleEndian::read_u32(&data[start..]),
+                key: data[start + 4..start + 4 + tag.key_length].to_vec(),
+            }
+        };
+        // Interior nodes have one more child than keys, leaf nodes leave it to 0
+        let last_child =
+            LittleEndian::read_u32(&data[NODE_HEADER_SIZE + num_keys * tag.key_item_length..]);
+        let is_leaf = last_child == 0;
+        let num_entries = if is_leaf { num_keys } else { num_keys + 1 };
+        Ok(Node {
+            entries: (0..num_entries).map(entry_at).collect(),
+            is_leaf,
+        })
+    }
+
+    fn read_at(&mut self, page: u32, data: &mut [u8]) -> Result<(), Error> {
+        self.source
+            .seek(SeekFrom::Start(u64::from(page) * PAGE_SIZE as u64))
+            .and_then(|_| self.source.read_exact(data))
+            .map_err(|error| Error::io_error(error, 0))
+    }
+}
+
+impl MdxIndex<BufReader<File>> {
+    /// Opens the index file at the given path
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path).map_err(|error| Error::io_error(error, 0))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+/// Decodes a numeric key
+///
+/// The first byte is the exponent (biased by 0x34), the second the number of digits
+/// and the sign, the others the digits, two per byte, the first one being right after
+/// the decimal point.
+fn decode_bcd_number(key: &[u8]) -> f64 {
+    if key.len() < NUMERIC_KEY_LENGTH {
+        return 0.0;
+    }
+    let exponent = i32::from(key[0]) - 0x34;
+    let is_negative = key[1] & 0x80 != 0;
+    let num_digits = usize::from((key[1] >> 2) & 0x1F);
+    let mantissa = key[2..NUMERIC_KEY_LENGTH]
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0F])
+        .take(num_digits)
+        .fold(0.0, |mantissa, digit| mantissa * 10.0 + f64::from(digit));
+    // Dividing by an exact power of ten rounds correctly
+    let scale = exponent - num_digits as i32;
+    let value = if scale >= 0 {
+        mantissa * 10f64.powi(scale)
+    } else {
+        mantissa / 10f64.powi(-scale)
+    };
+    if is_negative {
+        -value
+    } else {
+        value
+    }
+}
+
+/// Iterator over the indices of the records, in the order of an [MdxTag]
+///
+/// The indices can be given to [Reader::seek](crate::Reader::seek).
+pub struct MdxIter<'a, T: Read + Seek> {
+    index: &'a mut MdxIndex<T>,
+    tag: MdxTag,
+    /// Path from the root to the next key
+    stack: Vec<Cursor>,
+    /// Upper bound, in the order of the tree
+    end: Bound<IndexKey>,
+}
+
+impl<T: Read + Seek> MdxIter<'_, T> {
+    fn next_entry(&mut self) -> Result<Option<Entry>, Error> {
+        loop {
+            let depth = self.stack.len();
+            let Some(cursor) = self.stack.last_mut() else {
+                return Ok(None);
+            };
+            if let Some(entry) = cursor.node.entries.get(cursor.position) {
+                if cursor.node.is_leaf {
+                    cursor.position += 1;
+                    return Ok(Some(entry.clone()));
+                }
+                if depth > MAX_DEPTH {
+                    return Err(invalid_index("the tree is too deep"));
+                }
+                let node = self.index.read_node(&self.tag, entry.pointer)?;
+                self.stack.push(Cursor { node, position: 0 });
+                continue;
+            }
+            self.stack.pop();
+            if let Some(parent) = self.stack.last_mut() {
+                parent.position += 1;
+            }
+        }
+    }
+}
+
+impl<T: Read + Seek> Iterator for MdxIter<'_, T> {
+    type Item = Result<usize, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = match self.next_entry() {
+            Ok(entry) => entry?,
+            Err(error) => {
+                self.stack.clear();
+                return Some(Err(error));
+            }
+        };
+        let tag = &self.tag;
+        if !is_below_upper_bound(self.end.as_ref(), |key| tag.compare(&entry.key, key)) {
+            self.stack.clear();
+            return None;
+        }
+        match entry.pointer.checked_sub(1) {
+            Some(record_index) => Some(Ok(record_index as usize)),
+            None => {
+                self.stack.clear();
+                Some(Err(invalid_index("a key points to record 0")))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Number (starting at 1) of the record in leaf nodes,
+    /// page of the node of the keys lower or equal to this one in interior nodes
+    pointer: u32,
+    key: Vec<u8>,
+}
+
+struct Node {
+    entries: Vec<Entry>,
+    is_leaf: bool,
+}
+
+impl Node {
+    /// The last entry of interior nodes only holds a child
+    fn num_keys(&self) -> usize {
+        if self.is_leaf {
+            self.entries.len()
+        } else {
+            self.entries.len().saturating_sub(1)
+        }
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &[u8]> {
+        self.entries[..self.num_keys()]
+            .iter()
+            .map(|entry| entry.key.as_slice())
+    }
+}
+
+/// Position in a node of the tree
+struct Cursor {
+    node: Node,
+    position: usize,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::record::field::Date;
+    use std::io::Cursor as IoCursor;
+
+    const BLOCK_LENGTH: usize = 1024;
+
+    /// Encodes a number the way dBase IV stores numeric keys
+    fn bcd_number(value: f64) -> Vec<u8> {
+        let mut key = vec![0u8; NUMERIC_KEY_LENGTH];
+        let text = format!("{:.6}", value.abs());
+        let (integer, fraction) = text.split_once('.').unwrap();
+        let integer = integer.trim_start_matches('0');
+        let digits = format!("{}{}", integer, fraction);
+        let (exponent, digits) = if integer.is_empty() {
+            let significant = digits.trim_start_matches('0');
+            let exponent = significant.len() as i32 - digits.len() as i32;
+            (exponent, significant)
+        } else {
+            (integer.len() as i32, digits.as_str())
+        };
+        let digits = digits.trim_end_matches('0');
+        key[0] = (0x34 + exponent) as u8;
+        key[1] = ((digits.len() as u8) << 2) | if value < 0.0 { 0x80 } else { 0 };
+        for (i, digit) in digits.bytes().enumerate() {
+            let digit = digit - b'0';
+            key[2 + i / 2] |= if i % 2 == 0 { digit << 4 } else { digit };
+        }
+        key
+    }
+
+    struct TagFixture {
+        name: &'static str,
+        key_expression: &'static str,
+        key_type: u8,
+        key_format: u8,
+        /// Keys in the order of the tree
+        keys: Vec<(Vec<u8>, u32)>,
+    }
+
+    /// Builds an index, each tag having a root interior node
+    /// when it has more than `max_keys` keys
+    fn build_mdx(tags: &[TagFixture], max_keys: usize) -> IoCursor<Vec<u8>> {
+        let pages_per_block = BLOCK_LENGTH / PAGE_SIZE;
+        let mut data = vec![0u8; 4 * PAGE_SIZE];
+        data[0] = 2;
+        LittleEndian::write_u16(&mut data[20..], pages_per_block as u16);
+        LittleEndian::write_u16(&mut data[file_header::BLOCK_LENGTH..], BLOCK_LENGTH as u16);
+        data[25] = 48;
+        data[26] = TAG_ENTRY_SIZE as u8;
+        LittleEndian::write_u16(&mut data[file_header::NUM_TAGS..], tags.len() as u16);
+
+        let page_of = |data: &Vec<u8>| (data.len() / PAGE_SIZE) as u32;
+        for (i, tag) in tags.iter().enumerate() {
+            let key_length = tag.keys[0].0.len();
+            let key_item_length = (key_length + 4).div_ceil(4) * 4;
+            let write_node = |data: &mut Vec<u8>, entries: &[(u32, &[u8])], last_child: u32| {
+                let mut node = vec![0u8; BLOCK_LENGTH];
+                let num_keys = entries.len() - usize::from(last_child != 0);
+                LittleEndian::write_u32(&mut node, num_keys as u32);
+                for (j, (pointer, key)) in entries.iter().enumerate() {
+                    let start = NODE_HEADER_SIZE + j * key_item_length;
+                    LittleEndian::write_u32(&mut node[start..], *pointer);
+                    node[start + 4..start + 4 + key.len()].copy_from_slice(key);
+                }
+                let page = page_of(data);
+                data.extend(node);
+                page
+            };
+
+            let header_page = page_of(&data);
+            data.resize(data.len() + BLOCK_LENGTH, 0);
+            let leaves = tag
+                .keys
+                .chunks(max_keys)
+                .map(|chunk| {
+                    let entries = chunk
+                        .iter()
+                        .map(|(key, record_number)| (*record_number, key.as_slice()))
+                        .collect::<Vec<_>>();
+                    (write_node(&mut data, &entries, 0), &chunk.last().unwrap().0)
+                })
+                .collect::<Vec<_>>();
+            let root_page = if leaves.len() == 1 {
+                leaves[0].0
+            } else {
+                let last_child = leaves.last().unwrap().0;
+                let entries = leaves
+                    .iter()
+                    .map(|(page, key)| (*page, key.as_slice()))
+                    .collect::<Vec<_>>();
+                write_node(&mut data, &entries, last_child)
+            };
+
+            let header = &mut data[header_page as usize * PAGE_SIZE..];
+            LittleEndian::write_u32(&mut header[tag_header::ROOT_PAGE..], root_page);
+            header[tag_header::KEY_FORMAT] = tag.key_format;
+            header[tag_header::KEY_TYPE] = tag.key_type;
+            LittleEndian::write_u16(&mut header[tag_header::KEY_LENGTH..], key_length as u16);
+            LittleEndian::write_u16(
+                &mut header[tag_header::KEY_ITEM_LENGTH..],
+                key_item_length as u16,
+            );
+            header[tag_header::KEY_EXPRESSION..][..tag.key_expression.len()]
+                .copy_from_slice(tag.key_expression.as_bytes());
+
+            let entry = &mut data[TAG_TABLE_OFFSET + i * TAG_ENTRY_SIZE..];
+            LittleEndian::write_u32(&mut entry[tag_entry::HEADER_PAGE..], header_page);
+            entry[tag_entry::NAME..][..tag.name.len()].copy_from_slice(tag.name.as_bytes());
+            entry[20] = tag.key_type;
+        }
+        IoCursor::new(data)
+    }
+
+    fn customers_index() -> MdxIndex<IoCursor<Vec<u8>>> {
+        let names = ["ALEX", "FERRYS", "JAMIE", "JAMIE", "JAMIESON", "SAM"];
+        let since = [2_457_000.0, 2_458_000.0, 2_459_000.0];
+        let tags = [
+            TagFixture {
+                name: "NAME",
+                key_expression: "UPPER(NAME)",
+                key_type: b'C',
+                key_format: 0x10,
+                keys: names
+                    .iter()
+                    .zip([2, 5, 1, 4, 6, 3])
+                    .map(|(name, record)| (format!("{:<10}", name).into_bytes(), record))
+                    .collect(),
+            },
+            TagFixture {
+                name: "CUSTNO",
+                key_expression: "CUSTNO",
+                key_type: b'N',
+                key_format: 0,
+                keys: [
+                    (-12.5, 4),
+                    (0.0, 5),
+                    (0.25, 6),
+                    (7.0, 1),
+                    (42.0, 3),
+                    (1000.0, 2),
+                ]
+                .iter()
+                .map(|(value, record)| (bcd_number(*value), *record))
+                .collect(),
+            },
+            TagFixture {
+                name: "SINCE",
+                key_expression: "SINCE",
+                key_type: b'D',
+                key_format: key_format::DESCENDING,
+                keys: since
+                    .iter()
+                    .rev()
+                    .zip([3, 1, 2])
+                    .map(|(day, record)| (bcd_number(*day), record))
+                    .collect(),
+            },
+        ];
+        MdxIndex::new(build_mdx(&tags, 2)).unwrap()
+    }
+
+    fn collect(iter: Result<MdxIter<'_, IoCursor<Vec<u8>>>, Error>) -> Vec<usize> {
+        iter.unwrap().collect::<Result<Vec<_>, _>>().unwrap()
+    }
+
+    #[test]
+    fn decode_numbers() {
+        for value in [
+            0.0,
+            1.0,
+            -1.0,
+            0.25,
+            12.5,
+            -12.5,
+            1000.0,
+            2_459_000.0,
+            0.001,
+        ] {
+            assert_eq!(decode_bcd_number(&bcd_number(value)), value);
+        }
+    }
+
+    #[test]
+    fn enumerate_tags() {
+        let index = customers_index();
+        let names = index.tags().iter().map(MdxTag::name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["NAME", "CUSTNO", "SINCE"]);
+        let name = index.tag("name").unwrap();
+        assert_eq!(name.key_expression(), "UPPER(NAME)");
+        assert_eq!(name.key_type(), KeyType::Character);
+        assert_eq!(name.key_length(), 10);
+        assert!(!name.is_unique());
+        assert_eq!(index.tag("CUSTNO").unwrap().key_type(), KeyType::Numeric);
+        let since = index.tag("SINCE").unwrap();
+        assert_eq!(since.key_type(), KeyType::Date);
+        assert!(since.is_descending());
+    }
+
+    #[test]
+    fn iterate_and_seek() {
+        let mut index = customers_index();
+        assert_eq!(collect(index.iter("NAME")), vec![1, 4, 0, 3, 5, 2]);
+        assert_eq!(collect(index.iter("CUSTNO")), vec![3, 4, 5, 0, 2, 1]);
+        assert_eq!(collect(index.iter("SINCE")), vec![2, 0, 1]);
+        assert_eq!(collect(index.range("NAME", "B".."K")), vec![4, 0, 3, 5]);
+        assert_eq!(collect(index.range("CUSTNO", 0..=42)), vec![4, 5, 0, 2]);
+
+        assert_eq!(index.seek("NAME", "JAMIES").unwrap(), Some(5));
+        assert_eq!(index.seek("NAME", "ZED").unwrap(), None);
+        assert_eq!(index.seek("CUSTNO", 0.25).unwrap(), Some(5));
+        assert_eq!(index.seek("CUSTNO", 43).unwrap(), None);
+        let since = Date::julian_day_number_to_gregorian_date(2_458_000);
+        assert_eq!(index.seek("SINCE", since).unwrap(), Some(0));
+        assert_eq!(collect(index.range("SINCE", since..)), vec![2, 0]);
+        assert!(index.seek("CUSTNO", "42").is_err());
+        assert!(matches!(
+            index.iter("UNKNOWN").err().unwrap().kind(),
+            ErrorKind::TagNotFound(_)
+        ));
+    }
+}
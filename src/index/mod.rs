@@ -0,0 +1,214 @@
+//! Reading of the index files that can accompany a dBase file
+//!
+//! Indexes map the value of a key expression to record indices,
+//! which can then be used with [Reader::seek](crate::Reader::seek) to read
+//! the records in the order of the index, or only those matching a key.
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::BufReader;
+use std::ops::Bound;
+use std::path::Path;
+
+use crate::record::field::Date;
+use crate::{Error, ErrorKind, FieldInfo, FieldType};
+
+pub mod cdx;
+pub(crate) mod key;
+pub mod mdx;
+pub mod ndx;
+pub mod ntx;
+
+/// The type of the keys of an index
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyType {
+    Character,
+    Numeric,
+    Date,
+}
+
+/// A key to search in an index
+///
+/// Character keys are compared byte by byte with the keys of the index,
+/// so they must use the same encoding as the indexed table.
+/// Like the `SEEK` command of dBase (with `SET EXACT OFF`),
+/// a character key matches all the keys of the index that start with it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IndexKey {
+    Character(Vec<u8>),
+    Numeric(f64),
+    Date(Date),
+}
+
+impl From<&str> for IndexKey {
+    fn from(key: &str) -> Self {
+        IndexKey::Character(key.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for IndexKey {
+    fn from(key: String) -> Self {
+        IndexKey::Character(key.into_bytes())
+    }
+}
+
+impl From<Vec<u8>> for IndexKey {
+    fn from(key: Vec<u8>) -> Self {
+        IndexKey::Character(key)
+    }
+}
+
+impl From<f64> for IndexKey {
+    fn from(key: f64) -> Self {
+        IndexKey::Numeric(key)
+    }
+}
+
+impl From<i32> for IndexKey {
+    fn from(key: i32) -> Self {
+        IndexKey::Numeric(f64::from(key))
+    }
+}
+
+impl From<Date> for IndexKey {
+    fn from(key: Date) -> Self {
+        IndexKey::Date(key)
+    }
+}
+
+impl IndexKey {
+    /// Checks that the key can be compared to the keys of an index of the given type
+    pub(crate) fn check_compatible_with(&self, key_type: KeyType) -> Result<(), ErrorKind> {
+        match (self, key_type) {
+            (IndexKey::Character(_), KeyType::Character)
+            | (IndexKey::Numeric(_) | IndexKey::Date(_), KeyType::Numeric | KeyType::Date) => {
+                Ok(())
+            }
+            _ => Err(ErrorKind::IncompatibleType),
+        }
+    }
+
+    /// Returns the numeric value of the key, dates being converted to julian day numbers
+    pub(crate) fn as_number(&self) -> Option<f64> {
+        match self {
+            IndexKey::Character(_) => None,
+            IndexKey::Numeric(value) => Some(*value),
+            IndexKey::Date(date) => Some(f64::from(date.to_julian_day_number())),
+        }
+    }
+}
+
+/// The index that dBase and FoxPro keep up to date along with a table:
+/// the production .mdx of dBase IV, or the structural .cdx of FoxPro
+pub(crate) enum ProductionIndex {
+    Mdx(mdx::MdxIndex<BufReader<File>>),
+    Cdx(cdx::CdxIndex<BufReader<File>>),
+}
+
+impl ProductionIndex {
+    /// Opens the production index next to the table at `dbf_path`, if there is one
+    pub(crate) fn open(dbf_path: &Path, fields: &[FieldInfo]) -> Result<Option<Self>, Error> {
+        let mdx_path = dbf_path.with_extension("mdx");
+        if mdx_path.is_file() {
+            return mdx::MdxIndex::from_path(mdx_path)
+                .map(|index| Some(ProductionIndex::Mdx(index)));
+        }
+        let cdx_path = dbf_path.with_extension("cdx");
+        if cdx_path.is_file() {
+            let index = cdx::CdxIndex::from_path(cdx_path)?.with_table_fields(fields);
+            return Ok(Some(ProductionIndex::Cdx(index)));
+        }
+        Ok(None)
+    }
+
+    /// Returns the indices of the records, in the order of the tag
+    pub(crate) fn record_indices(&mut self, tag: &str) -> Result<Vec<usize>, Error> {
+        match self {
+            ProductionIndex::Mdx(index) => index.iter(tag)?.collect(),
+            ProductionIndex::Cdx(index) => index.iter(tag)?.collect(),
+        }
+    }
+}
+
+/// Returns the type of the keys computed by the expression,
+/// when it is only the name of one of the fields
+pub(crate) fn key_type_of_expression(expression: &str, fields: &[FieldInfo]) -> Option<KeyType> {
+    let field = fields
+        .iter()
+        .find(|field| field.name.eq_ignore_ascii_case(expression.trim()))?;
+    match field.field_type {
+        FieldType::Character | FieldType::Varchar => Some(KeyType::Character),
+        FieldType::Numeric
+        | FieldType::Float
+        | FieldType::Integer
+        | FieldType::Double
+        | FieldType::Currency => Some(KeyType::Numeric),
+        FieldType::Date | FieldType::DateTime => Some(KeyType::Date),
+        _ => None,
+    }
+}
+
+/// Compares a character key stored in an index with a searched one
+///
+/// Only the first `searched.len()` bytes of the stored key, padded with spaces, are compared
+/// so that a key matches all the stored keys it is a prefix of.
+pub(crate) fn compare_character_key(stored: &[u8], searched: &[u8]) -> Ordering {
+    stored
+        .iter()
+        .copied()
+        .chain(std::iter::repeat(b' '))
+        .take(searched.len())
+        .cmp(searched.iter().copied())
+}
+
+/// Returns whether a key, given its ordering relatively to the bound, is above the lower bound
+pub(crate) fn is_above_lower_bound<K>(
+    bound: Bound<&K>,
+    ordering: impl FnOnce(&K) -> Ordering,
+) -> bool {
+    match bound {
+        Bound::Included(key) => ordering(key) != Ordering::Less,
+        Bound::Excluded(key) => ordering(key) == Ordering::Greater,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Returns whether a key, given its ordering relatively to the bound, is below the upper bound
+pub(crate) fn is_below_upper_bound<K>(
+    bound: Bound<&K>,
+    ordering: impl FnOnce(&K) -> Ordering,
+) -> bool {
+    match bound {
+        Bound::Included(key) => ordering(key) != Ordering::Greater,
+        Bound::Excluded(key) => ordering(key) == Ordering::Less,
+        Bound::Unbounded => true,
+    }
+}
+
+pub(crate) fn index_error(kind: ErrorKind) -> Error {
+    Error {
+        record_num: 0,
+        field: None,
+        kind,
+    }
+}
+
+pub(crate) fn invalid_index(reason: &str) -> Error {
+    index_error(ErrorKind::InvalidIndex(reason.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn character_keys_match_by_prefix() {
+        assert_eq!(compare_character_key(b"SMITH     ", b"SM"), Ordering::Equal);
+        assert_eq!(
+            compare_character_key(b"SMITH     ", b"SMITH"),
+            Ordering::Equal
+        );
+        assert_eq!(compare_character_key(b"SMITH", b"SMITH  "), Ordering::Equal);
+        assert_eq!(compare_character_key(b"SMITH", b"SMITHS"), Ordering::Less);
+        assert_eq!(compare_character_key(b"SMITH", b"ADAMS"), Ordering::Greater);
+    }
+}
@@ -0,0 +1,612 @@
+//! dBase III single key index files (.ndx)
+//!
+//! A .ndx file is a B-tree made of 512 bytes blocks, the first one being the header.
+//! Each block holds a list of keys, along with the block of the sub-tree
+//! containing the keys lower or equal to it (interior blocks)
+//! or the number of the record having this key (leaf blocks).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # fn main() -> Result<(), dbase::Error> {
+//! use dbase::index::ndx::NdxIndex;
+//!
+//! let mut reader = dbase::Reader::from_path("customers.dbf")?;
+//! let mut index = NdxIndex::from_path("custname.ndx")?.with_table_fields(reader.fields());
+//!
+//! if let Some(record_index) = index.seek("SMITH")? {
+//!     reader.seek(record_index)?;
+//!     let customer = reader.iter_records().next().unwrap()?;
+//! }
+//!
+//! for record_index in index.range("A".."C")? {
+//!     reader.seek(record_index?)?;
+//!     let customer = reader.iter_records().next().unwrap()?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+
+use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
+
+use super::{
+    compare_character_key, index_error, invalid_index, is_above_lower_bound, is_below_upper_bound,
+    key_type_of_expression, IndexKey, KeyType,
+};
+use crate::{Error, FieldInfo};
+
+const BLOCK_SIZE: usize = 512;
+/// Blocks store the number of keys they hold, then the keys
+const BLOCK_HEADER_SIZE: usize = 4;
+const KEY_EXPRESSION_OFFSET: usize = 24;
+/// Guards against cycles in corrupted files
+const MAX_DEPTH: usize = 64;
+
+/// A dBase III index file
+pub struct NdxIndex<T: Read + Seek> {
+    source: T,
+    root_block: u32,
+    key_length: usize,
+    key_record_size: usize,
+    key_type: KeyType,
+    unique: bool,
+    key_expression: String,
+}
+
+impl<T: Read + Seek> NdxIndex<T> {
+    /// Reads the header of the index from the source
+    pub fn new(mut source: T) -> Result<Self, Error> {
+        let mut header = [0u8; BLOCK_SIZE];
+        source
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| source.read_exact(&mut header))
+            .map_err(|error| Error::io_error(error, 0))?;
+
+        let mut fields = &header[..];
+        let read_header = |fields: &mut &[u8]| -> std::io::Result<_> {
+            let root_block = fields.read_u32::<LittleEndian>()?;
+            let _num_blocks = fields.read_u32::<LittleEndian>()?;
+            let _reserved = fields.read_u32::<LittleEndian>()?;
+            let key_length = fields.read_u16::<LittleEndian>()?;
+            let _max_keys_per_block = fields.read_u16::<LittleEndian>()?;
+            let key_type = fields.read_u16::<LittleEndian>()?;
+            let key_record_size = fields.read_u16::<LittleEndian>()?;
+            Ok((root_block, key_length, key_type, key_record_size))
+        };
+        let (root_block, key_length, key_type, key_record_size) =
+            read_header(&mut fields).map_err(|error| Error::io_error(error, 0))?;
+        let unique = header[KEY_EXPRESSION_OFFSET - 1] != 0;
+
+        let key_type = if key_type == 0 {
+            KeyType::Character
+        } else {
+            KeyType::Numeric
+        };
+        let key_length = key_length as usize;
+        let key_record_size = key_record_size as usize;
+        if root_block == 0
+            || key_length == 0
+            || key_record_size < key_length + 8
+            || key_record_size > BLOCK_SIZE - BLOCK_HEADER_SIZE
+            || (key_type == KeyType::Numeric && key_length != std::mem::size_of::<f64>())
+        {
+            return Err(invalid_index("unexpected header values"));
+        }
+
+        let expression = &header[KEY_EXPRESSION_OFFSET..];
+        let expression_end = expression
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(expression.len());
+        let key_expression = String::from_utf8_lossy(&expression[..expression_end])
+            .trim()
+            .to_string();
+
+        Ok(Self {
+            source,
+            root_block,
+            key_length,
+            key_record_size,
+            key_type,
+            unique,
+            key_expression,
+        })
+    }
+
+    /// Returns the expression evaluated to compute the keys (e.g. `UPPER(NAME)`)
+    pub fn key_expression(&self) -> &str {
+        &self.key_expression
+    }
+
+    /// Returns the type of the keys
+    ///
+    /// NDX files store dates as numbers, [KeyType::Date] is only returned
+    /// once [with_table_fields](Self::with_table_fields) found out that the key is a date.
+    pub fn key_type(&self) -> KeyType {
+        self.key_type
+    }
+
+    /// Returns the length in bytes of the keys
+    pub fn key_length(&self) -> usize {
+        self.key_length
+    }
+
+    /// Returns whether the index only holds the first record of each key
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
+    /// Uses the fields of the indexed table to tell whether numeric keys are dates
+    ///
+    /// That is the case when the key expression is the name of a Date field.
+    pub fn with_table_fields(mut self, fields: &[FieldInfo]) -> Self {
+        let key_type = key_type_of_expression(&self.key_expression, fields);
+        if self.key_type == KeyType::Numeric && key_type == Some(KeyType::Date) {
+            self.key_type = KeyType::Date;
+        }
+        self
+    }
+
+    /// Returns the index of the first record (in the order of the index)
+    /// whose key matches the given one
+    ///
+    /// The returned index can be given to [Reader::seek](crate::Reader::seek).
+    pub fn seek<K: Into<IndexKey>>(&mut self, key: K) -> Result<Option<usize>, Error> {
+        let key = key.into();
+        self.range(key.clone()..=key)?.next().transpose()
+    }
+
+    /// Returns an iterator over the indices of the records whose keys are in the range,
+    /// in the order of the index
+    pub fn range<K, R>(&mut self, range: R) -> Result<NdxIter<'_, T>, Error>
+    where
+        K: Into<IndexKey> + Clone,
+        R: RangeBounds<K>,
+    {
+        let start = range.start_bound().cloned().map(Into::into);
+        let end = range.end_bound().cloned().map(Into::into);
+        for key in [&start, &end] {
+            if let Bound::Included(key) | Bound::Excluded(key) = key {
+                key.check_compatible_with(self.key_type)
+                    .map_err(index_error)?;
+            }
+        }
+
+        let stack = self.find_lower_bound(start.as_ref())?;
+        Ok(NdxIter {
+            index: self,
+            stack,
+            end,
+        })
+    }
+
+    /// Returns an iterator over the indices of all the records, in the order of the index
+    pub fn iter(&mut self) -> Result<NdxIter<'_, T>, Error> {
+        self.range::<IndexKey, _>(..)
+    }
+
+    /// Goes down the tree to the first key above the lower bound,
+    /// returns the path to it
+    fn find_lower_bound(&mut self, start: Bound<&IndexKey>) -> Result<Vec<Cursor>, Error> {
+        let mut stack = Vec::new();
+        let mut block = self.root_block;
+        loop {
+            if stack.len() > MAX_DEPTH {
+                return Err(invalid_index("the tree is too deep"));
+            }
+            let block_data = self.read_block(block)?;
+            let position = block_data
+                .keys()
+                .position(|stored| is_above_lower_bound(start, |key| self.compare(stored, key)))
+                .unwrap_or(block_data.num_keys());
+            let child = block_data.entries.get(position).map(|entry| entry.child);
+            let is_leaf = block_data.is_leaf;
+            stack.push(Cursor {
+                block: block_data,
+                position,
+            });
+            match child {
+                Some(child) if !is_leaf => block = child,
+                _ => return Ok(stack),
+            }
+        }
+    }
+
+    fn compare(&self, stored: &[u8], key: &IndexKey) -> Ordering {
+        match key {
+            IndexKey::Character(searched) => compare_character_key(stored, searched),
+            _ => {
+                let stored = LittleEndian::read_f64(stored);
+                let searched = key.as_number().unwrap_or_default();
+                stored.partial_cmp(&searched).unwrap_or(Ordering::Equal)
+            }
+        }
+    }
+
+    fn read_block(&mut self, block: u32) -> Result<Block, Error> {
+        if block == 0 {
+            return Err(invalid_index("a block points to the header"));
+        }
+        let mut data = [0u8; BLOCK_SIZE];
+        self.source
+            .seek(SeekFrom::Start(block as u64 * BLOCK_SIZE as u64))
+            .and_then(|_| self.source.read_exact(&mut data))
+            .map_err(|error| Error::io_error(error, 0))?;
+
+        let num_keys = LittleEndian::read_u32(&data) as usize;
+        let entry_at = |i: usize| {
+            let start = BLOCK_HEADER_SIZE + i * self.key_record_size;
+            let entry = &data[start..start + self.key_record_size];
+            Entry {
+                child: LittleEndian::read_u32(&entry[0..4]),
+                record_number: LittleEndian::read_u32(&entry[4..8]),
+                key: entry[8..8 + self.key_length].to_vec(),
+            }
+        };
+        let max_entries = (BLOCK_SIZE - BLOCK_HEADER_SIZE) / self.key_record_size;
+        // Interior blocks have one more child than keys
+        let is_leaf = entry_at(0).child == 0;
+        let num_entries = if is_leaf { num_keys } else { num_keys + 1 };
+        if num_entries > max_entries {
+            return Err(invalid_index("a block holds too many keys"));
+        }
+        Ok(Block {
+            entries: (0..num_entries).map(entry_at).collect(),
+            is_leaf,
+        })
+    }
+}
+
+impl NdxIndex<BufReader<File>> {
+    /// Opens the index file at the given path
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path).map_err(|error| Error::io_error(error, 0))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+/// Iterator over the indices of the records, in the order of an [NdxIndex]
+///
+/// The indices can be given to [Reader::seek](crate::Reader::seek).
+pub struct NdxIter<'a, T: Read + Seek> {
+    index: &'a mut NdxIndex<T>,
+    /// Path from the root to the next key
+    stack: Vec<Cursor>,
+    end: Bound<IndexKey>,
+}
+
+impl<T: Read + Seek> NdxIter<'_, T> {
+    fn next_entry(&mut self) -> Result<Option<Entry>, Error> {
+        loop {
+            let depth = self.stack.len();
+            let Some(cursor) = self.stack.last_mut() else {
+                return Ok(None);
+            };
+            if let Some(entry) = cursor.block.entries.get(cursor.position) {
+                if cursor.block.is_leaf {
+                    cursor.position += 1;
+                    return Ok(Some(entry.clone()));
+                }
+                if depth > MAX_DEPTH {
+                    return Err(invalid_index("the tree is too deep"));
+                }
+                let block = self.index.read_block(entry.child)?;
+                self.stack.push(Cursor { block, position: 0 });
+                continue;
+            }
+            self.stack.pop();
+            if let Some(parent) = self.stack.last_mut() {
+                parent.position += 1;
+            }
+        }
+    }
+}
+
+impl<T: Read + Seek> Iterator for NdxIter<'_, T> {
+    type Item = Result<usize, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = match self.next_entry() {
+            Ok(entry) => entry?,
+            Err(error) => {
+                self.stack.clear();
+                return Some(Err(error));
+            }
+        };
+        let index = &*self.index;
+        if !is_below_upper_bound(self.end.as_ref(), |key| index.compare(&entry.key, key)) {
+            self.stack.clear();
+            return None;
+        }
+        match entry.record_number.checked_sub(1) {
+            Some(record_index) => Some(Ok(record_index as usize)),
+            None => {
+                self.stack.clear();
+                Some(Err(invalid_index("a key points to record 0")))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Block of the keys lower or equal to this one, 0 in leaf blocks
+    child: u32,
+    /// Number (starting at 1) of the record, only meaningful in leaf blocks
+    record_number: u32,
+    key: Vec<u8>,
+}
+
+struct Block {
+    entries: Vec<Entry>,
+    is_leaf: bool,
+}
+
+impl Block {
+    /// The last entry of interior blocks only holds a child
+    fn num_keys(&self) -> usize {
+        if self.is_leaf {
+            self.entries.len()
+        } else {
+            self.entries.len().saturating_sub(1)
+        }
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &[u8]> {
+        self.entries[..self.num_keys()]
+            .iter()
+            .map(|entry| entry.key.as_slice())
+    }
+}
+
+/// Position in a block of the tree
+struct Cursor {
+    block: Block,
+    position: usize,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::record::field::Date;
+    use crate::FieldType;
+    use byteorder::WriteBytesExt;
+    use std::io::Write;
+
+    /// Builds an index with the given keys, sorted,
+    /// `max_keys` being the maximum number of keys per block
+    fn build_ndx(
+        keys: &[(Vec<u8>, u32)],
+        key_type: u16,
+        key_length: usize,
+        max_keys: usize,
+    ) -> std::io::Cursor<Vec<u8>> {
+        let key_record_size = (key_length + 8).div_ceil(4) * 4;
+        let mut blocks: Vec<Vec<u8>> = vec![vec![]];
+        let write_block =
+            |blocks: &mut Vec<Vec<u8>>, num_keys: usize, entries: &[(u32, u32, &[u8])]| {
+                let mut data = vec![];
+                data.write_u32::<LittleEndian>(num_keys as u32).unwrap();
+                for (child, record_number, key) in entries {
+                    data.write_u32::<LittleEndian>(*child).unwrap();
+                    data.write_u32::<LittleEndian>(*record_number).unwrap();
+                    data.write_all(key).unwrap();
+                    data.resize(data.len() + key_record_size - 8 - key.len(), 0);
+                }
+                data.resize(BLOCK_SIZE, 0);
+                blocks.push(data);
+                blocks.len() as u32 - 1
+            };
+
+        // (block, highest key)
+        let mut level: Vec<(u32, Vec<u8>)> = keys
+            .chunks(max_keys)
+            .map(|chunk| {
+                let entries = chunk
+                    .iter()
+                    .map(|(key, record_number)| (0, *record_number, key.as_slice()))
+                    .collect::<Vec<_>>();
+                let block = write_block(&mut blocks, chunk.len(), &entries);
+                (block, chunk.last().unwrap().0.clone())
+            })
+            .collect();
+        while level.len() > 1 {
+            level = level
+                .chunks(max_keys + 1)
+                .map(|chunk| {
+                    let empty_key = vec![0u8; key_length];
+                    let entries = chunk
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (child, key))| {
+                            let key = if i + 1 == chunk.len() {
+                                &empty_key
+                            } else {
+                                key
+                            };
+                            (*child, 0, key.as_slice())
+                        })
+                        .collect::<Vec<_>>();
+                    let block = write_block(&mut blocks, chunk.len() - 1, &entries);
+                    (block, chunk.last().unwrap().1.clone())
+                })
+                .collect();
+        }
+
+        let mut header = vec![];
+        header.write_u32::<LittleEndian>(level[0].0).unwrap();
+        header
+            .write_u32::<LittleEndian>(blocks.len() as u32)
+            .unwrap();
+        header.write_u32::<LittleEndian>(0).unwrap();
+        header.write_u16::<LittleEndian>(key_length as u16).unwrap();
+        header.write_u16::<LittleEndian>(max_keys as u16).unwrap();
+        header.write_u16::<LittleEndian>(key_type).unwrap();
+        header
+            .write_u16::<LittleEndian>(key_record_size as u16)
+            .unwrap();
+        header.resize(KEY_EXPRESSION_OFFSET, 0);
+        header.write_all(b"NAME").unwrap();
+        header.resize(BLOCK_SIZE, 0);
+        blocks[0] = header;
+        std::io::Cursor::new(blocks.concat())
+    }
+
+    fn names_index() -> NdxIndex<std::io::Cursor<Vec<u8>>> {
+        let names = ["Alex", "Ferrys", "Jamie", "Jamie", "Kim", "Sam", "Yoshi"];
+        let keys = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (format!("{:<8}", name).into_bytes(), i as u32 + 1))
+            .collect::<Vec<_>>();
+        NdxIndex::new(build_ndx(&keys, 0, 8, 2)).unwrap()
+    }
+
+    #[test]
+    fn read_header() {
+        let index = names_index();
+        assert_eq!(index.key_expression(), "NAME");
+        assert_eq!(index.key_type(), KeyType::Character);
+        assert_eq!(index.key_length(), 8);
+        assert!(!index.is_unique());
+    }
+
+    #[test]
+    fn iterate_in_order() {
+        let mut index = names_index();
+        let records = index
+            .iter()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(records, vec![0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn seek_character_keys() {
+        let mut index = names_index();
+        assert_eq!(index.seek("Jamie").unwrap(), Some(2));
+        assert_eq!(index.seek("Ja").unwrap(), Some(2));
+        assert_eq!(index.seek("Yoshi").unwrap(), Some(6));
+        assert_eq!(index.seek("Bob").unwrap(), None);
+        assert_eq!(index.seek("Zed").unwrap(), None);
+        assert!(index.seek(1.0).is_err());
+    }
+
+    #[test]
+    fn range_of_character_keys() {
+        let mut index = names_index();
+        let records = |index: &mut NdxIndex<_>, range: (Bound<&str>, Bound<&str>)| {
+            index
+                .range::<&str, _>(range)
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+        use Bound::*;
+        assert_eq!(
+            records(&mut index, (Included("F"), Excluded("K"))),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            records(&mut index, (Included("F"), Included("K"))),
+            vec![1, 2, 3, 4]
+        );
+        assert_eq!(
+            records(&mut index, (Excluded("Jamie"), Unbounded)),
+            vec![4, 5, 6]
+        );
+        assert_eq!(records(&mut index, (Unbounded, Excluded("Alex"))), vec![]);
+    }
+
+    #[test]
+    fn numeric_and_date_keys() {
+        let dates = [
+            Date::new(1, 1, 2000),
+            Date::new(15, 6, 2010),
+            Date::new(31, 12, 2020),
+        ];
+        let keys = dates
+            .iter()
+            .enumerate()
+            .map(|(i, date)| {
+                let mut key = vec![];
+                key.write_f64::<LittleEndian>(f64::from(date.to_julian_day_number()))
+                    .unwrap();
+                (key, 3 - i as u32)
+            })
+            .collect::<Vec<_>>();
+        let mut data = build_ndx(&keys, 1, 8, 2);
+        data.get_mut()[KEY_EXPRESSION_OFFSET..KEY_EXPRESSION_OFFSET + 4].copy_from_slice(b"BORN");
+        let born = FieldInfo::new(
+            crate::FieldName::try_from("Born").unwrap(),
+            FieldType::Date,
+            8,
+        );
+        let mut index = NdxIndex::new(data).unwrap();
+        assert_eq!(index.key_type(), KeyType::Numeric);
+        index = index.with_table_fields(&[born]);
+        assert_eq!(index.key_type(), KeyType::Date);
+
+        assert_eq!(index.seek(dates[1]).unwrap(), Some(1));
+        assert_eq!(index.seek(2_451_545).unwrap(), Some(2));
+        let records = index
+            .range(Date::new(1, 1, 2005)..)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(records, vec![1, 0]);
+    }
+
+    #[test]
+    fn fetch_records_through_the_reader() {
+        use crate::{FieldName, FieldValue, Reader, Record, TableWriterBuilder};
+        use std::convert::TryFrom;
+
+        let mut dbf = std::io::Cursor::new(Vec::<u8>::new());
+        let mut writer = TableWriterBuilder::new()
+            .add_character_field(FieldName::try_from("NAME").unwrap(), 8)
+            .build_with_dest(&mut dbf);
+        let names = ["Yoshi", "Alex", "Kim"];
+        for name in names {
+            let mut record = Record::default();
+            record.insert(
+                "NAME".to_string(),
+                FieldValue::Character(Some(name.to_string())),
+            );
+            writer.write_record(&record).unwrap();
+        }
+        writer.close().unwrap();
+        drop(writer);
+
+        let mut keys = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (format!("{:<8}", name).into_bytes(), i as u32 + 1))
+            .collect::<Vec<_>>();
+        keys.sort();
+        let mut index = NdxIndex::new(build_ndx(&keys, 0, 8, 2)).unwrap();
+
+        dbf.set_position(0);
+        let mut reader = Reader::new(dbf).unwrap();
+        let mut sorted_names = vec![];
+        for record_index in index.iter().unwrap() {
+            reader.seek(record_index.unwrap()).unwrap();
+            let record = reader.iter_records().next().unwrap().unwrap();
+            sorted_names.push(record.get("NAME").cloned().unwrap());
+        }
+        assert_eq!(
+            sorted_names,
+            vec![
+                FieldValue::Character(Some("Alex".to_string())),
+                FieldValue::Character(Some("Kim".to_string())),
+                FieldValue::Character(Some("Yoshi".to_string())),
+            ]
+        );
+    }
+}
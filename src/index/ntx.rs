@@ -0,0 +1,745 @@
+//! Clipper and Harbour index files (.ntx)
+//!
+//! A .ntx file is a B-tree made of 1024 bytes pages, the first one being the header.
+//! Unlike the other index formats, keys are stored in every page of the tree:
+//! each key comes with the number of its record and the page of the sub-tree
+//! holding the keys lower than it, the last item of a page only pointing to
+//! the sub-tree of the keys greater than all the others.
+//!
+//! Keys are always stored as characters: numbers as they are formatted by `STR()`
+//! (with leading zeros, negative numbers being transformed so that they sort first)
+//! and dates as `DTOS()` does.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # fn main() -> Result<(), dbase::Error> {
+//! use dbase::index::ntx::NtxIndex;
+//!
+//! let mut reader = dbase::Reader::from_path("customers.dbf")?;
+//! let mut index = NtxIndex::from_path("custno.ntx")?.with_table_fields(reader.fields());
+//!
+//! if let Some(record_index) = index.seek(1234)? {
+//!     reader.seek(record_index)?;
+//!     let customer = reader.iter_records().next().unwrap()?;
+//! }
+//!
+//! for record_index in index.iter()? {
+//!     reader.seek(record_index?)?;
+//!     let customer = reader.iter_records().next().unwrap()?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use super::{
+    compare_character_key, index_error, invalid_index, is_above_lower_bound, is_below_upper_bound,
+    key_type_of_expression, IndexKey, KeyType,
+};
+use crate::record::field::Date;
+use crate::{Error, ErrorKind, FieldInfo};
+
+const PAGE_SIZE: usize = 1024;
+/// Items store the page of their sub-tree and the record number, then the key
+const ITEM_HEADER_SIZE: usize = 8;
+/// Guards against cycles in corrupted files
+const MAX_DEPTH: usize = 64;
+
+/// Offsets of the fields of the header
+mod header {
+    pub(super) const SIGNATURE: usize = 0;
+    pub(super) const ROOT: usize = 4;
+    pub(super) const ITEM_SIZE: usize = 12;
+    pub(super) const KEY_SIZE: usize = 14;
+    pub(super) const KEY_DECIMALS: usize = 16;
+    pub(super) const MAX_ITEMS: usize = 18;
+    pub(super) const KEY_EXPRESSION: usize = 22;
+    pub(super) const UNIQUE: usize = 278;
+    pub(super) const DESCENDING: usize = 280;
+    pub(super) const FOR_EXPRESSION: usize = 282;
+    pub(super) const EXPRESSION_LENGTH: usize = 256;
+}
+
+/// Flags of the signature of the header
+mod signature {
+    /// Harbour stores page numbers instead of offsets, to address files larger than 4GB
+    pub(super) const LARGE_FILE: u16 = 0x0040;
+    /// Harbour files holding several tags, which use a different layout
+    pub(super) const COMPOUND: u16 = 0x8000;
+}
+
+/// A Clipper or Harbour index file
+pub struct NtxIndex<T: Read + Seek> {
+    source: T,
+    root_page: u32,
+    key_length: usize,
+    key_decimals: usize,
+    item_size: usize,
+    max_items: usize,
+    large_file: bool,
+    key_type: KeyType,
+    unique: bool,
+    descending: bool,
+    key_expression: String,
+    filter_expression: Option<String>,
+}
+
+impl<T: Read + Seek> NtxIndex<T> {
+    /// Reads the header of the index from the source
+    pub fn new(mut source: T) -> Result<Self, Error> {
+        let mut header = [0u8; PAGE_SIZE];
+        source
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| source.read_exact(&mut header))
+            .map_err(|error| Error::io_error(error, 0))?;
+
+        let signature = LittleEndian::read_u16(&header[header::SIGNATURE..]);
+        let root_page = LittleEndian::read_u32(&header[header::ROOT..]);
+        let item_size = LittleEndian::read_u16(&header[header::ITEM_SIZE..]) as usize;
+        let key_length = LittleEndian::read_u16(&header[header::KEY_SIZE..]) as usize;
+        let key_decimals = LittleEndian::read_u16(&header[header::KEY_DECIMALS..]) as usize;
+        let max_items = LittleEndian::read_u16(&header[header::MAX_ITEMS..]) as usize;
+        if signature & signature::COMPOUND != 0 {
+            return Err(invalid_index("multiple tags .ntx files are not supported"));
+        }
+        // The page starts with the number of keys and the offsets of the items
+        let items_start = 2 + 2 * (max_items + 1);
+        if root_page == 0
+            || key_length == 0
+            || item_size < key_length + ITEM_HEADER_SIZE
+            || max_items == 0
+            || items_start + (max_items + 1) * item_size > PAGE_SIZE
+        {
+            return Err(invalid_index("unexpected header values"));
+        }
+
+        let expression_at = |offset: usize| {
+            let expression = &header[offset..offset + header::EXPRESSION_LENGTH];
+            let expression_end = expression
+                .iter()
+                .position(|byte| *byte == 0)
+                .unwrap_or(expression.len());
+            String::from_utf8_lossy(&expression[..expression_end])
+                .trim()
+                .to_string()
+        };
+        let key_expression = expression_at(header::KEY_EXPRESSION);
+        let filter_expression =
+            Some(expression_at(header::FOR_EXPRESSION)).filter(|expression| !expression.is_empty());
+
+        Ok(Self {
+            source,
+            root_page,
+            key_length,
+            key_decimals,
+            item_size,
+            max_items,
+            large_file: signature & signature::LARGE_FILE != 0,
+            key_type: KeyType::Character,
+            unique: header[header::UNIQUE] != 0,
+            descending: header[header::DESCENDING] != 0,
+            key_expression,
+            filter_expression,
+        })
+    }
+
+    /// Returns the expression evaluated to compute the keys (e.g. `UPPER(NAME)`)
+    pub fn key_expression(&self) -> &str {
+        &self.key_expression
+    }
+
+    /// Returns the `FOR` expression selecting the indexed records, if there is one
+    pub fn filter_expression(&self) -> Option<&str> {
+        self.filter_expression.as_deref()
+    }
+
+    /// Returns the type of the keys
+    ///
+    /// NTX files store all keys as characters, [KeyType::Numeric] and [KeyType::Date]
+    /// are only returned once [with_table_fields](Self::with_table_fields) found out
+    /// the type of the key.
+    pub fn key_type(&self) -> KeyType {
+        self.key_type
+    }
+
+    /// Returns the length in bytes of the keys
+    pub fn key_length(&self) -> usize {
+        self.key_length
+    }
+
+    /// Returns the number of decimals of numeric keys
+    pub fn key_decimals(&self) -> usize {
+        self.key_decimals
+    }
+
+    /// Returns whether the index only holds the first record of each key
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
+    /// Returns whether the keys are sorted from the highest to the lowest
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+
+    /// Uses the fields of the indexed table to find the type of the keys
+    ///
+    /// The type is known when the key expression is the name of a field,
+    /// numeric and date keys can then be searched with numbers and dates.
+    pub fn with_table_fields(mut self, fields: &[FieldInfo]) -> Self {
+        if let Some(key_type) = key_type_of_expression(&self.key_expression, fields) {
+            self.key_type = key_type;
+        }
+        self
+    }
+
+    /// Returns the index of the first record (in the order of the index)
+    /// whose key matches the given one
+    ///
+    /// Character keys match the keys that start with them, and can be used
+    /// whatever the type of the index is.
+    ///
+    /// The returned index can be given to [Reader::seek](crate::Reader::seek).
+    pub fn seek<K: Into<IndexKey>>(&mut self, key: K) -> Result<Option<usize>, Error> {
+        let key = key.into();
+        self.range(key.clone()..=key)?.next().transpose()
+    }
+
+    /// Returns an iterator over the indices of the records whose keys are in the range,
+    /// in the order of the index
+    pub fn range<K, R>(&mut self, range: R) -> Result<NtxIter<'_, T>, Error>
+    where
+        K: Into<IndexKey> + Clone,
+        R: RangeBounds<K>,
+    {
+        let encode = |bound: Bound<&K>| -> Result<Bound<Vec<u8>>, Error> {
+            Ok(match bound {
+                Bound::Included(key) => Bound::Included(self.encode_key(&key.clone().into())?),
+                Bound::Excluded(key) => Bound::Excluded(self.encode_key(&key.clone().into())?),
+                Bound::Unbounded => Bound::Unbounded,
+            })
+        };
+        let start = encode(range.start_bound())?;
+        let end = encode(range.end_bound())?;
+        // The tree of descending indexes goes from the highest to the lowest key
+        let (start, end) = if self.descending {
+            (end, start)
+        } else {
+            (start, end)
+        };
+
+        let mut iter = NtxIter {
+            index: self,
+            stack: vec![],
+            end,
+        };
+        let root_page = iter.index.root_page;
+        iter.descend(root_page, start.as_ref())?;
+        Ok(iter)
+    }
+
+    /// Returns an iterator over the indices of all the records, in the order of the index
+    pub fn iter(&mut self) -> Result<NtxIter<'_, T>, Error> {
+        self.range::<IndexKey, _>(..)
+    }
+
+    /// Converts a key to the characters stored in the index
+    fn encode_key(&self, key: &IndexKey) -> Result<Vec<u8>, Error> {
+        if let IndexKey::Character(bytes) = key {
+            return Ok(bytes.clone());
+        }
+        key.check_compatible_with(self.key_type)
+            .map_err(index_error)?;
+        match (self.key_type, key) {
+            (KeyType::Date, IndexKey::Date(date)) => Ok(date.to_string().into_bytes()),
+            (KeyType::Date, IndexKey::Numeric(number)) => {
+                let date = Date::julian_day_number_to_gregorian_date(*number as i32);
+                Ok(date.to_string().into_bytes())
+            }
+            (_, key) => match key.as_number() {
+                Some(number) => Ok(encode_number(number, self.key_length, self.key_decimals)),
+                None => Err(index_error(ErrorKind::IncompatibleType)),
+            },
+        }
+    }
+
+    /// Compares a stored key with an encoded one, in the order of the tree
+    fn compare(&self, stored: &[u8], searched: &[u8]) -> Ordering {
+        let ordering = compare_character_key(stored, searched);
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    fn read_page(&mut self, page: u32) -> Result<Page, Error> {
+        let offset = if self.large_file {
+            u64::from(page) * PAGE_SIZE as u64
+        } else {
+            u64::from(page)
+        };
+        if offset < PAGE_SIZE as u64 {
+            return Err(invalid_index("a page points to the header"));
+        }
+        let mut data = [0u8; PAGE_SIZE];
+        self.source
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| self.source.read_exact(&mut data))
+            .map_err(|error| Error::io_error(error, 0))?;
+
+        let num_keys = LittleEndian::read_u16(&data) as usize;
+        if num_keys > self.max_items {
+            return Err(invalid_index("a page holds too many keys"));
+        }
+        // The last item only holds the page of the greatest keys
+        let items = (0..=num_keys)
+            .map(|i| {
+                let start = LittleEndian::read_u16(&data[2 + 2 * i..]) as usize;
+                let item = data
+                    .get(start..start + self.item_size)
+                    .ok_or_else(|| invalid_index("an item is outside of its page"))?;
+                Ok(Item {
+                    child: LittleEndian::read_u32(&item[0..4]),
+                    record_number: LittleEndian::read_u32(&item[4..8]),
+                    key: item[ITEM_HEADER_SIZE..ITEM_HEADER_SIZE + self.key_length].to_vec(),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(Page { items })
+    }
+}
+
+impl NtxIndex<BufReader<File>> {
+    /// Opens the index file at the given path
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path).map_err(|error| Error::io_error(error, 0))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+/// Formats a number the way Clipper stores it in keys
+///
+/// Leading spaces are replaced by zeros, and the digits of negative numbers
+/// are mapped below `'0'` in reverse order so that the keys sort as the numbers.
+fn encode_number(number: f64, length: usize, decimals: usize) -> Vec<u8> {
+    let mut key = format!("{:>length$.decimals$}", number).into_bytes();
+    if key.len() > length {
+        // Too large for the key, like the `*` that STR() returns
+        let filler = if number < 0.0 { b'#' } else { b'9' };
+        return vec![filler; length];
+    }
+    let num_spaces = key.iter().take_while(|byte| **byte == b' ').count();
+    key[..num_spaces].fill(b'0');
+    if key.get(num_spaces) == Some(&b'-') {
+        key[num_spaces] = b'0';
+        for byte in key.iter_mut().filter(|byte| byte.is_ascii_digit()) {
+            *byte = b'0' - (*byte - b'0') - 4;
+        }
+    }
+    key
+}
+
+/// Iterator over the indices of the records, in the order of an [NtxIndex]
+///
+/// The indices can be given to [Reader::seek](crate::Reader::seek).
+pub struct NtxIter<'a, T: Read + Seek> {
+    index: &'a mut NtxIndex<T>,
+    /// Path from the root to the next key,
+    /// the sub-trees on the left of the positions being already visited
+    stack: Vec<Cursor>,
+    end: Bound<Vec<u8>>,
+}
+
+impl<T: Read + Seek> NtxIter<'_, T> {
+    /// Goes down the tree from `page` to the first key above the lower bound
+    fn descend(&mut self, mut page: u32, start: Bound<&Vec<u8>>) -> Result<(), Error> {
+        loop {
+            if self.stack.len() > MAX_DEPTH {
+                return Err(invalid_index("the tree is too deep"));
+            }
+            let page_data = self.index.read_page(page)?;
+            let index = &*self.index;
+            let position = page_data
+                .keys()
+                .position(|stored| is_above_lower_bound(start, |key| index.compare(stored, key)))
+                .unwrap_or(page_data.num_keys());
+            let child = page_data.items[position].child;
+            self.stack.push(Cursor {
+                page: page_data,
+                position,
+            });
+            if child == 0 {
+                return Ok(());
+            }
+            page = child;
+        }
+    }
+
+    fn next_item(&mut self) -> Result<Option<Item>, Error> {
+        loop {
+            let Some(cursor) = self.stack.last_mut() else {
+                return Ok(None);
+            };
+            if cursor.position < cursor.page.num_keys() {
+                let item = cursor.page.items[cursor.position].clone();
+                cursor.position += 1;
+                let child = cursor.page.items[cursor.position].child;
+                if child != 0 {
+                    self.descend(child, Bound::Unbounded)?;
+                }
+                return Ok(Some(item));
+            }
+            self.stack.pop();
+        }
+    }
+}
+
+impl<T: Read + Seek> Iterator for NtxIter<'_, T> {
+    type Item = Result<usize, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = match self.next_item() {
+            Ok(item) => item?,
+            Err(error) => {
+                self.stack.clear();
+                return Some(Err(error));
+            }
+        };
+        let index = &*self.index;
+        if !is_below_upper_bound(self.end.as_ref(), |key| index.compare(&item.key, key)) {
+            self.stack.clear();
+            return None;
+        }
+        match item.record_number.checked_sub(1) {
+            Some(record_index) => Some(Ok(record_index as usize)),
+            None => {
+                self.stack.clear();
+                Some(Err(invalid_index("a key points to record 0")))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Item {
+    /// Page of the keys lower than this one, 0 when there are none
+    child: u32,
+    /// Number (starting at 1) of the record
+    record_number: u32,
+    key: Vec<u8>,
+}
+
+struct Page {
+    items: Vec<Item>,
+}
+
+impl Page {
+    /// The last item only holds a child
+    fn num_keys(&self) -> usize {
+        self.items.len() - 1
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &[u8]> {
+        self.items[..self.num_keys()]
+            .iter()
+            .map(|item| item.key.as_slice())
+    }
+}
+
+/// Position in a page of the tree
+struct Cursor {
+    page: Page,
+    position: usize,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FieldType;
+    use byteorder::WriteBytesExt;
+    use std::io::Write;
+
+    struct NtxBuilder {
+        key_length: usize,
+        max_items: usize,
+        pages: Vec<Vec<u8>>,
+    }
+
+    impl NtxBuilder {
+        /// Writes the sorted keys as a tree whose pages hold at most `max_items` keys,
+        /// returns the offset of its root
+        fn write_tree(&mut self, keys: &[(Vec<u8>, u32)]) -> u32 {
+            let (separators, children) = if keys.len() <= self.max_items {
+                (keys.to_vec(), vec![0; keys.len() + 1])
+            } else {
+                // Keys are spread between the sub-trees, around the separators
+                let num_separators = self.max_items.min((keys.len() - 1) / 2);
+                let num_children = num_separators + 1;
+                let child_keys = keys.len() - num_separators;
+                let mut separators = vec![];
+                let mut children = vec![];
+                let mut rest = keys;
+                for i in 0..num_children {
+                    let size =
+                        child_keys / num_children + usize::from(i < child_keys % num_children);
+                    children.push(self.write_tree(&rest[..size]));
+                    if let Some(separator) = rest.get(size).filter(|_| i < num_separators) {
+                        separators.push(separator.clone());
+                    }
+                    rest = &rest[(size + 1).min(rest.len())..];
+                }
+                (separators, children)
+            };
+
+            let item_size = self.key_length + ITEM_HEADER_SIZE;
+            let items_start = 2 + 2 * (self.max_items + 1);
+            let mut page = vec![];
+            page.write_u16::<LittleEndian>(separators.len() as u16)
+                .unwrap();
+            for i in 0..=self.max_items {
+                page.write_u16::<LittleEndian>((items_start + i * item_size) as u16)
+                    .unwrap();
+            }
+            let empty_key = vec![0u8; self.key_length];
+            for (i, child) in children.iter().enumerate() {
+                let (key, record_number) = separators
+                    .get(i)
+                    .map(|(key, record_number)| (key, *record_number))
+                    .unwrap_or((&empty_key, 0));
+                page.write_u32::<LittleEndian>(*child).unwrap();
+                page.write_u32::<LittleEndian>(record_number).unwrap();
+                page.write_all(key).unwrap();
+            }
+            page.resize(PAGE_SIZE, 0);
+            self.pages.push(page);
+            ((self.pages.len() - 1) * PAGE_SIZE) as u32
+        }
+    }
+
+    /// Builds an index with the given keys, sorted in the order of the index
+    fn build_ntx(
+        keys: &[(Vec<u8>, u32)],
+        key_length: usize,
+        key_decimals: usize,
+        max_items: usize,
+        descending: bool,
+    ) -> std::io::Cursor<Vec<u8>> {
+        let mut builder = NtxBuilder {
+            key_length,
+            max_items,
+            pages: vec![vec![]],
+        };
+        let root = builder.write_tree(keys);
+
+        let mut header = vec![];
+        header.write_u16::<LittleEndian>(6).unwrap();
+        header.write_u16::<LittleEndian>(1).unwrap();
+        header.write_u32::<LittleEndian>(root).unwrap();
+        header.write_u32::<LittleEndian>(0).unwrap();
+        header
+            .write_u16::<LittleEndian>((key_length + ITEM_HEADER_SIZE) as u16)
+            .unwrap();
+        header.write_u16::<LittleEndian>(key_length as u16).unwrap();
+        header
+            .write_u16::<LittleEndian>(key_decimals as u16)
+            .unwrap();
+        header.write_u16::<LittleEndian>(max_items as u16).unwrap();
+        header
+            .write_u16::<LittleEndian>((max_items / 2) as u16)
+            .unwrap();
+        header.write_all(b"NAME").unwrap();
+        header.resize(header::DESCENDING, 0);
+        header.push(u8::from(descending));
+        header.resize(PAGE_SIZE, 0);
+        builder.pages[0] = header;
+        std::io::Cursor::new(builder.pages.concat())
+    }
+
+    fn names_index(descending: bool) -> NtxIndex<std::io::Cursor<Vec<u8>>> {
+        let mut names = [
+            "Alex", "Ferrys", "Jamie", "Jamie", "Kim", "Sam", "Yoshi", "Zed",
+        ];
+        if descending {
+            names.reverse();
+        }
+        let keys = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (format!("{:<8}", name).into_bytes(), i as u32 + 1))
+            .collect::<Vec<_>>();
+        NtxIndex::new(build_ntx(&keys, 8, 0, 2, descending)).unwrap()
+    }
+
+    fn collect(iter: NtxIter<'_, std::io::Cursor<Vec<u8>>>) -> Vec<usize> {
+        iter.collect::<Result<Vec<_>, _>>().unwrap()
+    }
+
+    #[test]
+    fn read_header() {
+        let index = names_index(false);
+        assert_eq!(index.key_expression(), "NAME");
+        assert_eq!(index.filter_expression(), None);
+        assert_eq!(index.key_type(), KeyType::Character);
+        assert_eq!(index.key_length(), 8);
+        assert!(!index.is_unique());
+        assert!(!index.is_descending());
+    }
+
+    #[test]
+    fn iterate_and_seek_character_keys() {
+        let mut index = names_index(false);
+        assert_eq!(collect(index.iter().unwrap()), vec![0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(index.seek("Jamie").unwrap(), Some(2));
+        assert_eq!(index.seek("K").unwrap(), Some(4));
+        assert_eq!(index.seek("Zed").unwrap(), Some(7));
+        assert_eq!(index.seek("Bob").unwrap(), None);
+        assert!(index.seek(1.0).is_err());
+        assert_eq!(collect(index.range("F".."K").unwrap()), vec![1, 2, 3]);
+        assert_eq!(
+            collect(index.range("Jamie"..).unwrap()),
+            vec![2, 3, 4, 5, 6, 7]
+        );
+        assert_eq!(collect(index.range(.."Alex").unwrap()), vec![]);
+    }
+
+    #[test]
+    fn descending_keys() {
+        let mut index = names_index(true);
+        assert!(index.is_descending());
+        assert_eq!(collect(index.iter().unwrap()), vec![0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(index.seek("Kim").unwrap(), Some(3));
+        assert_eq!(collect(index.range("F".."K").unwrap()), vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn numeric_and_date_keys() {
+        let numbers = [-20.5, -3.25, -1.0, 0.0, 0.5, 42.0, 1000.0];
+        let mut encoded = numbers
+            .iter()
+            .map(|number| encode_number(*number, 8, 2))
+            .collect::<Vec<_>>();
+        assert_eq!(encoded[2], b",,,,+.,,".to_vec());
+        assert_eq!(encoded[5], b"00042.00".to_vec());
+        let sorted = encoded.clone();
+        encoded.sort();
+        assert_eq!(encoded, sorted);
+
+        // Records are stored in the reverse order of the amounts
+        let keys = encoded
+            .into_iter()
+            .enumerate()
+            .map(|(i, key)| (key, (numbers.len() - i) as u32))
+            .collect::<Vec<_>>();
+        let mut data = build_ntx(&keys, 8, 2, 3, false);
+        data.get_mut()[header::KEY_EXPRESSION..header::KEY_EXPRESSION + 4].copy_from_slice(b"AMNT");
+        let amount = FieldInfo::new(
+            crate::FieldName::try_from("Amnt").unwrap(),
+            FieldType::Numeric,
+            8,
+        );
+        let mut index = NtxIndex::new(data).unwrap();
+        assert_eq!(index.key_decimals(), 2);
+        assert!(index.seek(42.0).is_err());
+        index = index.with_table_fields(&[amount]);
+        assert_eq!(index.key_type(), KeyType::Numeric);
+        assert_eq!(index.seek(42.0).unwrap(), Some(1));
+        assert_eq!(index.seek(-3.25).unwrap(), Some(5));
+        assert_eq!(index.seek(7).unwrap(), None);
+        assert_eq!(collect(index.range(-2.0..1.0).unwrap()), vec![4, 3, 2]);
+
+        let dates = [Date::new(1, 1, 2000), Date::new(15, 6, 2010)];
+        let keys = dates
+            .iter()
+            .enumerate()
+            .map(|(i, date)| (date.to_string().into_bytes(), i as u32 + 1))
+            .collect::<Vec<_>>();
+        let mut data = build_ntx(&keys, 8, 0, 2, false);
+        data.get_mut()[header::KEY_EXPRESSION..header::KEY_EXPRESSION + 4].copy_from_slice(b"BORN");
+        let born = FieldInfo::new(
+            crate::FieldName::try_from("Born").unwrap(),
+            FieldType::Date,
+            8,
+        );
+        let mut index = NtxIndex::new(data).unwrap().with_table_fields(&[born]);
+        assert_eq!(index.seek(dates[1]).unwrap(), Some(1));
+        assert_eq!(index.seek(2_451_545).unwrap(), Some(0));
+        assert_eq!(index.seek("2010").unwrap(), Some(1));
+    }
+
+    #[test]
+    fn fetch_records_with_reader_seek() {
+        let names = ["Sam", "Alex", "Zed", "Kim"];
+        let mut table = std::io::Cursor::new(Vec::<u8>::new());
+        let mut writer = crate::TableWriterBuilder::new()
+            .add_character_field("Name".try_into().unwrap(), 8)
+            .build_with_dest(&mut table);
+        for name in names {
+            let mut record = crate::Record::default();
+            record.insert(
+                "Name".to_string(),
+                crate::FieldValue::Character(Some(name.to_string())),
+            );
+            writer.write_record(&record).unwrap();
+        }
+        writer.close().unwrap();
+        drop(writer);
+        table.set_position(0);
+
+        let mut keys = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (format!("{:<8}", name).into_bytes(), i as u32 + 1))
+            .collect::<Vec<_>>();
+        keys.sort();
+        let mut index = NtxIndex::new(build_ntx(&keys, 8, 0, 2, false)).unwrap();
+        let mut reader = crate::Reader::new(table).unwrap();
+        let mut name_at = |record_index: usize| {
+            reader.seek(record_index).unwrap();
+            let mut record = reader.iter_records().next().unwrap().unwrap();
+            record.remove("Name").unwrap()
+        };
+
+        let kim = index.seek("Kim").unwrap().unwrap();
+        assert_eq!(
+            name_at(kim),
+            crate::FieldValue::Character(Some("Kim".to_string()))
+        );
+        let sorted = collect(index.iter().unwrap())
+            .into_iter()
+            .map(&mut name_at)
+            .collect::<Vec<_>>();
+        let expected = ["Alex", "Kim", "Sam", "Zed"]
+            .iter()
+            .map(|name| crate::FieldValue::Character(Some(name.to_string())))
+            .collect::<Vec<_>>();
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn deep_trees() {
+        let keys = (0..500u32)
+            .map(|i| (format!("{:05}", i).into_bytes(), 500 - i))
+            .collect::<Vec<_>>();
+        let mut index = NtxIndex::new(build_ntx(&keys, 5, 0, 4, false)).unwrap();
+        let records = collect(index.iter().unwrap());
+        assert_eq!(records, (0..500).rev().collect::<Vec<_>>());
+        for i in [0, 1, 250, 498, 499] {
+            assert_eq!(
+                index.seek(format!("{:05}", i)).unwrap(),
+                Some(499 - i as usize)
+            );
+        }
+        assert_eq!(
+            collect(index.range("00100".."00103").unwrap()),
+            vec![399, 398, 397]
+        );
+    }
+}
@@ -147,11 +147,23 @@
 //! # }
 //! ```
 //!
+//! The double-byte codepages of Japanese, Chinese and Korean (CP932, CP936, CP949 and CP950)
+//! are provided via the crate `encoding_rs`, with the feature of the same name.
+//!
 //! The functions that do not take an encoding as parameter, use [`UnicodeLossy`] by default,
 //! they try to read all data as Unicode and replace unrepresentable characters with the unicode
 //! replacement character. Alternatively [`Unicode`] is available, to return an [`Err`] when data
 //! can't be represented as Unicode.
 //!
+//! ## Decoding on several threads
+//!
+//! With the `rayon` feature, `Reader::par_iter_records_as` decodes the records
+//! on the threads of the rayon pool. The records are still decoded on the calling thread
+//! when the table has memo fields, as the memo file is read from one place at a time,
+//! and when the encoding of the reader is not known to be `Send`, which is the case
+//! of the encodings given to [`Reader::new_with_encoding`] and the like
+//! (`ParallelRecordIterator::with_encoding` gives them again as `Send`).
+//!
 //! # Writing
 //!
 //! In order to get a [TableWriter](struct.TableWriter.html) you will need to build it using
@@ -266,21 +278,39 @@ mod ser;
 #[cfg(feature = "yore")]
 pub use yore;
 
+#[cfg(feature = "yore")]
+mod detection;
 pub mod encoding;
 mod error;
+pub mod expression;
 mod header;
+pub mod index;
+#[cfg(feature = "mmap")]
+mod mapped;
+#[cfg(feature = "rayon")]
+mod parallel;
 mod reading;
 mod record;
+mod table;
 mod writing;
 
+#[cfg(feature = "yore")]
+pub use crate::detection::DetectedEncoding;
 pub use crate::encoding::{Encoding, Unicode, UnicodeLossy};
 pub use crate::error::{Error, ErrorKind, FieldIOError};
 pub use crate::header::CodePageMark;
+#[cfg(feature = "mmap")]
+pub use crate::mapped::{FieldRef, MappedFile, MappedReader, RecordRef, RecordRefIterator};
+#[cfg(feature = "rayon")]
+pub use crate::parallel::ParallelRecordIterator;
 pub use crate::reading::{
-    read, FieldIterator, NamedValue, ReadableRecord, Reader, Record, RecordIterator, TableInfo,
+    read, DecodeDiagnostic, DecodeErrorPolicy, DeletionPolicy, FieldIterator, FilterIterator,
+    NamedValue, ProjectedRecordIterator, ReadableRecord, Reader, Record, RecordIterator,
+    RecordMeta, RecordWithMetaIterator, TableInfo, TagRecordIterator,
 };
 pub use crate::record::field::{Date, DateTime, FieldType, FieldValue, Time};
-pub use crate::record::{FieldConversionError, FieldInfo, FieldName};
+pub use crate::record::{FieldConversionError, FieldFlags, FieldInfo, FieldName};
+pub use crate::table::Table;
 pub use crate::writing::{FieldWriter, TableWriter, TableWriterBuilder, WritableRecord};
 
 /// macro to define a struct that implements the ReadableRecord and WritableRecord
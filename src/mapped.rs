@@ -0,0 +1,472 @@
+//! Zero-copy reading of memory mapped dBase files
+//!
+//! The [MappedReader] gives access to the records of a file without reading them
+//! into buffers: each [RecordRef] borrows the bytes of its record from the mapping,
+//! and each [FieldRef] the bytes of its field, character fields made of ASCII
+//! being read without allocation.
+//!
+//! # Example
+//!
+//! ```
+//! # fn main() -> Result<(), dbase::Error> {
+//! // SAFETY: the file is not modified while it is mapped
+//! let reader = unsafe { dbase::MappedReader::from_path("tests/data/stations.dbf")? };
+//! let name = reader.field_index("name").unwrap();
+//! for record in reader.iter_records() {
+//!     let station = record.field(name).unwrap().as_str()?;
+//!     println!("{}", station);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::encoding::DynEncoding;
+use crate::header::Header;
+use crate::reading::{null_flags_position, read_cpg_file, DELETED_RECORD_MARKER};
+use crate::record::field::{trim_field_data, MemoReader};
+use crate::{Encoding, Error, ErrorKind, FieldIOError, FieldInfo, FieldValue, Reader};
+
+/// Reader of the records of a dBase file held in memory, usually a [MappedFile]
+///
+/// Any type giving access to the bytes of the file can be used,
+/// for example a `Vec<u8>` or the mapping of another crate.
+///
+/// Memo fields cannot be read, only the index of their data in the memo file is available.
+pub struct MappedReader<D: AsRef<[u8]> = MappedFile> {
+    data: D,
+    header: Header,
+    layout: RecordLayout,
+    records_start: usize,
+    record_size: usize,
+    num_records: usize,
+}
+
+/// Where the fields are in the records, shared by the [RecordRef]s
+struct RecordLayout {
+    /// The fields of the records, without the hidden ones
+    fields_info: Vec<FieldInfo>,
+    /// Offset of the fields in the records
+    field_offsets: Vec<usize>,
+    null_flags_position: Option<usize>,
+    encoding: DynEncoding,
+}
+
+impl<D: AsRef<[u8]>> MappedReader<D> {
+    /// Creates a reader of the dBase file whose bytes are `data`
+    ///
+    /// Reads the header and fields information, and checks that the file holds all its records.
+    pub fn new(data: D) -> Result<Self, Error> {
+        Self::new_impl(data, None)
+    }
+
+    /// Creates a reader of the dBase file whose bytes are `data`, strings being decoded
+    /// with `encoding` or the encoding of the code page mark of the file when there is none
+    fn new_impl(data: D, encoding: Option<DynEncoding>) -> Result<Self, Error> {
+        let Reader {
+            header,
+            fields_info: all_fields,
+            encoding,
+            ..
+        } = Reader::new_impl(Cursor::new(data.as_ref()), encoding)?;
+
+        let null_flags_position = null_flags_position(&all_fields);
+        let mut fields_info = Vec::with_capacity(all_fields.len());
+        let mut field_offsets = Vec::with_capacity(all_fields.len());
+        let mut offset = 0;
+        for field in all_fields {
+            let length = usize::from(field.field_length);
+            if !field.is_hidden() {
+                fields_info.push(field);
+                field_offsets.push(offset);
+            }
+            offset += length;
+        }
+
+        let records_start = usize::from(header.offset_to_first_record);
+        // Like the Reader, trust the fields rather than the size of records given by the header
+        let record_size = offset;
+        let num_records = header.num_records as usize;
+        if records_start + num_records * record_size > data.as_ref().len() {
+            let error = std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "the file does not hold all its records",
+            );
+            return Err(Error::io_error(error, 0));
+        }
+
+        Ok(Self {
+            data,
+            header,
+            layout: RecordLayout {
+                fields_info,
+                field_offsets,
+                null_flags_position,
+                encoding,
+            },
+            records_start,
+            record_size,
+            num_records,
+        })
+    }
+
+    /// Returns the header of the file
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Returns the fields of the records
+    ///
+    /// Unlike [Reader::fields](crate::Reader::fields), the deletion flag
+    /// and the `_NullFlags` of Visual FoxPro are not part of them.
+    pub fn fields(&self) -> &[FieldInfo] {
+        &self.layout.fields_info
+    }
+
+    /// Returns the index of the field with the given name, to use with [RecordRef::field]
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        self.layout
+            .fields_info
+            .iter()
+            .position(|field| field.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the number of records of the file
+    pub fn num_records(&self) -> usize {
+        self.num_records
+    }
+
+    /// Sets the encoding used to decode character fields
+    pub fn set_encoding<E: Encoding + 'static>(&mut self, encoding: E) {
+        self.layout.encoding = DynEncoding::new(encoding);
+    }
+
+    /// Returns the record at `index`, whether it is deleted or not
+    pub fn record(&self, index: usize) -> Result<RecordRef<'_>, Error> {
+        if index >= self.num_records {
+            return Err(Error {
+                record_num: index,
+                field: None,
+                kind: ErrorKind::RecordIndexOutOfRange(index),
+            });
+        }
+        let start = self.records_start + index * self.record_size;
+        Ok(RecordRef {
+            data: &self.data.as_ref()[start..start + self.record_size],
+            index,
+            layout: &self.layout,
+        })
+    }
+
+    /// Returns an iterator over all the records, deleted ones included
+    pub fn iter_records(&self) -> RecordRefIterator<'_> {
+        let end = self.records_start + self.num_records * self.record_size;
+        RecordRefIterator {
+            records: self.data.as_ref()[self.records_start..end].chunks_exact(self.record_size),
+            index: 0,
+            layout: &self.layout,
+        }
+    }
+}
+
+impl MappedReader<MappedFile> {
+    /// Maps the file at the given path and creates a reader of it
+    ///
+    /// Like [Reader::from_path], when a `.cpg` file is next to the table, the encoding it names
+    /// is used instead of the one of the code page mark of the file.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified, by this process or another one, while it is mapped.
+    pub unsafe fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let encoding = read_cpg_file(path.as_ref())?;
+        let file = MappedFile::open(path).map_err(|error| Error::io_error(error, 0))?;
+        Self::new_impl(file, encoding)
+    }
+}
+
+/// View of a record, borrowing its bytes
+#[derive(Clone, Copy)]
+pub struct RecordRef<'a> {
+    data: &'a [u8],
+    index: usize,
+    layout: &'a RecordLayout,
+}
+
+impl<'a> RecordRef<'a> {
+    /// Returns the index of the record in the file, starting at 0
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns whether the record is marked as deleted
+    pub fn is_deleted(&self) -> bool {
+        self.data.first() == Some(&DELETED_RECORD_MARKER)
+    }
+
+    /// Returns the bytes of the whole record, deletion flag included
+    pub fn bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns the field at `index` in the [fields](MappedReader::fields) of the file
+    pub fn field(&self, index: usize) -> Option<FieldRef<'a>> {
+        let info = self.layout.fields_info.get(index)?;
+        let start = self.layout.field_offsets[index];
+        let mut bytes = &self.data[start..start + usize::from(info.field_length)];
+        if self.is_flag_set(info.varlength_bit) {
+            // The last byte holds the length actually used
+            let length = bytes.last().copied().unwrap_or(0);
+            bytes = &bytes[..usize::from(length).min(bytes.len().saturating_sub(1))];
+        }
+        Some(FieldRef {
+            info,
+            bytes,
+            is_null: self.is_flag_set(info.null_bit),
+            record_index: self.index,
+            encoding: &self.layout.encoding,
+        })
+    }
+
+    /// Returns the field with the given name
+    pub fn field_by_name(&self, name: &str) -> Option<FieldRef<'a>> {
+        let index = self
+            .layout
+            .fields_info
+            .iter()
+            .position(|field| field.name.eq_ignore_ascii_case(name))?;
+        self.field(index)
+    }
+
+    /// Returns whether the `bit` of the `_NullFlags` of the record is set
+    fn is_flag_set(&self, bit: Option<u16>) -> bool {
+        match (bit, self.layout.null_flags_position) {
+            (Some(bit), Some(position)) => self
+                .data
+                .get(position + usize::from(bit / 8))
+                .is_some_and(|byte| byte & (1 << (bit % 8)) != 0),
+            _ => false,
+        }
+    }
+}
+
+/// View of a field of a record, borrowing its bytes
+#[derive(Clone, Copy)]
+pub struct FieldRef<'a> {
+    info: &'a FieldInfo,
+    bytes: &'a [u8],
+    is_null: bool,
+    record_index: usize,
+    encoding: &'a DynEncoding,
+}
+
+impl<'a> FieldRef<'a> {
+    /// Returns the information of the field
+    pub fn info(&self) -> &'a FieldInfo {
+        self.info
+    }
+
+    /// Returns the bytes of the field, as stored in the file
+    ///
+    /// Only the bytes actually used by Visual FoxPro variable length fields are returned.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns whether the field is null (Visual FoxPro nullable fields)
+    pub fn is_null(&self) -> bool {
+        self.is_null
+    }
+
+    /// Returns the text of the field, without the blanks around it
+    ///
+    /// Text made of printable ASCII characters, which the code pages of dBase files
+    /// all read the same way, is borrowed from the file whatever the encoding is.
+    /// Other text is decoded with the encoding of the reader.
+    pub fn as_str(&self) -> Result<Cow<'a, str>, Error> {
+        if self.is_null {
+            return Ok(Cow::Borrowed(""));
+        }
+        let bytes = trim_field_data(self.bytes);
+        if bytes.iter().all(|byte| (b' '..=b'~').contains(byte)) {
+            if let Ok(text) = std::str::from_utf8(bytes) {
+                return Ok(Cow::Borrowed(text));
+            }
+        }
+        self.encoding
+            .decode(bytes)
+            .map_err(|error| self.error(error.into()))
+    }
+
+    /// Decodes the value of the field
+    ///
+    /// Memo fields cannot be decoded, as the memo file is not read.
+    pub fn value(&self) -> Result<FieldValue, Error> {
+        if self.is_null {
+            return Ok(FieldValue::null(self.info.field_type));
+        }
+        let mut memo_reader = None::<MemoReader<File>>;
+        FieldValue::read_from(self.bytes, &mut memo_reader, self.info, self.encoding)
+            .map_err(|kind| self.error(kind))
+    }
+
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error::new(
+            FieldIOError::new(kind, Some(self.info.clone())),
+            self.record_index,
+        )
+    }
+}
+
+/// Iterator over the records of a [MappedReader]
+pub struct RecordRefIterator<'a> {
+    records: std::slice::ChunksExact<'a, u8>,
+    index: usize,
+    layout: &'a RecordLayout,
+}
+
+impl<'a> Iterator for RecordRefIterator<'a> {
+    type Item = RecordRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = self.records.next()?;
+        let record = RecordRef {
+            data,
+            index: self.index,
+            layout: self.layout,
+        };
+        self.index += 1;
+        Some(record)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.records.size_hint()
+    }
+}
+
+impl ExactSizeIterator for RecordRefIterator<'_> {}
+
+/// A read-only memory mapping of a file
+pub struct MappedFile {
+    mapping: memmap2::Mmap,
+}
+
+impl MappedFile {
+    /// Maps the file at the given path
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified, by this process or another one, while it is mapped:
+    /// the bytes borrowed from the mapping would change under the reader,
+    /// and reading past the end of a file that was truncated raises a `SIGBUS` on unix.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the caller guarantees that the file is not modified while it is mapped
+        let mapping = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(Self { mapping })
+    }
+}
+
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        &self.mapping
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{FieldName, Record, TableWriterBuilder};
+    use std::convert::TryFrom;
+
+    fn table() -> Vec<u8> {
+        let mut dst = Cursor::new(Vec::<u8>::new());
+        let mut writer = TableWriterBuilder::new()
+            .add_character_field(FieldName::try_from("Name").unwrap(), 10)
+            .add_numeric_field(FieldName::try_from("Amount").unwrap(), 8, 2)
+            .add_varchar_field(FieldName::try_from("Nick").unwrap(), 10)
+            .nullable()
+            .build_with_dest(&mut dst);
+        for (name, amount, nick) in [("Ada", 1.5, Some("Countess")), ("Alan", 2.0, None)] {
+            let mut record = Record::default();
+            record.insert(
+                "Name".to_string(),
+                FieldValue::Character(Some(name.to_string())),
+            );
+            record.insert("Amount".to_string(), FieldValue::Numeric(Some(amount)));
+            record.insert(
+                "Nick".to_string(),
+                FieldValue::Varchar(nick.map(str::to_string)),
+            );
+            writer.write_record(&record).unwrap();
+        }
+        writer.close().unwrap();
+        drop(writer);
+        dst.into_inner()
+    }
+
+    #[test]
+    fn read_fields_without_copy() {
+        let data = table();
+        let reader = MappedReader::new(data.as_slice()).unwrap();
+        assert_eq!(reader.num_records(), 2);
+        assert_eq!(
+            reader
+                .fields()
+                .iter()
+                .map(|field| field.name())
+                .collect::<Vec<_>>(),
+            vec!["Name", "Amount", "Nick"]
+        );
+
+        let record = reader.record(1).unwrap();
+        assert_eq!(record.index(), 1);
+        assert!(!record.is_deleted());
+        let name = record.field(0).unwrap();
+        assert_eq!(name.bytes(), b"Alan      ");
+        assert!(matches!(name.as_str().unwrap(), Cow::Borrowed("Alan")));
+        assert_eq!(
+            record.field_by_name("AMOUNT").unwrap().value().unwrap(),
+            FieldValue::Numeric(Some(2.0))
+        );
+        assert!(record.field(2).unwrap().is_null());
+        assert!(record.field(3).is_none());
+        assert!(reader.record(2).is_err());
+
+        let nicks = reader
+            .iter_records()
+            .map(|record| record.field(2).unwrap().value().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            nicks,
+            vec![
+                FieldValue::Varchar(Some("Countess".to_string())),
+                FieldValue::Varchar(None)
+            ]
+        );
+    }
+
+    #[test]
+    fn map_a_file() {
+        let mut reader = Reader::from_path("tests/data/line.dbf").unwrap();
+        let records = reader.read().unwrap();
+        // SAFETY: the test data is not modified
+        let reader = unsafe { MappedReader::from_path("tests/data/line.dbf") }.unwrap();
+        assert_eq!(reader.iter_records().len(), records.len());
+        for (record, mapped) in records.iter().zip(reader.iter_records()) {
+            for index in 0..reader.fields().len() {
+                let field = mapped.field(index).unwrap();
+                assert_eq!(
+                    record.get(field.info().name()),
+                    Some(&field.value().unwrap())
+                );
+            }
+        }
+
+        let truncated = &table()[..100];
+        assert!(MappedReader::new(truncated).is_err());
+    }
+}
@@ -0,0 +1,277 @@
+//! Decoding of records on several threads
+//!
+//! Records have a fixed size, so once their bytes are read, each rayon task can decode
+//! a part of them with its own buffer and its own clone of the encoding.
+use std::io::{Cursor, Read, Seek};
+
+use rayon::prelude::*;
+
+use crate::encoding::DynEncoding;
+use crate::reading::{decode_record, null_flags_position, DecodeErrors, DELETED_RECORD_MARKER};
+use crate::record::field::MemoReader;
+use crate::{
+    DecodeDiagnostic, DecodeErrorPolicy, DeletionPolicy, Encoding, Error, FieldInfo,
+    ReadableRecord, Reader, Record, RecordMeta,
+};
+
+/// Number of records decoded by each rayon task
+const RECORDS_PER_TASK: usize = 1024;
+
+impl<T: Read + Seek> Reader<T> {
+    /// Creates an iterator over the records that decodes them on several threads
+    ///
+    /// The records are read in batches, each batch being split between the threads
+    /// of the current rayon thread pool, and are returned in the order of the file.
+    /// Use [ParallelRecordIterator::thread_pool] to decode them on another pool.
+    ///
+    /// Tables with memo fields are decoded on the calling thread,
+    /// as the memo file can only be read from one place at a time.
+    ///
+    /// Clones of the encoding are sent to the threads, so the records are also decoded
+    /// on the calling thread when the encoding of the reader is not known to be `Send`:
+    /// the encodings of the crate are, but not those given to
+    /// [Reader::new_with_encoding] and the like, use
+    /// [ParallelRecordIterator::with_encoding] to give them again.
+    ///
+    /// The [diagnostics](Reader::diagnostics) of the fields that could not be decoded
+    /// are also reported in the order of the file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// let mut reader = dbase::Reader::from_path("tests/data/line.dbf")?;
+    /// let records = reader
+    ///     .par_iter_records_as::<dbase::Record>()
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// assert_eq!(records.len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn par_iter_records_as<R: ReadableRecord + Send>(
+        &mut self,
+    ) -> ParallelRecordIterator<'_, T, R> {
+        let next_record = self.current_record_index();
+        let encoding = self.encoding.clone();
+        ParallelRecordIterator {
+            reader: self,
+            next_record,
+            encoding,
+            thread_pool: None,
+            decoded: Vec::new().into_iter(),
+        }
+    }
+
+    /// Shortcut function to decode the [Records](struct.Record.html) on several threads
+    pub fn par_iter_records(&mut self) -> ParallelRecordIterator<'_, T, Record> {
+        self.par_iter_records_as::<Record>()
+    }
+}
+
+/// Iterator over the records of a [Reader], decoded on several threads
+pub struct ParallelRecordIterator<'a, T: Read + Seek, R: ReadableRecord> {
+    reader: &'a mut Reader<T>,
+    /// Index of the next record to read from the source
+    next_record: usize,
+    encoding: DynEncoding,
+    /// The pool decoding the records, the current one if `None`
+    thread_pool: Option<&'a rayon::ThreadPool>,
+    /// The records of the current batch that were not returned yet
+    decoded: std::vec::IntoIter<Result<R, Error>>,
+}
+
+impl<'a, T: Read + Seek, R: ReadableRecord + Send> ParallelRecordIterator<'a, T, R> {
+    /// Sets the encoding used to decode the records, instead of the one of the reader
+    ///
+    /// As the encoding is `Send`, records are decoded on several threads with it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// let mut reader = dbase::Reader::new_with_encoding(
+    ///     std::fs::File::open("tests/data/line.dbf").unwrap(),
+    ///     dbase::Unicode,
+    /// )?;
+    /// let records = reader
+    ///     .par_iter_records()
+    ///     .with_encoding(dbase::Unicode)
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_encoding<E: Encoding + Clone + Send + 'static>(mut self, encoding: E) -> Self {
+        self.encoding = DynEncoding::new_send(encoding);
+        self
+    }
+
+    /// Sets the rayon thread pool decoding the records, instead of the current one
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+    /// let mut reader = dbase::Reader::from_path("tests/data/line.dbf")?;
+    /// let records = reader
+    ///     .par_iter_records()
+    ///     .thread_pool(&pool)
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn thread_pool(mut self, pool: &'a rayon::ThreadPool) -> Self {
+        self.thread_pool = Some(pool);
+        self
+    }
+
+    /// Reads the next batch of records and decodes them
+    fn decode_next_batch(&mut self) -> Result<(), Error> {
+        let num_records = self.reader.header.num_records as usize;
+        let num_threads = self
+            .thread_pool
+            .map_or_else(rayon::current_num_threads, |pool| {
+                pool.current_num_threads()
+            });
+        let count = (num_records - self.next_record).min(num_threads * RECORDS_PER_TASK);
+        let record_size = self
+            .reader
+            .fields_info
+            .iter()
+            .map(|field| usize::from(field.field_length))
+            .sum::<usize>();
+        let mut data = vec![0u8; count * record_size];
+        let first_record = self.next_record;
+        if let Err(error) = self.reader.source.read_exact(&mut data) {
+            // Stop at the error
+            self.next_record = num_records;
+            return Err(Error::io_error(error, first_record));
+        }
+        self.next_record += count;
+
+        let policy = self.reader.deletion_policy();
+        let decode_error_policy = self.reader.decode_error_policy();
+        let fields_info = &self.reader.fields_info;
+        let null_flags_position = null_flags_position(fields_info);
+        let send_encoding = self
+            .encoding
+            .to_send()
+            .filter(|_| self.reader.memo_reader.is_none() && num_threads > 1);
+        let decoded = if let Some(send_encoding) = send_encoding {
+            // Each task gets its own clone of the encoding, which are Send but not Sync
+            let tasks = data
+                .chunks(RECORDS_PER_TASK * record_size)
+                .map(|chunk| (chunk, send_encoding.clone()))
+                .collect::<Vec<_>>();
+            let decode_tasks = || {
+                tasks
+                    .into_par_iter()
+                    .enumerate()
+                    .map(|(i, (chunk, encoding))| {
+                        let mut diagnostics = Vec::new();
+                        let records = decode_records::<T, R>(
+                            chunk,
+                            first_record + i * RECORDS_PER_TASK,
+                            record_size,
+                            fields_info,
+                            &mut None,
+                            &DynEncoding::from(encoding),
+                            null_flags_position,
+                            policy,
+                            decode_error_policy,
+                            &mut diagnostics,
+                        );
+                        (records, diagnostics)
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let batches = match self.thread_pool {
+                Some(pool) => pool.install(decode_tasks),
+                None => decode_tasks(),
+            };
+            let mut decoded = Vec::with_capacity(count);
+            for (records, diagnostics) in batches {
+                decoded.extend(records);
+                self.reader.diagnostics.extend(diagnostics);
+            }
+            decoded
+        } else {
+            decode_records(
+                &data,
+                first_record,
+                record_size,
+                fields_info,
+                &mut self.reader.memo_reader,
+                &self.encoding,
+                null_flags_position,
+                policy,
+                decode_error_policy,
+                &mut self.reader.diagnostics,
+            )
+        };
+        self.decoded = decoded.into_iter();
+        Ok(())
+    }
+}
+
+impl<T: Read + Seek, R: ReadableRecord + Send> Iterator for ParallelRecordIterator<'_, T, R> {
+    type Item = Result<R, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.decoded.next() {
+                return Some(record);
+            }
+            if self.next_record >= self.reader.header.num_records as usize {
+                return None;
+            }
+            if let Err(error) = self.decode_next_batch() {
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+/// Decodes the records whose bytes are in `data`, the first one being at `first_record`
+#[allow(clippy::too_many_arguments)]
+fn decode_records<T: Read + Seek, R: ReadableRecord>(
+    data: &[u8],
+    first_record: usize,
+    record_size: usize,
+    fields_info: &[FieldInfo],
+    memo_reader: &mut Option<MemoReader<T>>,
+    encoding: &DynEncoding,
+    null_flags_position: Option<usize>,
+    policy: DeletionPolicy,
+    decode_error_policy: DecodeErrorPolicy,
+    diagnostics: &mut Vec<DecodeDiagnostic>,
+) -> Vec<Result<R, Error>> {
+    let mut buffer = Cursor::new(vec![0u8; record_size]);
+    data.chunks_exact(record_size)
+        .enumerate()
+        .filter_map(|(i, record_data)| {
+            let meta = RecordMeta {
+                index: first_record + i,
+                is_deleted: record_data[0] == DELETED_RECORD_MARKER,
+            };
+            if !policy.accepts(meta.is_deleted) {
+                return None;
+            }
+            buffer.get_mut().copy_from_slice(record_data);
+            Some(decode_record(
+                &mut buffer,
+                fields_info,
+                memo_reader,
+                encoding,
+                null_flags_position,
+                meta,
+                DecodeErrors {
+                    policy: decode_error_policy,
+                    record_index: meta.index,
+                    diagnostics: &mut *diagnostics,
+                },
+                None,
+            ))
+        })
+        .collect()
+}
@@ -8,13 +8,15 @@ use std::convert::TryFrom;
 use std::fs::File;
 use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::iter::FusedIterator;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::encoding::DynEncoding;
 use crate::error::{Error, ErrorKind, FieldIOError};
-use crate::header::Header;
-use crate::record::field::{FieldType, FieldValue, MemoFileType, MemoReader};
-use crate::record::FieldInfo;
+use crate::expression::Expression;
+use crate::header::{CodePageMark, Header};
+use crate::index::ProductionIndex;
+use crate::record::field::{FieldType, FieldValue, MemoReader};
+use crate::record::{assign_null_bits, FieldInfo};
 use crate::ErrorKind::UnsupportedCodePage;
 use crate::{Encoding, FieldConversionError};
 
@@ -23,6 +25,81 @@ pub(crate) const TERMINATOR_VALUE: u8 = 0x0D;
 
 pub(crate) const BACKLINK_SIZE: u16 = 263;
 
+/// Value of the first byte of a record that is marked as deleted
+pub(crate) const DELETED_RECORD_MARKER: u8 = b'*';
+
+/// Value of the first byte of a record that is not deleted
+pub(crate) const VALID_RECORD_MARKER: u8 = b' ';
+
+/// What to do with records that are marked as deleted when reading
+///
+/// dBase and FoxPro do not physically remove deleted records,
+/// they only set the deletion flag of the record.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DeletionPolicy {
+    /// Deleted records are returned alongside the others (the default)
+    #[default]
+    Include,
+    /// Deleted records are skipped
+    Skip,
+    /// Only deleted records are returned
+    Only,
+}
+
+impl DeletionPolicy {
+    pub(crate) fn accepts(self, is_deleted: bool) -> bool {
+        match self {
+            DeletionPolicy::Include => true,
+            DeletionPolicy::Skip => !is_deleted,
+            DeletionPolicy::Only => is_deleted,
+        }
+    }
+}
+
+/// What to do with Character, Varchar and Memo fields whose bytes
+/// cannot be decoded with the encoding of the reader
+///
+/// Each field that is not decoded strictly is reported as a [DecodeDiagnostic].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DecodeErrorPolicy {
+    /// Reading the record fails with a `StringDecodeError` (the default)
+    #[default]
+    Strict,
+    /// The bytes that cannot be decoded are replaced with U+FFFD
+    Replace,
+    /// The bytes of the field are kept as they are, in a `FieldValue::Varbinary`
+    /// (a `FieldValue::BinaryMemo` for memos)
+    KeepBytes,
+}
+
+/// A field whose string could not be decoded, and was read following
+/// the [DecodeErrorPolicy] of the reader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeDiagnostic {
+    /// Index of the record, starting at 0
+    pub record_index: usize,
+    /// Name of the field
+    pub field_name: String,
+    /// Description of the decoding error
+    pub message: String,
+}
+
+/// Where decoding errors are handled while reading a record
+pub(crate) struct DecodeErrors<'a> {
+    pub(crate) policy: DecodeErrorPolicy,
+    pub(crate) record_index: usize,
+    pub(crate) diagnostics: &'a mut Vec<DecodeDiagnostic>,
+}
+
+/// Information about a record that is not stored in its fields
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RecordMeta {
+    /// Index of the record in the file, starting at 0
+    pub index: usize,
+    /// Whether the record is marked as deleted
+    pub is_deleted: bool,
+}
+
 /// Trait to be implemented by structs that represent records read from a
 /// dBase file.
 ///
@@ -131,6 +208,8 @@ pub struct TableInfo {
     pub(crate) header: Header,
     pub(crate) fields_info: Vec<FieldInfo>,
     pub(crate) encoding: DynEncoding,
+    /// The dBase 7 field properties, kept as they are
+    pub(crate) field_properties: Vec<u8>,
 }
 
 /// Struct with the handle to the source .dbf file
@@ -139,11 +218,19 @@ pub struct TableInfo {
 #[derive(Clone)]
 pub struct Reader<T: Read + Seek> {
     /// Where the data is read from
-    source: T,
-    memo_reader: Option<MemoReader<T>>,
-    header: Header,
-    fields_info: Vec<FieldInfo>,
-    encoding: DynEncoding,
+    pub(crate) source: T,
+    pub(crate) memo_reader: Option<MemoReader<T>>,
+    pub(crate) header: Header,
+    pub(crate) fields_info: Vec<FieldInfo>,
+    pub(crate) encoding: DynEncoding,
+    deletion_policy: DeletionPolicy,
+    decode_error_policy: DecodeErrorPolicy,
+    /// The fields read following the decode error policy, since they were last taken
+    pub(crate) diagnostics: Vec<DecodeDiagnostic>,
+    /// The dBase 7 field properties, kept as they are
+    pub(crate) field_properties: Vec<u8>,
+    /// Path of the table, when it has a production index
+    production_index_path: Option<PathBuf>,
 }
 
 impl<T: Read + Seek> Reader<T> {
@@ -173,9 +260,59 @@ impl<T: Read + Seek> Reader<T> {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn new(mut source: T) -> Result<Self, Error> {
+    pub fn new(source: T) -> Result<Self, Error> {
+        Self::new_impl(source, None)
+    }
+
+    /// Creates a new reader, strings being decoded with `encoding`
+    /// or the encoding of the code page mark of the file when there is none
+    pub(crate) fn new_impl(mut source: T, encoding: Option<DynEncoding>) -> Result<Self, Error> {
         let header = Header::read_from(&mut source).map_err(|error| Error::io_error(error, 0))?;
 
+        let mut field_properties = vec![];
+        let fields_info = if header.file_type.is_dbase7() {
+            let fields_info = Self::read_dbase7_fields_info(&mut source)?;
+            // The field properties are between the terminator and the first record
+            let properties_start =
+                header.size() + (fields_info.len() - 1) * FieldInfo::DBASE7_SIZE + 1;
+            let properties_size =
+                (header.offset_to_first_record as usize).saturating_sub(properties_start);
+            field_properties.resize(properties_size, 0);
+            source
+                .read_exact(&mut field_properties)
+                .map_err(|error| Error::io_error(error, 0))?;
+            fields_info
+        } else {
+            Self::read_fields_info(&mut source, &header)?
+        };
+
+        source
+            .seek(SeekFrom::Start(u64::from(header.offset_to_first_record)))
+            .map_err(|error| Error::io_error(error, 0))?;
+
+        let encoding = match encoding {
+            Some(encoding) => encoding,
+            None => header.code_page_mark.to_encoding().ok_or_else(|| {
+                let field_error =
+                    FieldIOError::new(UnsupportedCodePage(header.code_page_mark), None);
+                Error::new(field_error, 0)
+            })?,
+        };
+        Ok(Self {
+            source,
+            memo_reader: None,
+            header,
+            fields_info,
+            encoding,
+            deletion_policy: DeletionPolicy::default(),
+            decode_error_policy: DecodeErrorPolicy::default(),
+            diagnostics: Vec::new(),
+            field_properties,
+            production_index_path: None,
+        })
+    }
+
+    fn read_fields_info(source: &mut T, header: &Header) -> Result<Vec<FieldInfo>, Error> {
         let offset = if header.file_type.is_visual_fox_pro() {
             if BACKLINK_SIZE > header.offset_to_first_record {
                 panic!("Invalid file");
@@ -190,50 +327,114 @@ impl<T: Read + Seek> Reader<T> {
         let mut fields_info = Vec::<FieldInfo>::with_capacity(num_fields as usize + 1);
         fields_info.push(FieldInfo::new_deletion_flag());
         for _ in 0..num_fields {
-            let info = FieldInfo::read_from(&mut source).map_err(|error| Error {
+            let info = FieldInfo::read_from(source).map_err(|error| Error {
                 record_num: 0,
                 field: None,
                 kind: error,
             })?;
             fields_info.push(info);
         }
+        assign_null_bits(&mut fields_info);
 
         let terminator = source
             .read_u8()
             .map_err(|error| Error::io_error(error, 0))?;
 
         debug_assert_eq!(terminator, TERMINATOR_VALUE);
+        Ok(fields_info)
+    }
 
-        source
-            .seek(SeekFrom::Start(u64::from(header.offset_to_first_record)))
-            .map_err(|error| Error::io_error(error, 0))?;
-
-        let encoding = header.code_page_mark.to_encoding().ok_or_else(|| {
-            let field_error = FieldIOError::new(UnsupportedCodePage(header.code_page_mark), None);
-            Error::new(field_error, 0)
-        })?;
-        Ok(Self {
-            source,
-            memo_reader: None,
-            header,
-            fields_info,
-            encoding,
-        })
+    /// The number of dBase 7 fields cannot be deduced from the header,
+    /// as the field properties are stored after them, so we read until the terminator
+    fn read_dbase7_fields_info(source: &mut T) -> Result<Vec<FieldInfo>, Error> {
+        let mut fields_info = vec![FieldInfo::new_deletion_flag()];
+        let mut descriptor = [0u8; FieldInfo::DBASE7_SIZE];
+        loop {
+            source
+                .read_exact(&mut descriptor[..1])
+                .map_err(|error| Error::io_error(error, 0))?;
+            if descriptor[0] == TERMINATOR_VALUE {
+                return Ok(fields_info);
+            }
+            source
+                .read_exact(&mut descriptor[1..])
+                .map_err(|error| Error::io_error(error, 0))?;
+            let info =
+                FieldInfo::read_dbase7_from(&mut &descriptor[..]).map_err(|error| Error {
+                    record_num: 0,
+                    field: None,
+                    kind: error,
+                })?;
+            fields_info.push(info);
+        }
     }
 
     /// Creates a new reader from the source and reads strings using the encoding provided.
     ///
     /// See [`Self::new`] for more information.
     pub fn new_with_encoding<E: Encoding + 'static>(source: T, encoding: E) -> Result<Self, Error> {
-        let mut reader = Self::new(source)?;
-        reader.set_encoding(encoding);
-        Ok(reader)
+        Self::new_impl(source, Some(DynEncoding::new(encoding)))
     }
 
     pub fn set_encoding<E: Encoding + 'static>(&mut self, encoding: E) {
         self.encoding = DynEncoding::new(encoding);
     }
 
+    /// Sets what the reader does with records marked as deleted
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// let mut reader = dbase::Reader::from_path("tests/data/line.dbf")?;
+    /// reader.set_deletion_policy(dbase::DeletionPolicy::Skip);
+    /// let records = reader.read()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_deletion_policy(&mut self, policy: DeletionPolicy) {
+        self.deletion_policy = policy;
+    }
+
+    /// Returns what the reader does with records marked as deleted
+    pub fn deletion_policy(&self) -> DeletionPolicy {
+        self.deletion_policy
+    }
+
+    /// Sets what the reader does with strings that cannot be decoded
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// let mut reader = dbase::Reader::from_path("tests/data/line.dbf")?;
+    /// reader.set_decode_error_policy(dbase::DecodeErrorPolicy::Replace);
+    /// let records = reader.read()?;
+    /// for diagnostic in reader.take_diagnostics() {
+    ///     println!("{}: {}", diagnostic.record_index, diagnostic.field_name);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_decode_error_policy(&mut self, policy: DecodeErrorPolicy) {
+        self.decode_error_policy = policy;
+    }
+
+    /// Returns what the reader does with strings that cannot be decoded
+    pub fn decode_error_policy(&self) -> DecodeErrorPolicy {
+        self.decode_error_policy
+    }
+
+    /// Returns the fields that could not be decoded since the diagnostics were last taken
+    pub fn diagnostics(&self) -> &[DecodeDiagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns the fields that could not be decoded, and clears them from the reader
+    pub fn take_diagnostics(&mut self) -> Vec<DecodeDiagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
     /// Returns the header of the file
     pub fn header(&self) -> &Header {
         &self.header
@@ -245,26 +446,219 @@ impl<T: Read + Seek> Reader<T> {
     }
 
     /// Creates an iterator of records of the type you want
-    pub fn iter_records_as<R: ReadableRecord>(&mut self) -> RecordIterator<T, R> {
+    pub fn iter_records_as<R: ReadableRecord>(&mut self) -> RecordIterator<'_, T, R> {
         let record_size: usize = self
             .fields_info
             .iter()
             .map(|i| i.field_length as usize)
             .sum();
+        let current_record = self.current_record_index() as u32;
+        let null_flags_position = null_flags_position(&self.fields_info);
         RecordIterator {
             reader: self,
             record_type: std::marker::PhantomData,
-            current_record: 0,
+            current_record,
+            null_flags_position,
             record_data_buffer: std::io::Cursor::new(vec![0u8; record_size]),
-            field_data_buffer: [0u8; 255],
         }
     }
 
+    /// Returns the index of the record the source is at
+    ///
+    /// The source may have been moved using seek
+    pub(crate) fn current_record_index(&mut self) -> usize {
+        self.source
+            .stream_position()
+            .ok()
+            .and_then(|pos| pos.checked_sub(u64::from(self.header.offset_to_first_record)))
+            .and_then(|offset| offset.checked_div(u64::from(self.header.size_of_record)))
+            .unwrap_or(0) as usize
+    }
+
     /// Shortcut function to get an iterator over the [Records](struct.Record.html) in the file
-    pub fn iter_records(&mut self) -> RecordIterator<T, Record> {
+    pub fn iter_records(&mut self) -> RecordIterator<'_, T, Record> {
         self.iter_records_as::<Record>()
     }
 
+    /// Creates an iterator of records of the type you want,
+    /// each record comes with its [RecordMeta]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// let mut reader = dbase::Reader::from_path("tests/data/line.dbf")?;
+    /// for result in reader.iter_records_with_meta_as::<dbase::Record>() {
+    ///     let (meta, record) = result?;
+    ///     println!("record {} is deleted: {}", meta.index, meta.is_deleted);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_records_with_meta_as<R: ReadableRecord>(
+        &mut self,
+    ) -> RecordWithMetaIterator<'_, T, R> {
+        RecordWithMetaIterator {
+            inner: self.iter_records_as::<R>(),
+        }
+    }
+
+    /// Creates an iterator over the [Records](struct.Record.html) of the file,
+    /// holding only the given fields
+    ///
+    /// The position of the fields in the records is computed once, then only their bytes
+    /// are decoded: the other fields are not, and the memos of unselected memo fields
+    /// are not read.
+    ///
+    /// Records are filtered according to the [DeletionPolicy].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// let mut reader = dbase::Reader::from_path("tests/data/stations.dbf")?;
+    /// for record in reader.iter_records_projected(&["name", "line"])? {
+    ///     let record = record?;
+    ///     assert!(record.get("marker-col").is_none());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_records_projected(
+        &mut self,
+        field_names: &[&str],
+    ) -> Result<ProjectedRecordIterator<'_, T>, Error> {
+        let plan = self.field_plan(field_names)?;
+        Ok(ProjectedRecordIterator {
+            inner: self.iter_records_as::<Record>(),
+            plan,
+        })
+    }
+
+    /// Returns the offset in the record and the information of each of the fields
+    fn field_plan(&self, field_names: &[&str]) -> Result<Vec<(usize, FieldInfo)>, Error> {
+        let offsets = self
+            .fields_info
+            .iter()
+            .scan(0, |offset, field| {
+                let field_offset = *offset;
+                *offset += usize::from(field.field_length);
+                Some(field_offset)
+            })
+            .collect::<Vec<_>>();
+        field_names
+            .iter()
+            .map(|name| {
+                self.fields_info
+                    .iter()
+                    .zip(&offsets)
+                    .find(|(field, _)| !field.is_hidden() && field.name.eq_ignore_ascii_case(name))
+                    .map(|(field, offset)| (*offset, field.clone()))
+                    .ok_or_else(|| Error {
+                        record_num: 0,
+                        field: None,
+                        kind: ErrorKind::FieldNotFound(name.to_string()),
+                    })
+            })
+            .collect()
+    }
+
+    /// Creates an iterator of the records of the type you want
+    /// that match an xBase filter expression
+    ///
+    /// The expression uses the syntax of dBase and FoxPro,
+    /// see the [expression](crate::expression) module for what is supported.
+    /// It is parsed and its fields are checked before reading any record.
+    ///
+    /// Records are also filtered according to the [DeletionPolicy].
+    pub fn filter_as<R: ReadableRecord>(
+        &mut self,
+        expression: &str,
+    ) -> Result<FilterIterator<'_, T, R>, Error> {
+        let expression = Expression::parse(expression)?;
+        expression.check_fields(&self.fields_info)?;
+        let plan = self.field_plan(&expression.field_names())?;
+        Ok(FilterIterator {
+            inner: self.iter_records_as::<R>(),
+            expression,
+            plan,
+        })
+    }
+
+    /// Shortcut function to get an iterator over the [Records](struct.Record.html)
+    /// that match an xBase filter expression
+    ///
+    /// See [filter_as](Reader::filter_as)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// let mut reader = dbase::Reader::from_path("tests/data/stations.dbf")?;
+    /// for station in reader.filter("UPPER(line) = 'BLUE' .AND. .NOT. DELETED()")? {
+    ///     let station = station?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn filter(&mut self, expression: &str) -> Result<FilterIterator<'_, T, Record>, Error> {
+        self.filter_as::<Record>(expression)
+    }
+
+    /// Creates an iterator of records of the type you want,
+    /// in the order of a tag of the production index of the table
+    ///
+    /// The production index is the .mdx (dBase IV) or the structural .cdx (FoxPro)
+    /// with the same name as the table. It is only used by readers created with
+    /// [from_path](Reader::from_path) when the `has_structural_cdx` table flag is set,
+    /// otherwise a [MissingIndexFile](ErrorKind::MissingIndexFile) error is returned.
+    ///
+    /// Records are filtered according to the [DeletionPolicy].
+    pub fn iter_records_by_tag_as<R: ReadableRecord>(
+        &mut self,
+        tag: &str,
+    ) -> Result<TagRecordIterator<'_, T, R>, Error> {
+        let index = match &self.production_index_path {
+            Some(path) => ProductionIndex::open(path, &self.fields_info)?,
+            None => None,
+        };
+        let record_indices = index
+            .ok_or(Error {
+                record_num: 0,
+                field: None,
+                kind: ErrorKind::MissingIndexFile,
+            })?
+            .record_indices(tag)?;
+        Ok(TagRecordIterator {
+            reader: self,
+            record_indices: record_indices.into_iter(),
+            record_type: std::marker::PhantomData,
+        })
+    }
+
+    /// Shortcut function to get an iterator over the [Records](struct.Record.html)
+    /// in the order of a tag of the production index of the table
+    ///
+    /// See [iter_records_by_tag_as](Reader::iter_records_by_tag_as)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// let mut reader = dbase::Reader::from_path("customers.dbf")?;
+    /// for customer in reader.iter_records_by_tag("CUSTNO")? {
+    ///     let customer = customer?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_records_by_tag(
+        &mut self,
+        tag: &str,
+    ) -> Result<TagRecordIterator<'_, T, Record>, Error> {
+        self.iter_records_by_tag_as::<Record>(tag)
+    }
+
     /// Reads all the records of the file inside a `Vec`
     pub fn read_as<R: ReadableRecord>(&mut self) -> Result<Vec<R>, Error> {
         // We don't read the file terminator
@@ -291,15 +685,90 @@ impl<T: Read + Seek> Reader<T> {
     }
 
     /// Seek to the start of the record at `index`
+    ///
+    /// Iterating over the records after seeking starts at that record.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// let mut reader = dbase::Reader::from_path("tests/data/stations.dbf")?;
+    /// reader.seek(3)?;
+    /// let fourth_station = reader.iter_records().next().unwrap()?;
+    /// # Ok(())
+    /// # }
+    /// ```
     pub fn seek(&mut self, index: usize) -> Result<(), Error> {
-        let offset = self.header.offset_to_first_record as usize
-            + (index * self.header.size_of_record as usize);
+        let offset = self.header.record_position(index);
         self.source
-            .seek(SeekFrom::Start(offset as u64))
+            .seek(SeekFrom::Start(offset))
             .map_err(|err| Error::io_error(err, 0))?;
         Ok(())
     }
 
+    /// Reads the record at `index`, whatever the [DeletionPolicy] is
+    pub(crate) fn read_record_at<R: ReadableRecord>(
+        &mut self,
+        index: usize,
+    ) -> Result<(RecordMeta, R), Error> {
+        self.read_record_at_with_policy(index, DeletionPolicy::Include)
+            .map(|record| record.expect("all the records are included"))
+    }
+
+    /// Reads the record at `index` if the `policy` accepts it,
+    /// its deletion flag being checked before the record is decoded
+    pub(crate) fn read_record_at_with_policy<R: ReadableRecord>(
+        &mut self,
+        index: usize,
+        policy: DeletionPolicy,
+    ) -> Result<Option<(RecordMeta, R)>, Error> {
+        if index >= self.header.num_records as usize {
+            return Err(Error {
+                record_num: index,
+                field: None,
+                kind: ErrorKind::RecordIndexOutOfRange(index),
+            });
+        }
+        self.seek(index)?;
+        let mut iter = self.iter_records_as::<R>();
+        match iter.read_next_record_data() {
+            Some(Ok(meta)) if !policy.accepts(meta.is_deleted) => Ok(None),
+            Some(Ok(meta)) => iter.decode_record_data(meta).map(Some),
+            Some(Err(error)) => Err(Error::io_error(error, index)),
+            None => unreachable!("index was checked against the number of records"),
+        }
+    }
+
+    /// Opens the memo file associated with the dbf file at `dbf_path`
+    /// using the `open` function, if at least one field is a Memo
+    pub(crate) fn open_memo_file_with<F>(&mut self, dbf_path: &Path, open: F) -> Result<(), Error>
+    where
+        F: FnOnce(&Path) -> std::io::Result<T>,
+    {
+        let at_least_one_field_is_memo = self
+            .fields_info
+            .iter()
+            .any(|f_info| f_info.field_type.is_memo());
+
+        if at_least_one_field_is_memo {
+            let memo_type = self.header.file_type.supported_memo_type();
+            if let Some(mt) = memo_type {
+                let memo_path = mt.file_path_for(dbf_path);
+
+                let memo_file = open(&memo_path).map_err(|error| Error {
+                    record_num: 0,
+                    field: None,
+                    kind: ErrorKind::ErrorOpeningMemoFile(error),
+                })?;
+
+                let memo_reader =
+                    MemoReader::new(mt, memo_file).map_err(|error| Error::io_error(error, 0))?;
+                self.memo_reader = Some(memo_reader);
+            }
+        }
+        Ok(())
+    }
+
     /// Consumes the reader, and returns the info that
     /// allow to create a writer that would write a file
     /// with the same structure.
@@ -323,6 +792,7 @@ impl<T: Read + Seek> Reader<T> {
             header: self.header,
             fields_info: self.fields_info,
             encoding: self.encoding,
+            field_properties: self.field_properties,
         }
     }
 }
@@ -330,6 +800,9 @@ impl<T: Read + Seek> Reader<T> {
 impl Reader<BufReader<File>> {
     /// Creates a new dbase Reader from a path
     ///
+    /// When a `.cpg` file is next to the table, as in shapefiles, the encoding it names
+    /// is used instead of the one of the code page mark of the file.
+    ///
     /// # Example
     ///
     /// ```
@@ -339,33 +812,21 @@ impl Reader<BufReader<File>> {
     /// # }
     /// ```
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let encoding = read_cpg_file(path.as_ref())?;
+        Self::from_path_impl(path, encoding)
+    }
+
+    pub(crate) fn from_path_impl<P: AsRef<Path>>(
+        path: P,
+        encoding: Option<DynEncoding>,
+    ) -> Result<Self, Error> {
         let p = path.as_ref().to_owned();
         let bufreader =
             BufReader::new(File::open(path).map_err(|error| Error::io_error(error, 0))?);
-        let mut reader = Reader::new(bufreader)?;
-        let at_least_one_field_is_memo = reader
-            .fields_info
-            .iter()
-            .any(|f_info| f_info.field_type == FieldType::Memo);
-
-        if at_least_one_field_is_memo {
-            let memo_type = dbg!(reader.header.file_type.supported_memo_type());
-            if let Some(mt) = memo_type {
-                let memo_path = match mt {
-                    MemoFileType::DbaseMemo | MemoFileType::DbaseMemo4 => p.with_extension("dbt"),
-                    MemoFileType::FoxBaseMemo => p.with_extension("fpt"),
-                };
-
-                let memo_file = File::open(memo_path).map_err(|error| Error {
-                    record_num: 0,
-                    field: None,
-                    kind: ErrorKind::ErrorOpeningMemoFile(error),
-                })?;
-
-                let memo_reader = MemoReader::new(mt, BufReader::new(memo_file))
-                    .map_err(|error| Error::io_error(error, 0))?;
-                reader.memo_reader = Some(memo_reader);
-            }
+        let mut reader = Reader::new_impl(bufreader, encoding)?;
+        reader.open_memo_file_with(&p, |memo_path| File::open(memo_path).map(BufReader::new))?;
+        if reader.header.table_flags.has_structural_cdx() {
+            reader.production_index_path = Some(p);
         }
         Ok(reader)
     }
@@ -375,10 +836,29 @@ impl Reader<BufReader<File>> {
         path: P,
         encoding: E,
     ) -> Result<Self, Error> {
-        let mut reader = Self::from_path(path)?;
-        reader.encoding = DynEncoding::new(encoding);
-        Ok(reader)
+        Self::from_path_impl(path, Some(DynEncoding::new(encoding)))
+    }
+}
+
+/// Reads the `.cpg` file that GIS software writes next to the table to give its encoding
+///
+/// Returns `None` when there is no such file, or when its code page is not supported.
+pub(crate) fn read_cpg_file(dbf_path: &Path) -> Result<Option<DynEncoding>, Error> {
+    let Some(cpg_path) = ["cpg", "CPG"]
+        .iter()
+        .map(|extension| dbf_path.with_extension(extension))
+        .find(|path| path.is_file())
+    else {
+        return Ok(None);
+    };
+    let name = std::fs::read_to_string(cpg_path).map_err(|error| Error::io_error(error, 0))?;
+    #[cfg(feature = "encoding_rs")]
+    if let Some(encoding) =
+        crate::header::iso_8859_part(&name).and_then(crate::encoding::Iso8859::new)
+    {
+        return Ok(Some(DynEncoding::new_send(encoding)));
     }
+    Ok(CodePageMark::from_cpg_name(&name).and_then(CodePageMark::to_encoding))
 }
 
 /// Simple struct to wrap together the value with the name
@@ -403,10 +883,20 @@ pub struct FieldIterator<'a, T: Read + Seek> {
     pub(crate) fields_info: std::iter::Peekable<std::slice::Iter<'a, FieldInfo>>,
     /// The source where the Memo field data is read
     pub(crate) memo_reader: &'a mut Option<MemoReader<T>>,
-    /// Buffer where field data is stored
-    field_data_buffer: &'a mut [u8; 255],
     /// The string encoding
     encoding: &'a DynEncoding,
+    /// Position of the `_NullFlags` field in the record, if any
+    null_flags_position: Option<usize>,
+    decode_errors: DecodeErrors<'a>,
+    /// Where the values of some fields are copied, if any
+    tap: Option<FieldTap<'a>>,
+}
+
+/// Copies the values of some of the fields while a record is decoded
+pub(crate) struct FieldTap<'a> {
+    /// The fields whose values are copied, and their offset in the record
+    pub(crate) fields: &'a [(usize, FieldInfo)],
+    pub(crate) values: &'a mut HashMap<String, FieldValue>,
 }
 
 impl<'a, T: Read + Seek> FieldIterator<'a, T> {
@@ -419,7 +909,7 @@ impl<'a, T: Read + Seek> FieldIterator<'a, T> {
             .fields_info
             .next()
             .ok_or_else(FieldIOError::end_of_record)?;
-        if field_info.is_deletion_flag() {
+        if field_info.is_hidden() {
             if let Err(e) = self.skip_field(field_info) {
                 Err(e)
             } else {
@@ -492,7 +982,7 @@ impl<'a, T: Read + Seek> FieldIterator<'a, T> {
             .fields_info
             .next()
             .ok_or(FieldIOError::end_of_record())?;
-        if field_info.is_deletion_flag() {
+        if field_info.is_hidden() {
             self.skip_field(field_info)?;
             self.read_next_field_raw()
         } else {
@@ -510,7 +1000,7 @@ impl<'a, T: Read + Seek> FieldIterator<'a, T> {
             field: None,
             kind: ErrorKind::EndOfRecord,
         })?;
-        if field_info.is_deletion_flag() {
+        if field_info.is_hidden() {
             self.skip_field(field_info)?;
             self.fields_info.next().unwrap();
             field_info = self
@@ -543,20 +1033,27 @@ impl<'a, T: Read + Seek> FieldIterator<'a, T> {
 
     /// read the next field using the given info
     fn read_field(&mut self, field_info: &'a FieldInfo) -> Result<FieldValue, FieldIOError> {
-        let field_data_buffer = &mut self.field_data_buffer[..field_info.length() as usize];
-        self.source.read_exact(field_data_buffer).unwrap();
-        match FieldValue::read_from(
-            field_data_buffer,
-            self.memo_reader,
+        let offset = self.source.position() as usize;
+        let value = decode_field(
+            self.source.get_ref(),
+            offset,
             field_info,
-            &*self.encoding,
-        ) {
-            Ok(value) => Ok(value),
-            Err(kind) => Err(FieldIOError {
-                field: Some(field_info.clone()),
-                kind,
-            }),
+            self.null_flags_position,
+            self.memo_reader,
+            self.encoding,
+            &mut self.decode_errors,
+        );
+        self.skip_field(field_info)?;
+        if let (Ok(value), Some(tap)) = (&value, &mut self.tap) {
+            if tap
+                .fields
+                .iter()
+                .any(|(_, field)| field.name == field_info.name)
+            {
+                tap.values.insert(field_info.name.clone(), value.clone());
+            }
         }
+        value
     }
 }
 
@@ -581,40 +1078,330 @@ pub struct RecordIterator<'a, T: Read + Seek, R: ReadableRecord> {
     reader: &'a mut Reader<T>,
     record_type: std::marker::PhantomData<R>,
     current_record: u32,
+    null_flags_position: Option<usize>,
     record_data_buffer: std::io::Cursor<Vec<u8>>,
-    /// Non-Memo field length is stored on a u8,
-    /// so fields cannot exceed 255 bytes
-    field_data_buffer: [u8; 255],
+}
+
+impl<'a, T: Read + Seek, R: ReadableRecord> RecordIterator<'a, T, R> {
+    /// Reads the next record accepted by the reader's [DeletionPolicy]
+    fn next_with_meta(&mut self) -> Option<Result<(RecordMeta, R), Error>> {
+        loop {
+            let meta = self.read_next_record_data()?.ok()?;
+            if !self.reader.deletion_policy.accepts(meta.is_deleted) {
+                continue;
+            }
+            return Some(self.decode_record_data(meta));
+        }
+    }
+
+    /// Reads the bytes of the next record into the record buffer
+    ///
+    /// Returns `None` when there are no more records
+    fn read_next_record_data(&mut self) -> Option<std::io::Result<RecordMeta>> {
+        if self.current_record >= self.reader.header.num_records {
+            return None;
+        }
+        if let Err(error) = self
+            .reader
+            .source
+            .read_exact(self.record_data_buffer.get_mut())
+        {
+            return Some(Err(error));
+        }
+        self.record_data_buffer.set_position(0);
+
+        let meta = RecordMeta {
+            index: self.current_record as usize,
+            is_deleted: self.record_data_buffer.get_ref()[0] == DELETED_RECORD_MARKER,
+        };
+        self.current_record += 1;
+        Some(Ok(meta))
+    }
+
+    /// Decodes the record currently held in the record buffer
+    fn decode_record_data(&mut self, meta: RecordMeta) -> Result<(RecordMeta, R), Error> {
+        self.decode_record_data_with_tap(meta, None)
+    }
+
+    /// Decodes the record currently held in the record buffer,
+    /// copying the values of the fields of the `tap`
+    fn decode_record_data_with_tap(
+        &mut self,
+        meta: RecordMeta,
+        tap: Option<FieldTap<'_>>,
+    ) -> Result<(RecordMeta, R), Error> {
+        decode_record(
+            &mut self.record_data_buffer,
+            &self.reader.fields_info,
+            &mut self.reader.memo_reader,
+            &self.reader.encoding,
+            self.null_flags_position,
+            meta,
+            DecodeErrors {
+                policy: self.reader.decode_error_policy,
+                record_index: meta.index,
+                diagnostics: &mut self.reader.diagnostics,
+            },
+            tap,
+        )
+        .map(|record| (meta, record))
+    }
+}
+
+/// Decodes the record held in the buffer
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn decode_record<T: Read + Seek, R: ReadableRecord>(
+    record_data: &mut std::io::Cursor<Vec<u8>>,
+    fields_info: &[FieldInfo],
+    memo_reader: &mut Option<MemoReader<T>>,
+    encoding: &DynEncoding,
+    null_flags_position: Option<usize>,
+    meta: RecordMeta,
+    decode_errors: DecodeErrors<'_>,
+    tap: Option<FieldTap<'_>>,
+) -> Result<R, Error> {
+    record_data.set_position(0);
+    let mut iter = FieldIterator {
+        source: record_data,
+        fields_info: fields_info.iter().peekable(),
+        memo_reader,
+        encoding,
+        null_flags_position,
+        decode_errors,
+        tap,
+    };
+
+    R::read_using(&mut iter)
+        .and_then(|record| iter.skip_remaining_fields().and(Ok(record)))
+        .map_err(|error| Error::new(error, meta.index))
 }
 
 impl<'a, T: Read + Seek, R: ReadableRecord> Iterator for RecordIterator<'a, T, R> {
     type Item = Result<R, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current_record >= self.reader.header.num_records {
-            None
-        } else {
-            self.reader
-                .source
-                .read_exact(self.record_data_buffer.get_mut())
-                .ok()?;
-            self.record_data_buffer.set_position(0);
-
-            let mut iter = FieldIterator {
-                source: &mut self.record_data_buffer,
-                fields_info: self.reader.fields_info.iter().peekable(),
-                memo_reader: &mut self.reader.memo_reader,
-                field_data_buffer: &mut self.field_data_buffer,
-                encoding: &self.reader.encoding,
+        self.next_with_meta()
+            .map(|result| result.map(|(_meta, record)| record))
+    }
+}
+
+/// Iterator over records contained in the dBase, alongside their [RecordMeta]
+pub struct RecordWithMetaIterator<'a, T: Read + Seek, R: ReadableRecord> {
+    inner: RecordIterator<'a, T, R>,
+}
+
+impl<'a, T: Read + Seek, R: ReadableRecord> Iterator for RecordWithMetaIterator<'a, T, R> {
+    type Item = Result<(RecordMeta, R), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next_with_meta()
+    }
+}
+
+/// Iterator over the records contained in the dBase, holding only some of their fields
+pub struct ProjectedRecordIterator<'a, T: Read + Seek> {
+    inner: RecordIterator<'a, T, Record>,
+    /// Offset in the record and information of each selected field
+    plan: Vec<(usize, FieldInfo)>,
+}
+
+impl<T: Read + Seek> Iterator for ProjectedRecordIterator<'_, T> {
+    type Item = Result<Record, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let meta = match self.inner.read_next_record_data()? {
+                Ok(meta) => meta,
+                Err(error) => {
+                    let index = self.inner.current_record as usize;
+                    return Some(Err(Error::io_error(error, index)));
+                }
+            };
+            if !self.inner.reader.deletion_policy.accepts(meta.is_deleted) {
+                continue;
+            }
+            let reader = &mut *self.inner.reader;
+            let mut decode_errors = DecodeErrors {
+                policy: reader.decode_error_policy,
+                record_index: meta.index,
+                diagnostics: &mut reader.diagnostics,
             };
+            let mut map = HashMap::with_capacity(self.plan.len());
+            for (offset, field_info) in &self.plan {
+                let value = decode_field(
+                    self.inner.record_data_buffer.get_ref(),
+                    *offset,
+                    field_info,
+                    self.inner.null_flags_position,
+                    &mut reader.memo_reader,
+                    &reader.encoding,
+                    &mut decode_errors,
+                );
+                match value {
+                    Ok(value) => map.insert(field_info.name.clone(), value),
+                    Err(error) => return Some(Err(Error::new(error, meta.index))),
+                };
+            }
+            return Some(Ok(Record { map }));
+        }
+    }
+}
+
+/// Iterator over the records contained in the dBase that match an xBase expression
+pub struct FilterIterator<'a, T: Read + Seek, R: ReadableRecord> {
+    inner: RecordIterator<'a, T, R>,
+    expression: Expression,
+    /// Offset in the record and information of each field used by the expression
+    plan: Vec<(usize, FieldInfo)>,
+}
+
+impl<T: Read + Seek, R: ReadableRecord> FilterIterator<'_, T, R> {
+    /// Decodes the record held in the record buffer, and tells whether it matches the expression
+    ///
+    /// Each field is decoded once: the values of the fields used by the expression are
+    /// copied while R is decoded, and only the ones R does not read are decoded afterwards.
+    fn decode_if_matching(&mut self, meta: RecordMeta) -> Result<Option<R>, Error> {
+        let mut values = HashMap::with_capacity(self.plan.len());
+        let tap = FieldTap {
+            fields: &self.plan,
+            values: &mut values,
+        };
+        let (meta, record) = self.inner.decode_record_data_with_tap(meta, Some(tap))?;
+
+        let reader = &mut *self.inner.reader;
+        let mut decode_errors = DecodeErrors {
+            policy: reader.decode_error_policy,
+            record_index: meta.index,
+            diagnostics: &mut reader.diagnostics,
+        };
+        for (offset, field_info) in &self.plan {
+            if values.contains_key(&field_info.name) {
+                continue;
+            }
+            let value = decode_field(
+                self.inner.record_data_buffer.get_ref(),
+                *offset,
+                field_info,
+                self.inner.null_flags_position,
+                &mut reader.memo_reader,
+                &reader.encoding,
+                &mut decode_errors,
+            )
+            .map_err(|error| Error::new(error, meta.index))?;
+            values.insert(field_info.name.clone(), value);
+        }
+
+        let matches = self.expression.matches(&Record { map: values }, meta)?;
+        Ok(matches.then_some(record))
+    }
+}
+
+impl<T: Read + Seek, R: ReadableRecord> Iterator for FilterIterator<'_, T, R> {
+    type Item = Result<R, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let meta = match self.inner.read_next_record_data()? {
+                Ok(meta) => meta,
+                Err(error) => {
+                    let index = self.inner.current_record as usize;
+                    return Some(Err(Error::io_error(error, index)));
+                }
+            };
+            if !self.inner.reader.deletion_policy.accepts(meta.is_deleted) {
+                continue;
+            }
+            match self.decode_if_matching(meta) {
+                Ok(Some(record)) => return Some(Ok(record)),
+                Ok(None) => continue,
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
+
+/// Iterator over the records contained in the dBase, in the order of a tag
+/// of the production index
+pub struct TagRecordIterator<'a, T: Read + Seek, R: ReadableRecord> {
+    reader: &'a mut Reader<T>,
+    record_indices: std::vec::IntoIter<usize>,
+    record_type: std::marker::PhantomData<R>,
+}
+
+impl<T: Read + Seek, R: ReadableRecord> Iterator for TagRecordIterator<'_, T, R> {
+    type Item = Result<R, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let policy = self.reader.deletion_policy;
+        for index in self.record_indices.by_ref() {
+            match self.reader.read_record_at_with_policy::<R>(index, policy) {
+                Ok(None) => continue,
+                Ok(Some((_, record))) => return Some(Ok(record)),
+                Err(error) => return Some(Err(error)),
+            }
+        }
+        None
+    }
+}
+
+/// Decodes the field whose bytes start at `offset` in the data of the record
+fn decode_field<T: Read + Seek>(
+    record_data: &[u8],
+    offset: usize,
+    field_info: &FieldInfo,
+    null_flags_position: Option<usize>,
+    memo_reader: &mut Option<MemoReader<T>>,
+    encoding: &DynEncoding,
+    decode_errors: &mut DecodeErrors<'_>,
+) -> Result<FieldValue, FieldIOError> {
+    let field_error = |kind| FieldIOError::new(kind, Some(field_info.clone()));
+    // Whether the `bit` of the `_NullFlags` of the record is set
+    let is_flag_set = |bit: Option<u16>| match (bit, null_flags_position) {
+        (Some(bit), Some(position)) => record_data
+            .get(position + usize::from(bit / 8))
+            .is_some_and(|byte| byte & (1 << (bit % 8)) != 0),
+        _ => false,
+    };
+    if is_flag_set(field_info.null_bit) {
+        return Ok(FieldValue::null(field_info.field_type));
+    }
+    let mut field_data = record_data
+        .get(offset..offset + usize::from(field_info.length()))
+        .ok_or_else(|| field_error(ErrorKind::IoError(std::io::ErrorKind::UnexpectedEof.into())))?;
+    if is_flag_set(field_info.varlength_bit) {
+        // The last byte holds the length actually used
+        let length = field_data.last().copied().unwrap_or(0);
+        let length = usize::from(length).min(field_data.len().saturating_sub(1));
+        field_data = &field_data[..length];
+    }
+    let (value, decode_error) = FieldValue::read_with_policy(
+        field_data,
+        memo_reader,
+        field_info,
+        encoding,
+        decode_errors.policy,
+    )
+    .map_err(field_error)?;
+    if let Some(error) = decode_error {
+        decode_errors.diagnostics.push(DecodeDiagnostic {
+            record_index: decode_errors.record_index,
+            field_name: field_info.name.clone(),
+            message: error.to_string(),
+        });
+    }
+    Ok(value)
+}
 
-            let record = R::read_using(&mut iter)
-                .and_then(|record| iter.skip_remaining_fields().and(Ok(record)))
-                .map_err(|error| Error::new(error, self.current_record as usize));
-            self.current_record += 1;
-            Some(record)
+/// Returns the position of the `_NullFlags` field in the record, if there is one
+pub(crate) fn null_flags_position(fields_info: &[FieldInfo]) -> Option<usize> {
+    let mut position = 0;
+    for field_info in fields_info {
+        if field_info.field_type == FieldType::NullFlags {
+            return Some(position);
         }
+        position += field_info.field_length as usize;
     }
+    None
 }
 
 /// One liner to read the content of a .dbf file
@@ -633,7 +1420,7 @@ pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<Record>, Error> {
 #[cfg(test)]
 mod test {
     use std::fs::File;
-    use std::io::{Seek, SeekFrom};
+    use std::io::Seek;
 
     use super::*;
 
@@ -641,7 +1428,7 @@ mod test {
     fn pos_after_reading() {
         let file = File::open("tests/data/line.dbf").unwrap();
         let mut reader = Reader::new(file).unwrap();
-        let pos_after_reading = reader.source.seek(SeekFrom::Current(0)).unwrap();
+        let pos_after_reading = reader.source.stream_position().unwrap();
 
         // Do not count the the "DeletionFlag record info that is added
         let mut expected_pos = Header::SIZE + ((reader.fields_info.len() - 1) * FieldInfo::SIZE);
@@ -1,12 +1,14 @@
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use crate::Encoding;
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
 
-use crate::error::ErrorKind;
+use crate::error::{DecodeError, ErrorKind};
+use crate::reading::DecodeErrorPolicy;
 use crate::record::FieldInfo;
 use crate::writing::WritableAsDbaseField;
 
@@ -18,6 +20,19 @@ pub(crate) enum MemoFileType {
     FoxBaseMemo,
 }
 
+impl MemoFileType {
+    /// Returns the path of the memo file associated to the dbf file at `dbf_path`
+    pub(crate) fn file_path_for(self, dbf_path: &Path) -> PathBuf {
+        match self {
+            MemoFileType::DbaseMemo | MemoFileType::DbaseMemo4 => dbf_path.with_extension("dbt"),
+            MemoFileType::FoxBaseMemo => dbf_path.with_extension("fpt"),
+        }
+    }
+}
+
+/// dBase IV memo blocks start with 0xFFFF0800 followed by the length
+const DBASE4_MEMO_BLOCK_HEADER_SIZE: u32 = 8;
+
 /// Although there are different memo file type with each a different
 /// header organisation, we use the same struct internally
 #[derive(Debug, Copy, Clone)]
@@ -27,21 +42,40 @@ pub(crate) struct MemoHeader {
 }
 
 impl MemoHeader {
+    /// Size of the header, the first block that can hold data
+    /// is the one right after it
+    pub(crate) const SIZE: u32 = 512;
+
+    pub(crate) fn new(memo_type: MemoFileType) -> Self {
+        let block_size = match memo_type {
+            MemoFileType::DbaseMemo | MemoFileType::DbaseMemo4 => 512,
+            MemoFileType::FoxBaseMemo => 64,
+        };
+        Self {
+            next_available_block_index: Self::SIZE / block_size,
+            block_size,
+        }
+    }
+
     pub(crate) fn read_from<R: Read>(
         src: &mut R,
         memo_type: MemoFileType,
     ) -> std::io::Result<Self> {
-        let next_available_block_index = src.read_u32::<LittleEndian>()?;
-        let block_size = match memo_type {
+        let (next_available_block_index, block_size) = match memo_type {
             MemoFileType::DbaseMemo | MemoFileType::DbaseMemo4 => {
+                let next_available_block_index = src.read_u32::<LittleEndian>()?;
                 match src.read_u16::<LittleEndian>()? {
-                    0 => 512,
-                    v => u32::from(v),
+                    0 => (next_available_block_index, 512),
+                    v => (next_available_block_index, u32::from(v)),
                 }
             }
             MemoFileType::FoxBaseMemo => {
+                let next_available_block_index = src.read_u32::<BigEndian>()?;
                 let _ = src.read_u16::<BigEndian>();
-                u32::from(src.read_u16::<BigEndian>()?)
+                (
+                    next_available_block_index,
+                    u32::from(src.read_u16::<BigEndian>()?),
+                )
             }
         };
 
@@ -50,6 +84,48 @@ impl MemoHeader {
             block_size,
         })
     }
+
+    /// Writes the whole header, including the padding up to the first data block
+    pub(crate) fn write_to<W: Write>(
+        &self,
+        dst: &mut W,
+        memo_type: MemoFileType,
+    ) -> std::io::Result<()> {
+        let mut bytes = [0u8; Self::SIZE as usize];
+        match memo_type {
+            MemoFileType::DbaseMemo => {
+                bytes[..4].copy_from_slice(&self.next_available_block_index.to_le_bytes());
+                // dBase III version byte
+                bytes[16] = 0x03;
+            }
+            MemoFileType::DbaseMemo4 => {
+                bytes[..4].copy_from_slice(&self.next_available_block_index.to_le_bytes());
+                bytes[20..22].copy_from_slice(&(self.block_size as u16).to_le_bytes());
+            }
+            MemoFileType::FoxBaseMemo => {
+                bytes[..4].copy_from_slice(&self.next_available_block_index.to_be_bytes());
+                bytes[6..8].copy_from_slice(&(self.block_size as u16).to_be_bytes());
+            }
+        }
+        dst.write_all(&bytes)
+    }
+
+    /// Writes the index of the next available block at the start of the header,
+    /// leaving the other bytes of the header as they are
+    pub(crate) fn write_next_available_block_to<W: Write>(
+        &self,
+        dst: &mut W,
+        memo_type: MemoFileType,
+    ) -> std::io::Result<()> {
+        match memo_type {
+            MemoFileType::DbaseMemo | MemoFileType::DbaseMemo4 => {
+                dst.write_u32::<LittleEndian>(self.next_available_block_index)
+            }
+            MemoFileType::FoxBaseMemo => {
+                dst.write_u32::<BigEndian>(self.next_available_block_index)
+            }
+        }
+    }
 }
 
 /// Struct that reads knows how to read data from a memo source
@@ -73,9 +149,65 @@ impl<T: Read + Seek> MemoReader<T> {
         })
     }
 
+    pub(crate) fn block_size(&self) -> u32 {
+        self.header.block_size
+    }
+
+    /// Reads the memo entry starting at the block `index` as it is stored,
+    /// that is, including the block header (or terminator) but without
+    /// the padding of the last block
+    pub(crate) fn read_raw_entry_at(&mut self, index: u32) -> std::io::Result<&[u8]> {
+        match self.memo_file_type {
+            MemoFileType::FoxBaseMemo | MemoFileType::DbaseMemo4 => {
+                let byte_offset = u64::from(index) * u64::from(self.header.block_size);
+                self.source.seek(SeekFrom::Start(byte_offset))?;
+                let mut block_header = [0u8; DBASE4_MEMO_BLOCK_HEADER_SIZE as usize];
+                self.source.read_exact(&mut block_header)?;
+                let length = if self.memo_file_type == MemoFileType::FoxBaseMemo {
+                    BigEndian::read_u32(&block_header[4..]) + DBASE4_MEMO_BLOCK_HEADER_SIZE
+                } else {
+                    // The length includes the 8 bytes of the block header
+                    LittleEndian::read_u32(&block_header[4..]).max(DBASE4_MEMO_BLOCK_HEADER_SIZE)
+                } as usize;
+                if length > self.internal_buffer.len() {
+                    self.internal_buffer.resize(length, 0);
+                }
+                self.internal_buffer[..block_header.len()].copy_from_slice(&block_header);
+                self.source
+                    .read_exact(&mut self.internal_buffer[block_header.len()..length])?;
+                Ok(&self.internal_buffer[..length])
+            }
+            MemoFileType::DbaseMemo => {
+                let length = self.read_data_at(index)?.len();
+                self.internal_buffer.resize(length + 2, 0);
+                self.internal_buffer[length..].copy_from_slice(&[0x1A, 0x1A]);
+                Ok(&self.internal_buffer[..length + 2])
+            }
+        }
+    }
+
+    /// Reads the text stored at the given block index,
+    /// without the padding and terminators some writers add
     fn read_data_at(&mut self, index: u32) -> std::io::Result<&[u8]> {
-        let byte_offset = index * self.header.block_size;
-        self.source.seek(SeekFrom::Start(u64::from(byte_offset)))?;
+        let memo_file_type = self.memo_file_type;
+        let data = self.read_binary_data_at(index)?;
+        match memo_file_type {
+            MemoFileType::FoxBaseMemo => match data.iter().rposition(|b| *b != 0) {
+                Some(pos) => Ok(&data[..=pos]),
+                None => Ok(&data[..0]),
+            },
+            MemoFileType::DbaseMemo4 => match data.iter().position(|b| *b == 0x1F) {
+                Some(pos) => Ok(&data[..pos]),
+                None => Ok(data),
+            },
+            MemoFileType::DbaseMemo => Ok(data),
+        }
+    }
+
+    /// Reads the bytes stored at the given block index, as they are
+    fn read_binary_data_at(&mut self, index: u32) -> std::io::Result<&[u8]> {
+        let byte_offset = u64::from(index) * u64::from(self.header.block_size);
+        self.source.seek(SeekFrom::Start(byte_offset))?;
 
         match self.memo_file_type {
             MemoFileType::FoxBaseMemo => {
@@ -86,47 +218,241 @@ impl<T: Read + Seek> MemoReader<T> {
                 }
                 let buf_slice = &mut self.internal_buffer[..length as usize];
                 self.source.read_exact(buf_slice)?;
-                match buf_slice.iter().rposition(|b| *b != 0) {
-                    Some(pos) => Ok(&buf_slice[..=pos]),
-                    None => {
-                        if buf_slice.iter().all(|b| *b == 0) {
-                            Ok(&buf_slice[..0])
-                        } else {
-                            Ok(buf_slice)
-                        }
-                    }
-                }
+                Ok(buf_slice)
             }
             MemoFileType::DbaseMemo4 => {
                 let _ = self.source.read_u32::<LittleEndian>()?;
-                let length = self.source.read_u32::<LittleEndian>()?;
-                self.source
-                    .read_exact(&mut self.internal_buffer[..length as usize])?;
-                match self.internal_buffer[..length as usize]
-                    .iter()
-                    .position(|b| *b == 0x1F)
-                {
-                    Some(pos) => Ok(&self.internal_buffer[..pos]),
-                    None => Ok(&self.internal_buffer),
+                // The length includes the 8 bytes of the block header
+                let length =
+                    self.source
+                        .read_u32::<LittleEndian>()?
+                        .saturating_sub(DBASE4_MEMO_BLOCK_HEADER_SIZE) as usize;
+                if length > self.internal_buffer.len() {
+                    self.internal_buffer.resize(length, 0);
                 }
+                let buf_slice = &mut self.internal_buffer[..length];
+                self.source.read_exact(buf_slice)?;
+                Ok(buf_slice)
             }
             MemoFileType::DbaseMemo => {
-                if let Err(e) = self.source.read_exact(&mut self.internal_buffer) {
-                    if index != self.header.next_available_block_index - 1
-                        && e.kind() != std::io::ErrorKind::UnexpectedEof
-                    {
-                        return Err(e);
+                // The text spans as many blocks as needed
+                // and is terminated by 0x1A
+                let block_size = self.header.block_size as usize;
+                let mut length = 0;
+                loop {
+                    if self.internal_buffer.len() < length + block_size {
+                        self.internal_buffer.resize(length + block_size, 0);
+                    }
+                    let block = &mut self.internal_buffer[length..length + block_size];
+                    let num_read = read_as_much_as_possible(&mut self.source, block)?;
+                    if let Some(pos) = block[..num_read].iter().position(|b| *b == 0x1A) {
+                        return Ok(&self.internal_buffer[..length + pos]);
+                    }
+                    length += num_read;
+                    if num_read < block_size {
+                        return Ok(&self.internal_buffer[..length]);
                     }
-                }
-                match self.internal_buffer.iter().position(|b| *b == 0x1A) {
-                    Some(pos) => Ok(&self.internal_buffer[..pos]),
-                    None => Ok(&self.internal_buffer),
                 }
             }
         }
     }
 }
 
+/// Reads the index of the first memo block from the bytes of a Memo field
+///
+/// The index is either stored as text (dBase) or as a little endian u32 (Visual FoxPro),
+/// 0 means that there is no memo data.
+pub(crate) fn memo_index_from_bytes(field_bytes: &[u8]) -> Result<u32, ErrorKind> {
+    if field_bytes.len() > 4 {
+        let trimmed_value = trim_field_data(field_bytes);
+        if trimmed_value.is_empty() {
+            Ok(0)
+        } else {
+            let index = std::str::from_utf8(trimmed_value)
+                .map_err(|_| ErrorKind::Message("Memo index is not valid text".to_string()))?
+                .parse::<u32>()?;
+            Ok(index)
+        }
+    } else {
+        let mut le_bytes = [0u8; std::mem::size_of::<u32>()];
+        le_bytes.copy_from_slice(&field_bytes[..std::mem::size_of::<u32>()]);
+        Ok(u32::from_le_bytes(le_bytes))
+    }
+}
+
+/// Writes the index of the first memo block in the bytes of a Memo field,
+/// using the same representation as [memo_index_from_bytes]
+pub(crate) fn memo_index_to_bytes(index: u32, field_bytes: &mut [u8]) {
+    if field_bytes.len() > 4 {
+        let text = format!("{:>width$}", index, width = field_bytes.len());
+        field_bytes.copy_from_slice(&text.as_bytes()[..field_bytes.len()]);
+    } else {
+        field_bytes[..std::mem::size_of::<u32>()].copy_from_slice(&index.to_le_bytes());
+    }
+}
+
+/// Reads until the buffer is full or the end of the source is reached,
+/// returns the number of bytes read
+fn read_as_much_as_possible<T: Read>(source: &mut T, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut num_read = 0;
+    while num_read < buf.len() {
+        match source.read(&mut buf[num_read..]) {
+            Ok(0) => break,
+            Ok(n) => num_read += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(num_read)
+}
+
+/// Struct that knows how to write data to a memo file
+///
+/// Each memo is written at the next available block, and occupies
+/// as many blocks as needed.
+/// As blocks are always appended, writing data does not require to seek.
+#[derive(Debug)]
+pub(crate) struct MemoWriter<T: Write> {
+    memo_file_type: MemoFileType,
+    header: MemoHeader,
+    dst: T,
+}
+
+impl<T: Write + Seek> MemoWriter<T> {
+    /// Creates a new writer, writing the header of the memo file
+    pub(crate) fn new(memo_type: MemoFileType, dst: T) -> std::io::Result<Self> {
+        let header = MemoHeader::new(memo_type);
+        Self::with_block_size(memo_type, header.block_size, dst)
+    }
+
+    /// Creates a new writer using the given block size,
+    /// writing the header of the memo file
+    pub(crate) fn with_block_size(
+        memo_type: MemoFileType,
+        block_size: u32,
+        mut dst: T,
+    ) -> std::io::Result<Self> {
+        let header = MemoHeader {
+            next_available_block_index: MemoHeader::SIZE.div_ceil(block_size),
+            block_size,
+        };
+        dst.seek(SeekFrom::Start(0))?;
+        header.write_to(&mut dst, memo_type)?;
+        let first_block_offset = header.next_available_block_index * block_size;
+        for _ in MemoHeader::SIZE..first_block_offset {
+            dst.write_u8(0)?;
+        }
+        Ok(Self {
+            memo_file_type: memo_type,
+            header,
+            dst,
+        })
+    }
+
+    /// Creates a writer that appends data to an existing memo file
+    pub(crate) fn append_to(memo_type: MemoFileType, mut dst: T) -> std::io::Result<Self>
+    where
+        T: Read,
+    {
+        dst.seek(SeekFrom::Start(0))?;
+        let header = MemoHeader::read_from(&mut dst, memo_type)?;
+        Self::with_header(memo_type, header, dst)
+    }
+
+    /// Creates a writer that appends data to an existing memo file
+    /// for which the header was already read
+    pub(crate) fn with_header(
+        memo_type: MemoFileType,
+        header: MemoHeader,
+        mut dst: T,
+    ) -> std::io::Result<Self> {
+        let byte_offset =
+            u64::from(header.next_available_block_index) * u64::from(header.block_size);
+        dst.seek(SeekFrom::Start(byte_offset))?;
+        Ok(Self {
+            memo_file_type: memo_type,
+            header,
+            dst,
+        })
+    }
+
+    /// Updates the header so that it contains the next available block
+    pub(crate) fn close(&mut self) -> std::io::Result<()> {
+        self.dst.seek(SeekFrom::Start(0))?;
+        self.header
+            .write_next_available_block_to(&mut self.dst, self.memo_file_type)?;
+        self.dst.seek(SeekFrom::End(0))?;
+        self.dst.flush()
+    }
+}
+
+/// Trait for the destinations where memo data can be written,
+/// it allows the [FieldWriter](crate::FieldWriter) to not depend on the memo
+/// destination type.
+pub(crate) trait MemoDataWriter {
+    /// Writes the data in the next available block(s) and returns
+    /// the index of the first block used
+    fn write_data(&mut self, data: &[u8]) -> std::io::Result<u32>;
+
+    /// Writes an entry read with [MemoReader::read_raw_entry_at] in the next available
+    /// block(s) and returns the index of the first block used
+    fn write_raw_entry(&mut self, entry: &[u8]) -> std::io::Result<u32>;
+}
+
+impl<T: Write> MemoDataWriter for MemoWriter<T> {
+    /// The destination is expected to be positioned at the start of
+    /// the next available block.
+    fn write_data(&mut self, data: &[u8]) -> std::io::Result<u32> {
+        let index = self.header.next_available_block_index;
+
+        let num_bytes_written = match self.memo_file_type {
+            MemoFileType::FoxBaseMemo => {
+                // 1 means the block contains text
+                self.dst.write_u32::<BigEndian>(1)?;
+                self.dst.write_u32::<BigEndian>(data.len() as u32)?;
+                self.dst.write_all(data)?;
+                8 + data.len()
+            }
+            MemoFileType::DbaseMemo4 => {
+                self.dst.write_all(&[0xFF, 0xFF, 0x08, 0x00])?;
+                self.dst
+                    .write_u32::<LittleEndian>(data.len() as u32 + DBASE4_MEMO_BLOCK_HEADER_SIZE)?;
+                self.dst.write_all(data)?;
+                8 + data.len()
+            }
+            MemoFileType::DbaseMemo => {
+                self.dst.write_all(data)?;
+                self.dst.write_all(&[0x1A, 0x1A])?;
+                data.len() + 2
+            }
+        };
+
+        self.pad_last_block(num_bytes_written)?;
+        Ok(index)
+    }
+
+    fn write_raw_entry(&mut self, entry: &[u8]) -> std::io::Result<u32> {
+        let index = self.header.next_available_block_index;
+        self.dst.write_all(entry)?;
+        self.pad_last_block(entry.len())?;
+        Ok(index)
+    }
+}
+
+impl<T: Write> MemoWriter<T> {
+    /// Pads the last block used by the `num_bytes_written` and
+    /// updates the next available block
+    fn pad_last_block(&mut self, mut num_bytes_written: usize) -> std::io::Result<()> {
+        let block_size = self.header.block_size as usize;
+        while !num_bytes_written.is_multiple_of(block_size) {
+            self.dst.write_u8(0)?;
+            num_bytes_written += 1;
+        }
+        self.header.next_available_block_index += (num_bytes_written / block_size) as u32;
+        Ok(())
+    }
+}
+
 /// Enum listing all the field types we know of
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum FieldType {
@@ -140,12 +466,32 @@ pub enum FieldType {
     Currency,
     DateTime,
     Integer,
+    /// Visual FoxPro variable length string
+    Varchar,
+    /// Visual FoxPro variable length binary data
+    Varbinary,
     // Unknown
     Double,
     Memo,
-    //General,
-    //BinaryCharacter,
-    //BinaryMemo,
+    /// Memo whose content is binary data (Visual FoxPro)
+    BinaryMemo,
+    /// OLE object stored in the memo file
+    General,
+    /// Picture stored in the memo file (FoxPro)
+    Picture,
+    /// Binary data stored in the memo file (Visual FoxPro)
+    Blob,
+    // dBase 7
+    /// dBase 7 long integer, stored big-endian with the sign bit flipped
+    Long,
+    /// dBase 7 long integer incremented automatically, stored like [FieldType::Long]
+    Autoincrement,
+    /// dBase 7 date and time
+    Timestamp,
+    /// dBase 7 double, stored big-endian with the sign bit flipped
+    DBase7Double,
+    /// Visual FoxPro system field holding the null flags of the record
+    NullFlags,
 }
 
 impl From<FieldType> for u8 {
@@ -159,8 +505,18 @@ impl From<FieldType> for u8 {
             FieldType::Currency => 'Y',
             FieldType::DateTime => 'T',
             FieldType::Integer => 'I',
+            FieldType::Varchar => 'V',
+            FieldType::Varbinary => 'Q',
             FieldType::Double => 'B',
-            FieldType::Memo => 'M',
+            FieldType::Memo | FieldType::BinaryMemo => 'M',
+            FieldType::General => 'G',
+            FieldType::Picture => 'P',
+            FieldType::Blob => 'W',
+            FieldType::Long => 'I',
+            FieldType::Autoincrement => '+',
+            FieldType::Timestamp => '@',
+            FieldType::DBase7Double => 'O',
+            FieldType::NullFlags => '0',
         };
         v as u8
     }
@@ -181,12 +537,21 @@ impl FieldType {
             'Y' => Some(FieldType::Currency),
             'T' => Some(FieldType::DateTime),
             'I' => Some(FieldType::Integer),
+            'V' => Some(FieldType::Varchar),
+            'Q' => Some(FieldType::Varbinary),
             // unknown version
             'B' => Some(FieldType::Double),
             'M' => Some(FieldType::Memo),
-            //'G' => Some(FieldType::General),
-            //'C' => Some(FieldType::BinaryCharacter), ??
-            //'M' => Some(FieldType::BinaryMemo),
+            'G' => Some(FieldType::General),
+            'P' => Some(FieldType::Picture),
+            'W' => Some(FieldType::Blob),
+            // dBase 7 field types,
+            // 'I' is a Long and 'B' a binary memo in dBase 7 files
+            '+' => Some(FieldType::Autoincrement),
+            '@' => Some(FieldType::Timestamp),
+            'O' => Some(FieldType::DBase7Double),
+            // Visual FoxPro system field
+            '0' => Some(FieldType::NullFlags),
             _ => None,
         }
     }
@@ -205,9 +570,25 @@ impl FieldType {
             FieldType::Currency => Some(std::mem::size_of::<f64>() as u8),
             FieldType::DateTime => Some(2 * std::mem::size_of::<i32>() as u8),
             FieldType::Double => Some(std::mem::size_of::<f64>() as u8),
+            FieldType::Long | FieldType::Autoincrement => Some(std::mem::size_of::<i32>() as u8),
+            FieldType::Timestamp | FieldType::DBase7Double => {
+                Some(std::mem::size_of::<f64>() as u8)
+            }
             _ => None,
         }
     }
+
+    /// Returns whether the data of the field is stored in the memo file
+    pub(crate) fn is_memo(self) -> bool {
+        matches!(
+            self,
+            FieldType::Memo
+                | FieldType::BinaryMemo
+                | FieldType::General
+                | FieldType::Picture
+                | FieldType::Blob
+        )
+    }
 }
 
 impl TryFrom<char> for FieldType {
@@ -246,26 +627,164 @@ pub enum FieldValue {
     /// Another dBase type to represent numbers, stored as String in the file
     Float(Option<f32>),
     //Visual FoxPro fields
-    Integer(i32),
-    Currency(f64),
-    DateTime(DateTime),
-    Double(f64),
+    //
+    // These are stored in binary formats, they are `None`
+    // only when the field is nullable and its null flag is set
+    Integer(Option<i32>),
+    Currency(Option<f64>),
+    DateTime(Option<DateTime>),
+    Double(Option<f64>),
+    /// Visual FoxPro string whose length varies up to the field length
+    Varchar(Option<String>),
+    /// Visual FoxPro binary data whose length varies up to the field length
+    Varbinary(Option<Vec<u8>>),
 
     /// Memo is a dBase type that allows to store Strings
     /// that are longer than 255 bytes.
     /// These strings are stored in an external file
     /// called the `Memo file`
     Memo(String),
+    /// Memo holding binary data, read without decoding
+    BinaryMemo(Vec<u8>),
+    /// OLE object stored in the memo file
+    General(Vec<u8>),
+    /// Picture stored in the memo file
+    Picture(Vec<u8>),
+    /// Binary data stored in the memo file
+    Blob(Vec<u8>),
+    // dBase 7 fields
+    //
+    // These are stored in binary formats, they are `None`
+    // when all their bytes are zeros
+    Long(Option<i32>),
+    Autoincrement(Option<i32>),
+    Timestamp(Option<DateTime>),
+    DBase7Double(Option<f64>),
+}
+
+/// The string of a text field, or its bytes when they could not be decoded
+enum DecodedText {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+/// Decodes the string of a text field, following the `policy` when it cannot be decoded,
+/// in which case the error is stored in `decode_error`
+fn decode_text<E: Encoding>(
+    bytes: &[u8],
+    encoding: &E,
+    policy: DecodeErrorPolicy,
+    decode_error: &mut Option<DecodeError>,
+) -> Result<DecodedText, ErrorKind> {
+    let error = match encoding.decode(bytes) {
+        Ok(text) => return Ok(DecodedText::Text(text.into_owned())),
+        Err(error) => error,
+    };
+    let text = match policy {
+        DecodeErrorPolicy::Strict => return Err(ErrorKind::StringDecodeError(error)),
+        DecodeErrorPolicy::Replace => DecodedText::Text(encoding.decode_lossy(bytes).into_owned()),
+        DecodeErrorPolicy::KeepBytes => DecodedText::Bytes(bytes.to_vec()),
+    };
+    *decode_error = Some(error);
+    Ok(text)
+}
+
+/// Reads the memo data whose block index is stored in `field_bytes`
+///
+/// Binary data is returned as stored, without removing the padding and terminators
+/// some writers add after text
+fn read_memo_data<'a, T: Read + Seek>(
+    field_bytes: &[u8],
+    memo_reader: &'a mut Option<MemoReader<T>>,
+    binary: bool,
+) -> Result<&'a [u8], ErrorKind> {
+    let index_in_memo = match memo_index_from_bytes(field_bytes)? {
+        // Block 0 is the memo header, so it means no data
+        0 => return Ok(&[]),
+        index => index,
+    };
+
+    let memo_reader = memo_reader.as_mut().ok_or(ErrorKind::MissingMemoFile)?;
+    let data = if binary {
+        memo_reader.read_binary_data_at(index_in_memo)?
+    } else {
+        memo_reader.read_data_at(index_in_memo)?
+    };
+    Ok(data)
+}
+
+const SIGN_BIT_32: u32 = 1 << 31;
+const SIGN_BIT_64: u64 = 1 << 63;
+
+/// dBase 7 stores numbers big-endian with the sign bit flipped
+/// (and all bits flipped for negative doubles) so that they sort bytewise,
+/// a field full of zeros is empty
+fn read_dbase7_long(field_bytes: &[u8]) -> Option<i32> {
+    let mut be_bytes = [0u8; std::mem::size_of::<i32>()];
+    be_bytes.copy_from_slice(&field_bytes[..std::mem::size_of::<i32>()]);
+    match u32::from_be_bytes(be_bytes) {
+        0 => None,
+        bits => Some((bits ^ SIGN_BIT_32) as i32),
+    }
+}
+
+pub(crate) fn dbase7_long_bytes(value: i32) -> [u8; 4] {
+    ((value as u32) ^ SIGN_BIT_32).to_be_bytes()
+}
+
+fn read_dbase7_double(field_bytes: &[u8]) -> Option<f64> {
+    let mut be_bytes = [0u8; std::mem::size_of::<f64>()];
+    be_bytes.copy_from_slice(&field_bytes[..std::mem::size_of::<f64>()]);
+    match u64::from_be_bytes(be_bytes) {
+        0 => None,
+        bits if bits & SIGN_BIT_64 != 0 => Some(f64::from_bits(bits ^ SIGN_BIT_64)),
+        bits => Some(f64::from_bits(!bits)),
+    }
+}
+
+pub(crate) fn dbase7_double_bytes(value: f64) -> [u8; 8] {
+    let bits = value.to_bits();
+    let bits = if bits & SIGN_BIT_64 == 0 {
+        bits | SIGN_BIT_64
+    } else {
+        !bits
+    };
+    bits.to_be_bytes()
 }
 
 impl FieldValue {
     pub(crate) fn read_from<T: Read + Seek, E: Encoding>(
-        mut field_bytes: &[u8],
+        field_bytes: &[u8],
         memo_reader: &mut Option<MemoReader<T>>,
         field_info: &FieldInfo,
         encoding: &E,
     ) -> Result<Self, ErrorKind> {
-        debug_assert_eq!(field_bytes.len(), field_info.length() as usize);
+        Self::read_with_policy(
+            field_bytes,
+            memo_reader,
+            field_info,
+            encoding,
+            DecodeErrorPolicy::Strict,
+        )
+        .map(|(value, _)| value)
+    }
+
+    /// Reads the value of a field, the strings of Character, Varchar and Memo fields
+    /// that cannot be decoded being read following the `policy`
+    ///
+    /// The decoding error is returned alongside the value read that way.
+    pub(crate) fn read_with_policy<T: Read + Seek, E: Encoding>(
+        mut field_bytes: &[u8],
+        memo_reader: &mut Option<MemoReader<T>>,
+        field_info: &FieldInfo,
+        encoding: &E,
+        policy: DecodeErrorPolicy,
+    ) -> Result<(Self, Option<DecodeError>), ErrorKind> {
+        let mut decode_error = None;
+        // Variable length fields only give the bytes actually used
+        debug_assert!(
+            field_bytes.len() == field_info.length() as usize || field_info.varlength_bit.is_some()
+        );
         let value = match field_info.field_type {
             FieldType::Logical => match field_bytes[0] as char {
                 ' ' | '?' => FieldValue::Logical(None),
@@ -279,7 +798,10 @@ impl FieldValue {
                 if value.is_empty() {
                     FieldValue::Character(None)
                 } else {
-                    FieldValue::Character(Some(encoding.decode(value)?.to_string()))
+                    match decode_text(value, encoding, policy, &mut decode_error)? {
+                        DecodedText::Text(text) => FieldValue::Character(Some(text)),
+                        DecodedText::Bytes(bytes) => FieldValue::Varbinary(Some(bytes)),
+                    }
                 }
             }
             FieldType::Numeric => {
@@ -315,47 +837,108 @@ impl FieldValue {
             FieldType::Integer => {
                 let mut le_bytes = [0u8; std::mem::size_of::<i32>()];
                 le_bytes.copy_from_slice(&field_bytes[..std::mem::size_of::<i32>()]);
-                FieldValue::Integer(i32::from_le_bytes(le_bytes))
+                FieldValue::Integer(Some(i32::from_le_bytes(le_bytes)))
             }
             FieldType::Double => {
                 let mut le_bytes = [0u8; std::mem::size_of::<f64>()];
                 le_bytes.copy_from_slice(&field_bytes[..std::mem::size_of::<f64>()]);
-                FieldValue::Double(f64::from_le_bytes(le_bytes))
+                FieldValue::Double(Some(f64::from_le_bytes(le_bytes)))
             }
             FieldType::Currency => {
                 let mut le_bytes = [0u8; std::mem::size_of::<f64>()];
                 le_bytes.copy_from_slice(&field_bytes[..std::mem::size_of::<f64>()]);
-                FieldValue::Currency(f64::from_le_bytes(le_bytes))
+                FieldValue::Currency(Some(f64::from_le_bytes(le_bytes)))
             }
             FieldType::DateTime => {
                 let mut source = std::io::Cursor::new(&mut field_bytes);
-                FieldValue::DateTime(DateTime::read_from(&mut source)?)
+                FieldValue::DateTime(Some(DateTime::read_from(&mut source)?))
             }
+            FieldType::Varchar => {
+                match decode_text(field_bytes, encoding, policy, &mut decode_error)? {
+                    DecodedText::Text(text) => FieldValue::Varchar(Some(text)),
+                    DecodedText::Bytes(bytes) => FieldValue::Varbinary(Some(bytes)),
+                }
+            }
+            FieldType::Varbinary => FieldValue::Varbinary(Some(field_bytes.to_vec())),
             FieldType::Memo => {
-                let index_in_memo = if field_info.field_length > 4 {
-                    // let string = read_string_of_len(&mut source, field_info.field_length)?;
-                    let trimmed_value = trim_field_data(field_bytes);
-                    if trimmed_value.is_empty() {
-                        return Ok(FieldValue::Memo(String::from("")));
-                    } else {
-                        encoding.decode(trimmed_value)?.parse::<u32>()?
-                        // string.parse::<u32>()?
-                    }
-                } else {
-                    let mut le_bytes = [0u8; std::mem::size_of::<u32>()];
-                    le_bytes.copy_from_slice(&field_bytes[..std::mem::size_of::<u32>()]);
-                    u32::from_le_bytes(le_bytes)
-                };
-
-                if let Some(memo_reader) = memo_reader {
-                    let data_from_memo = memo_reader.read_data_at(index_in_memo)?;
-                    FieldValue::Memo(encoding.decode(data_from_memo)?.to_string())
-                } else {
-                    return Err(ErrorKind::MissingMemoFile);
+                let data_from_memo = read_memo_data(field_bytes, memo_reader, false)?;
+                match decode_text(data_from_memo, encoding, policy, &mut decode_error)? {
+                    DecodedText::Text(text) => FieldValue::Memo(text),
+                    DecodedText::Bytes(bytes) => FieldValue::BinaryMemo(bytes),
                 }
             }
+            FieldType::BinaryMemo => {
+                FieldValue::BinaryMemo(read_memo_data(field_bytes, memo_reader, true)?.to_vec())
+            }
+            FieldType::General => {
+                FieldValue::General(read_memo_data(field_bytes, memo_reader, true)?.to_vec())
+            }
+            FieldType::Picture => {
+                FieldValue::Picture(read_memo_data(field_bytes, memo_reader, true)?.to_vec())
+            }
+            FieldType::Blob => {
+                FieldValue::Blob(read_memo_data(field_bytes, memo_reader, true)?.to_vec())
+            }
+            FieldType::Long => FieldValue::Long(read_dbase7_long(field_bytes)),
+            FieldType::Autoincrement => FieldValue::Autoincrement(read_dbase7_long(field_bytes)),
+            FieldType::Timestamp => FieldValue::Timestamp(
+                read_dbase7_double(field_bytes).map(DateTime::from_dbase7_timestamp),
+            ),
+            FieldType::DBase7Double => FieldValue::DBase7Double(read_dbase7_double(field_bytes)),
+            // The null flags are not a value of the record,
+            // they are used when reading the other fields
+            FieldType::NullFlags => return Err(ErrorKind::IncompatibleType),
         };
-        Ok(value)
+        Ok((value, decode_error))
+    }
+
+    /// Returns the value of a field of the given type whose null flag is set
+    pub(crate) fn null(field_type: FieldType) -> Self {
+        match field_type {
+            FieldType::Character => FieldValue::Character(None),
+            FieldType::Numeric => FieldValue::Numeric(None),
+            FieldType::Logical => FieldValue::Logical(None),
+            FieldType::Date => FieldValue::Date(None),
+            FieldType::Float => FieldValue::Float(None),
+            FieldType::Integer => FieldValue::Integer(None),
+            FieldType::Currency => FieldValue::Currency(None),
+            FieldType::DateTime => FieldValue::DateTime(None),
+            FieldType::Double => FieldValue::Double(None),
+            FieldType::Varchar => FieldValue::Varchar(None),
+            FieldType::Varbinary => FieldValue::Varbinary(None),
+            FieldType::Memo => FieldValue::Memo(String::new()),
+            FieldType::BinaryMemo => FieldValue::BinaryMemo(Vec::new()),
+            FieldType::General => FieldValue::General(Vec::new()),
+            FieldType::Picture => FieldValue::Picture(Vec::new()),
+            FieldType::Blob => FieldValue::Blob(Vec::new()),
+            FieldType::Long => FieldValue::Long(None),
+            FieldType::Autoincrement => FieldValue::Autoincrement(None),
+            FieldType::Timestamp => FieldValue::Timestamp(None),
+            FieldType::DBase7Double => FieldValue::DBase7Double(None),
+            FieldType::NullFlags => unreachable!("the null flags field cannot be null"),
+        }
+    }
+
+    /// Returns whether the value is a None
+    pub(crate) fn is_none(&self) -> bool {
+        matches!(
+            self,
+            FieldValue::Character(None)
+                | FieldValue::Numeric(None)
+                | FieldValue::Logical(None)
+                | FieldValue::Date(None)
+                | FieldValue::Float(None)
+                | FieldValue::Integer(None)
+                | FieldValue::Currency(None)
+                | FieldValue::DateTime(None)
+                | FieldValue::Double(None)
+                | FieldValue::Varchar(None)
+                | FieldValue::Varbinary(None)
+                | FieldValue::Long(None)
+                | FieldValue::Autoincrement(None)
+                | FieldValue::Timestamp(None)
+                | FieldValue::DBase7Double(None)
+        )
     }
 
     /// Returns the corresponding field type of the contained value
@@ -369,8 +952,18 @@ impl FieldValue {
             FieldValue::Double(_) => FieldType::Double,
             FieldValue::Date(_) => FieldType::Date,
             FieldValue::Memo(_) => FieldType::Memo,
+            FieldValue::BinaryMemo(_) => FieldType::BinaryMemo,
+            FieldValue::General(_) => FieldType::General,
+            FieldValue::Picture(_) => FieldType::Picture,
+            FieldValue::Blob(_) => FieldType::Blob,
+            FieldValue::Long(_) => FieldType::Long,
+            FieldValue::Autoincrement(_) => FieldType::Autoincrement,
+            FieldValue::Timestamp(_) => FieldType::Timestamp,
+            FieldValue::DBase7Double(_) => FieldType::DBase7Double,
             FieldValue::Currency(_) => FieldType::Currency,
             FieldValue::DateTime(_) => FieldType::DateTime,
+            FieldValue::Varchar(_) => FieldType::Varchar,
+            FieldValue::Varbinary(_) => FieldType::Varbinary,
         }
     }
 }
@@ -432,7 +1025,7 @@ impl Date {
 
     // https://en.wikipedia.org/wiki/Julian_day
     // at "Julian or Gregorian calendar from Julian day number"
-    fn julian_day_number_to_gregorian_date(jdn: i32) -> Date {
+    pub(crate) fn julian_day_number_to_gregorian_date(jdn: i32) -> Date {
         const Y: i32 = 4716;
         const J: i32 = 1401;
         const M: i32 = 2;
@@ -462,7 +1055,7 @@ impl Date {
         }
     }
 
-    fn to_julian_day_number(&self) -> i32 {
+    pub(crate) fn to_julian_day_number(self) -> i32 {
         let (month, year) = if self.month > 2 {
             (self.month - 3, self.year)
         } else {
@@ -492,9 +1085,9 @@ impl FromStr for Date {
     }
 }
 
-impl std::string::ToString for Date {
-    fn to_string(&self) -> String {
-        format!("{:04}{:02}{:02}", self.year, self.month, self.day)
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}{:02}{:02}", self.year, self.month, self.day)
     }
 }
 
@@ -579,7 +1172,7 @@ impl Time {
         }
     }
 
-    fn to_time_word(&self) -> i32 {
+    fn to_time_word(self) -> i32 {
         let mut time_word = self.hours * Self::HOURS_FACTOR as u32;
         time_word += self.minutes * Self::MINUTES_FACTOR as u32;
         time_word += self.seconds * Self::SECONDS_FACTOR as u32;
@@ -623,9 +1216,35 @@ impl DateTime {
         dest.write_i32::<LittleEndian>(self.time.to_time_word())?;
         Ok(())
     }
+
+    /// dBase 7 timestamps are the number of milliseconds since 01/01/0001, plus one day
+    const DBASE7_TIMESTAMP_EPOCH_JULIAN_DAY: i64 = 1_721_425;
+    const MILLISECONDS_PER_DAY: i64 = 86_400_000;
+
+    fn from_dbase7_timestamp(milliseconds: f64) -> Self {
+        let milliseconds = milliseconds.round() as i64;
+        let days = milliseconds.div_euclid(Self::MILLISECONDS_PER_DAY);
+        let time_word = milliseconds.rem_euclid(Self::MILLISECONDS_PER_DAY);
+        Self {
+            date: Date::julian_day_number_to_gregorian_date(
+                (days + Self::DBASE7_TIMESTAMP_EPOCH_JULIAN_DAY) as i32,
+            ),
+            time: Time::from_word(time_word as i32),
+        }
+    }
+
+    fn to_dbase7_timestamp(self) -> f64 {
+        let days =
+            i64::from(self.date.to_julian_day_number()) - Self::DBASE7_TIMESTAMP_EPOCH_JULIAN_DAY;
+        (days * Self::MILLISECONDS_PER_DAY + i64::from(self.time.to_time_word())) as f64
+    }
 }
 
 impl WritableAsDbaseField for FieldValue {
+    fn is_none(&self) -> bool {
+        FieldValue::is_none(self)
+    }
+
     fn write_as<E: Encoding, W: Write>(
         &self,
         field_info: &FieldInfo,
@@ -645,7 +1264,18 @@ impl WritableAsDbaseField for FieldValue {
                 FieldValue::Currency(value) => value.write_as(field_info, encoding, dst),
                 FieldValue::DateTime(value) => value.write_as(field_info, encoding, dst),
                 FieldValue::Double(value) => value.write_as(field_info, encoding, dst),
-                FieldValue::Memo(_) => unimplemented!("Cannot write memo"),
+                FieldValue::Varchar(value) => value.write_as(field_info, encoding, dst),
+                FieldValue::Varbinary(value) => value.write_as(field_info, encoding, dst),
+                FieldValue::Memo(value) => value.write_as(field_info, encoding, dst),
+                FieldValue::BinaryMemo(value)
+                | FieldValue::General(value)
+                | FieldValue::Picture(value)
+                | FieldValue::Blob(value) => value.write_as(field_info, encoding, dst),
+                FieldValue::Long(value) | FieldValue::Autoincrement(value) => {
+                    value.write_as(field_info, encoding, dst)
+                }
+                FieldValue::Timestamp(value) => value.write_as(field_info, encoding, dst),
+                FieldValue::DBase7Double(value) => value.write_as(field_info, encoding, dst),
             }
         }
     }
@@ -666,13 +1296,17 @@ impl WritableAsDbaseField for f64 {
                     precision = field_info.num_decimal_places as usize
                 );
                 let encoded_string = encoding.encode(&string)?;
-                dst.write_all(&*encoded_string)?;
+                dst.write_all(&encoded_string)?;
                 Ok(())
             }
             FieldType::Currency | FieldType::Double => {
                 dst.write_f64::<LittleEndian>(*self)?;
                 Ok(())
             }
+            FieldType::DBase7Double => {
+                dst.write_all(&dbase7_double_bytes(*self))?;
+                Ok(())
+            }
             _ => Err(ErrorKind::IncompatibleType),
         }
     }
@@ -688,7 +1322,7 @@ impl WritableAsDbaseField for Date {
         if field_info.field_type == FieldType::Date {
             let string = format!("{:04}{:02}{:02}", self.year, self.month, self.day);
             let encoded_string = encoding.encode(&string)?;
-            dst.write_all(&*encoded_string)?;
+            dst.write_all(&encoded_string)?;
             Ok(())
         } else {
             Err(ErrorKind::IncompatibleType)
@@ -697,6 +1331,10 @@ impl WritableAsDbaseField for Date {
 }
 
 impl WritableAsDbaseField for Option<Date> {
+    fn is_none(&self) -> bool {
+        self.is_none()
+    }
+
     fn write_as<E: Encoding, W: Write>(
         &self,
         field_info: &FieldInfo,
@@ -719,20 +1357,24 @@ impl WritableAsDbaseField for Option<Date> {
 }
 
 impl WritableAsDbaseField for Option<f64> {
+    fn is_none(&self) -> bool {
+        self.is_none()
+    }
+
     fn write_as<E: Encoding, W: Write>(
         &self,
         field_info: &FieldInfo,
         encoding: &E,
         dst: &mut W,
     ) -> Result<(), ErrorKind> {
-        if field_info.field_type == FieldType::Numeric {
-            if let Some(value) = self {
-                value.write_as(field_info, encoding, dst)
-            } else {
+        match (field_info.field_type, self) {
+            (_, Some(value)) => value.write_as(field_info, encoding, dst),
+            (FieldType::Numeric, None) => Ok(()),
+            (FieldType::Currency | FieldType::Double | FieldType::DBase7Double, None) => {
+                dst.write_all(&[0u8; std::mem::size_of::<f64>()])?;
                 Ok(())
             }
-        } else {
-            Err(ErrorKind::IncompatibleType)
+            _ => Err(ErrorKind::IncompatibleType),
         }
     }
 }
@@ -751,7 +1393,7 @@ impl WritableAsDbaseField for f32 {
                 precision = field_info.num_decimal_places as usize
             );
             let encoded_string = encoding.encode(&string)?;
-            dst.write_all(&*encoded_string)?;
+            dst.write_all(&encoded_string)?;
             Ok(())
         } else {
             Err(ErrorKind::IncompatibleType)
@@ -760,6 +1402,10 @@ impl WritableAsDbaseField for f32 {
 }
 
 impl WritableAsDbaseField for Option<f32> {
+    fn is_none(&self) -> bool {
+        self.is_none()
+    }
+
     fn write_as<E: Encoding, W: Write>(
         &self,
         field_info: &FieldInfo,
@@ -777,6 +1423,16 @@ impl WritableAsDbaseField for Option<f32> {
     }
 }
 
+/// Strings can be written to Character and Varchar fields, or to Memo fields
+/// in which case the bytes written are the content of the memo,
+/// and the [FieldWriter](crate::FieldWriter) takes care of storing them in the memo file
+fn is_string_field(field_type: FieldType) -> bool {
+    matches!(
+        field_type,
+        FieldType::Character | FieldType::Varchar | FieldType::Memo
+    )
+}
+
 impl WritableAsDbaseField for String {
     fn write_as<E: Encoding, W: Write>(
         &self,
@@ -784,9 +1440,9 @@ impl WritableAsDbaseField for String {
         encoding: &E,
         dst: &mut W,
     ) -> Result<(), ErrorKind> {
-        if field_info.field_type == FieldType::Character {
+        if is_string_field(field_info.field_type) {
             let encoded_bytes = encoding.encode(self.as_str())?;
-            dst.write_all(&*encoded_bytes)?;
+            dst.write_all(&encoded_bytes)?;
             Ok(())
         } else {
             Err(ErrorKind::IncompatibleType)
@@ -795,13 +1451,17 @@ impl WritableAsDbaseField for String {
 }
 
 impl WritableAsDbaseField for Option<String> {
+    fn is_none(&self) -> bool {
+        self.is_none()
+    }
+
     fn write_as<E: Encoding, W: Write>(
         &self,
         field_info: &FieldInfo,
         encoding: &E,
         dst: &mut W,
     ) -> Result<(), ErrorKind> {
-        if field_info.field_type == FieldType::Character {
+        if is_string_field(field_info.field_type) {
             if let Some(s) = self {
                 s.write_as(field_info, encoding, dst)?;
             }
@@ -819,9 +1479,59 @@ impl WritableAsDbaseField for &str {
         encoding: &E,
         dst: &mut W,
     ) -> Result<(), ErrorKind> {
-        if field_info.field_type == FieldType::Character {
+        if is_string_field(field_info.field_type) {
             let encoded_bytes = encoding.encode(self)?;
-            dst.write_all(&*encoded_bytes)?;
+            dst.write_all(&encoded_bytes)?;
+            Ok(())
+        } else {
+            Err(ErrorKind::IncompatibleType)
+        }
+    }
+}
+
+/// Bytes can be written to Varbinary fields, or to the binary fields stored in the memo file
+fn is_binary_field(field_type: FieldType) -> bool {
+    matches!(
+        field_type,
+        FieldType::Varbinary
+            | FieldType::BinaryMemo
+            | FieldType::General
+            | FieldType::Picture
+            | FieldType::Blob
+    )
+}
+
+impl WritableAsDbaseField for Vec<u8> {
+    fn write_as<E: Encoding, W: Write>(
+        &self,
+        field_info: &FieldInfo,
+        _encoding: &E,
+        dst: &mut W,
+    ) -> Result<(), ErrorKind> {
+        if is_binary_field(field_info.field_type) {
+            dst.write_all(self)?;
+            Ok(())
+        } else {
+            Err(ErrorKind::IncompatibleType)
+        }
+    }
+}
+
+impl WritableAsDbaseField for Option<Vec<u8>> {
+    fn is_none(&self) -> bool {
+        self.is_none()
+    }
+
+    fn write_as<E: Encoding, W: Write>(
+        &self,
+        field_info: &FieldInfo,
+        encoding: &E,
+        dst: &mut W,
+    ) -> Result<(), ErrorKind> {
+        if is_binary_field(field_info.field_type) {
+            if let Some(bytes) = self {
+                bytes.write_as(field_info, encoding, dst)?;
+            }
             Ok(())
         } else {
             Err(ErrorKind::IncompatibleType)
@@ -839,10 +1549,10 @@ impl WritableAsDbaseField for bool {
         if field_info.field_type == FieldType::Logical {
             if *self {
                 let encoded_bytes = encoding.encode("t")?;
-                dst.write_all(&*encoded_bytes)?;
+                dst.write_all(&encoded_bytes)?;
             } else {
                 let encoded_bytes = encoding.encode("f")?;
-                dst.write_all(&*encoded_bytes)?;
+                dst.write_all(&encoded_bytes)?;
             }
             Ok(())
         } else {
@@ -852,6 +1562,10 @@ impl WritableAsDbaseField for bool {
 }
 
 impl WritableAsDbaseField for Option<bool> {
+    fn is_none(&self) -> bool {
+        self.is_none()
+    }
+
     fn write_as<E: Encoding, W: Write>(
         &self,
         field_info: &FieldInfo,
@@ -876,11 +1590,60 @@ impl WritableAsDbaseField for i32 {
         _encoding: &E,
         dst: &mut W,
     ) -> Result<(), ErrorKind> {
-        if field_info.field_type == FieldType::Integer {
-            dst.write_i32::<LittleEndian>(*self)?;
-            Ok(())
-        } else {
-            Err(ErrorKind::IncompatibleType)
+        match field_info.field_type {
+            FieldType::Integer => {
+                dst.write_i32::<LittleEndian>(*self)?;
+                Ok(())
+            }
+            FieldType::Long | FieldType::Autoincrement => {
+                dst.write_all(&dbase7_long_bytes(*self))?;
+                Ok(())
+            }
+            _ => Err(ErrorKind::IncompatibleType),
+        }
+    }
+}
+
+impl WritableAsDbaseField for Option<i32> {
+    fn is_none(&self) -> bool {
+        self.is_none()
+    }
+
+    fn write_as<E: Encoding, W: Write>(
+        &self,
+        field_info: &FieldInfo,
+        encoding: &E,
+        dst: &mut W,
+    ) -> Result<(), ErrorKind> {
+        match (field_info.field_type, self) {
+            (_, Some(value)) => value.write_as(field_info, encoding, dst),
+            (FieldType::Integer | FieldType::Long | FieldType::Autoincrement, None) => {
+                dst.write_i32::<LittleEndian>(0)?;
+                Ok(())
+            }
+            _ => Err(ErrorKind::IncompatibleType),
+        }
+    }
+}
+
+impl WritableAsDbaseField for Option<DateTime> {
+    fn is_none(&self) -> bool {
+        self.is_none()
+    }
+
+    fn write_as<E: Encoding, W: Write>(
+        &self,
+        field_info: &FieldInfo,
+        encoding: &E,
+        dst: &mut W,
+    ) -> Result<(), ErrorKind> {
+        match (field_info.field_type, self) {
+            (_, Some(value)) => value.write_as(field_info, encoding, dst),
+            (FieldType::DateTime | FieldType::Timestamp, None) => {
+                dst.write_all(&[0u8; 2 * std::mem::size_of::<i32>()])?;
+                Ok(())
+            }
+            _ => Err(ErrorKind::IncompatibleType),
         }
     }
 }
@@ -892,11 +1655,16 @@ impl WritableAsDbaseField for DateTime {
         _encoding: &E,
         dst: &mut W,
     ) -> Result<(), ErrorKind> {
-        if field_info.field_type == FieldType::DateTime {
-            self.write_to(dst)?;
-            Ok(())
-        } else {
-            Err(ErrorKind::IncompatibleType)
+        match field_info.field_type {
+            FieldType::DateTime => {
+                self.write_to(dst)?;
+                Ok(())
+            }
+            FieldType::Timestamp => {
+                dst.write_all(&dbase7_double_bytes(self.to_dbase7_timestamp()))?;
+                Ok(())
+            }
+            _ => Err(ErrorKind::IncompatibleType),
         }
     }
 }
@@ -999,7 +1767,7 @@ mod ser {
     }
 }
 
-fn trim_field_data(bytes: &[u8]) -> &[u8] {
+pub(crate) fn trim_field_data(bytes: &[u8]) -> &[u8] {
     // Value in the dbf file is surrounded by space characters (32u8). We discard them before
     // parsing the bytes into string. Doing so doubles the performance in comparison to
     // using String::trim() afterwards.
@@ -1051,9 +1819,11 @@ mod test {
             displacement_field: [0u8; 4],
             field_length: len,
             num_decimal_places: 0,
-            flags: FieldFlags { 0: 0u8 },
-            autoincrement_next_val: [0u8; 5],
+            flags: FieldFlags(0u8),
+            autoincrement_next_val: 0,
             autoincrement_step: 0u8,
+            null_bit: None,
+            varlength_bit: None,
         }
     }
 
@@ -1078,8 +1848,8 @@ mod test {
     fn write_read_date() {
         let date = FieldValue::from(Date {
             year: 2019,
-            month: 01,
-            day: 01,
+            month: 1,
+            day: 1,
         });
 
         let field_info = create_temp_field_info(FieldType::Date, FieldType::Date.size().unwrap());
@@ -1106,7 +1876,7 @@ mod test {
     fn test_write_read_integer_via_enum() {
         use crate::record::FieldName;
 
-        let value = FieldValue::Integer(1457);
+        let value = FieldValue::Integer(Some(1457));
 
         let field_info = FieldInfo::new(
             FieldName::try_from("Integer").unwrap(),
@@ -1117,11 +1887,66 @@ mod test {
         test_we_can_read_back(&field_info, &value);
     }
 
+    fn test_memo_write_read_back(memo_type: MemoFileType) {
+        let long_text = "A rather long memo text. ".repeat(50);
+        let texts = ["short text", long_text.as_str(), "another one"];
+
+        let mut memo_writer = MemoWriter::new(memo_type, Cursor::new(Vec::<u8>::new())).unwrap();
+        let indices = texts
+            .iter()
+            .map(|text| memo_writer.write_data(text.as_bytes()).unwrap())
+            .collect::<Vec<u32>>();
+        memo_writer.close().unwrap();
+
+        let mut cursor = memo_writer.dst;
+        cursor.set_position(0);
+        let mut memo_reader = MemoReader::new(memo_type, cursor).unwrap();
+        for (text, index) in texts.iter().zip(indices) {
+            let data = memo_reader.read_data_at(index).unwrap();
+            assert_eq!(data, text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn close_keeps_the_rest_of_the_memo_header() {
+        let mut memo_writer =
+            MemoWriter::new(MemoFileType::DbaseMemo4, Cursor::new(Vec::<u8>::new())).unwrap();
+        memo_writer.close().unwrap();
+        let mut memo_file = memo_writer.dst;
+        // Name of the table and block length written by dBase IV, not modeled by MemoHeader
+        memo_file.get_mut()[8..16].copy_from_slice(b"STATIONS");
+        memo_file.get_mut()[18..20].copy_from_slice(&[0x02, 0x01]);
+
+        let mut memo_writer = MemoWriter::append_to(MemoFileType::DbaseMemo4, memo_file).unwrap();
+        let index = memo_writer.write_data(b"appended").unwrap();
+        memo_writer.close().unwrap();
+
+        let memo_file = memo_writer.dst.into_inner();
+        assert_eq!(&memo_file[8..16], b"STATIONS");
+        assert_eq!(&memo_file[18..20], &[0x02, 0x01]);
+        assert_eq!(&memo_file[..4], &(index + 1).to_le_bytes());
+    }
+
+    #[test]
+    fn write_read_dbase_memo() {
+        test_memo_write_read_back(MemoFileType::DbaseMemo);
+    }
+
+    #[test]
+    fn write_read_dbase4_memo() {
+        test_memo_write_read_back(MemoFileType::DbaseMemo4);
+    }
+
+    #[test]
+    fn write_read_foxbase_memo() {
+        test_memo_write_read_back(MemoFileType::FoxBaseMemo);
+    }
+
     #[test]
     fn test_from_julian_day_number() {
         let date = Date::julian_day_number_to_gregorian_date(2458685);
         assert_eq!(date.year, 2019);
-        assert_eq!(date.month, 07);
+        assert_eq!(date.month, 7);
         assert_eq!(date.day, 20);
     }
 
@@ -1129,9 +1954,34 @@ mod test {
     fn test_to_julian_day_number() {
         let date = Date {
             year: 2019,
-            month: 07,
+            month: 7,
             day: 20,
         };
         assert_eq!(date.to_julian_day_number(), 2458685);
     }
+
+    #[test]
+    fn dbase7_numbers_sort_as_bytes() {
+        let longs = [i32::MIN, -42, -1, 1, 42, i32::MAX];
+        for pair in longs.windows(2) {
+            assert!(dbase7_long_bytes(pair[0]) < dbase7_long_bytes(pair[1]));
+        }
+        let doubles = [-1e10, -0.5, 0.25, 3.0, 1e10];
+        for pair in doubles.windows(2) {
+            assert!(dbase7_double_bytes(pair[0]) < dbase7_double_bytes(pair[1]));
+        }
+        assert_eq!(read_dbase7_long(&dbase7_long_bytes(-42)), Some(-42));
+        assert_eq!(read_dbase7_double(&dbase7_double_bytes(-0.5)), Some(-0.5));
+    }
+
+    #[test]
+    fn write_read_dbase7_timestamp() {
+        let value = FieldValue::Timestamp(Some(DateTime::new(
+            Date::new(29, 2, 2000),
+            Time::new(23, 59, 58),
+        )));
+        let field_info =
+            create_temp_field_info(FieldType::Timestamp, FieldType::Timestamp.size().unwrap());
+        test_we_can_read_back(&field_info, &value);
+    }
 }
@@ -1,7 +1,7 @@
 use std::convert::TryFrom;
 use std::io::{Read, Write};
 
-use byteorder::{ReadBytesExt, WriteBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
 pub mod field;
 
@@ -9,7 +9,9 @@ use self::field::{Date, DateTime, FieldType};
 use crate::{Encoding, ErrorKind, FieldValue};
 
 const DELETION_FLAG_NAME: &str = "DeletionFlag";
+const NULL_FLAGS_NAME: &str = "_NullFlags";
 const FIELD_NAME_LENGTH: usize = 11;
+const DBASE7_FIELD_NAME_LENGTH: usize = 32;
 
 #[derive(Debug)]
 /// Wrapping struct to create a FieldName from a String.
@@ -31,7 +33,7 @@ impl TryFrom<&str> for FieldName {
     type Error = &'static str;
 
     fn try_from(name: &str) -> Result<Self, Self::Error> {
-        if name.as_bytes().len() > FIELD_NAME_LENGTH {
+        if name.len() > FIELD_NAME_LENGTH {
             Err("FieldName byte representation cannot exceed 11 bytes")
         } else {
             Ok(Self(name.to_string()))
@@ -39,6 +41,28 @@ impl TryFrom<&str> for FieldName {
     }
 }
 
+impl FieldName {
+    /// Creates a FieldName for a dBase 7 file, where names can be up to 32 bytes long.
+    ///
+    /// If the name is used in a file of another version it is truncated to 11 bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dbase::FieldName;
+    ///
+    /// assert!(FieldName::dbase7("A rather long field name").is_ok());
+    /// assert!(FieldName::dbase7(&"N".repeat(33)).is_err());
+    /// ```
+    pub fn dbase7(name: &str) -> Result<Self, &'static str> {
+        if name.len() > DBASE7_FIELD_NAME_LENGTH {
+            Err("dBase 7 FieldName byte representation cannot exceed 32 bytes")
+        } else {
+            Ok(Self(name.to_string()))
+        }
+    }
+}
+
 /// Struct giving the info for a record field
 #[derive(Debug, PartialEq, Clone)]
 pub struct FieldInfo {
@@ -50,12 +74,23 @@ pub struct FieldInfo {
     pub(crate) field_length: u8,
     pub(crate) num_decimal_places: u8,
     pub(crate) flags: FieldFlags,
-    pub(crate) autoincrement_next_val: [u8; 5],
+    /// The value given to the next record, for autoincrement fields
+    pub(crate) autoincrement_next_val: u32,
     pub(crate) autoincrement_step: u8,
+    /// Index of the bit telling if the field is null in the `_NullFlags` field
+    pub(crate) null_bit: Option<u16>,
+    /// Index of the bit telling if a variable length field does not use its full length
+    /// (its last byte then holds the length) in the `_NullFlags` field
+    pub(crate) varlength_bit: Option<u16>,
 }
 
 impl FieldInfo {
     pub(crate) const SIZE: usize = 32;
+    pub(crate) const DBASE7_SIZE: usize = 48;
+    /// Position of the autoincrement next value in the descriptor
+    pub(crate) const AUTOINCREMENT_OFFSET: u64 = 19;
+    /// Position of the autoincrement next value in the dBase 7 descriptor
+    pub(crate) const DBASE7_AUTOINCREMENT_OFFSET: u64 = 40;
 
     pub fn name(&self) -> &str {
         &self.name
@@ -69,6 +104,32 @@ impl FieldInfo {
         self.field_length
     }
 
+    /// Returns the flags of the field (only used by Visual FoxPro)
+    pub fn flags(&self) -> FieldFlags {
+        self.flags
+    }
+
+    /// Returns whether the values of this field are assigned by the writer
+    ///
+    /// This is the case of dBase 7 [Autoincrement](FieldType::Autoincrement) fields
+    /// and Visual FoxPro [Integer](FieldType::Integer) fields with the autoincrement flag.
+    pub fn is_autoincrement(&self) -> bool {
+        self.field_type == FieldType::Autoincrement
+            || (self.field_type == FieldType::Integer && self.flags.is_autoincrement())
+    }
+
+    /// Returns the value the next record will get, if the field is an autoincrement one
+    pub fn autoincrement_next_value(&self) -> Option<u32> {
+        self.is_autoincrement()
+            .then_some(self.autoincrement_next_val)
+    }
+
+    /// Returns the difference between two consecutive values,
+    /// if the field is an autoincrement one
+    pub fn autoincrement_step(&self) -> Option<u8> {
+        self.is_autoincrement().then_some(self.autoincrement_step)
+    }
+
     pub(crate) fn new(name: FieldName, field_type: FieldType, length: u8) -> Self {
         Self {
             name: name.0,
@@ -77,8 +138,10 @@ impl FieldInfo {
             field_length: length,
             num_decimal_places: 0,
             flags: FieldFlags::default(),
-            autoincrement_next_val: [0u8; 5],
+            autoincrement_next_val: 0,
             autoincrement_step: 0u8,
+            null_bit: None,
+            varlength_bit: None,
         }
     }
 
@@ -105,12 +168,10 @@ impl FieldInfo {
 
         let flags = FieldFlags(source.read_u8()?);
 
-        let mut autoincrement_next_val = [0u8; 5];
-
-        source.read_exact(&mut autoincrement_next_val)?;
+        let autoincrement_next_val = source.read_u32::<LittleEndian>()?;
         let autoincrement_step = source.read_u8()?;
 
-        let mut _reserved = [0u8; 7];
+        let mut _reserved = [0u8; 8];
         source.read_exact(&mut _reserved)?;
 
         let s = encoding
@@ -118,7 +179,10 @@ impl FieldInfo {
             .trim_matches(|c| c == '\u{0}')
             .to_owned();
 
-        let field_type = FieldType::try_from(field_type as char)?;
+        let field_type = match FieldType::try_from(field_type as char)? {
+            FieldType::Memo if flags.is_binary() => FieldType::BinaryMemo,
+            field_type => field_type,
+        };
 
         Ok(Self {
             name: s,
@@ -129,24 +193,86 @@ impl FieldInfo {
             flags,
             autoincrement_next_val,
             autoincrement_step,
+            null_bit: None,
+            varlength_bit: None,
+        })
+    }
+
+    /// Reads a dBase 7 field descriptor
+    ///
+    /// The differences with other versions are the longer name,
+    /// the absence of the VFP flags, and the meaning of some type codes
+    pub(crate) fn read_dbase7_from<T: Read>(source: &mut T) -> Result<Self, ErrorKind> {
+        let mut name = [0u8; DBASE7_FIELD_NAME_LENGTH];
+        source.read_exact(&mut name)?;
+        let field_type = source.read_u8()?;
+        let field_length = source.read_u8()?;
+        let num_decimal_places = source.read_u8()?;
+        let mut _reserved = [0u8; 2];
+        source.read_exact(&mut _reserved)?;
+        let _production_mdx_field_flag = source.read_u8()?;
+        source.read_exact(&mut _reserved)?;
+        let autoincrement_next_val = source.read_u32::<LittleEndian>()?;
+        let mut _reserved = [0u8; 4];
+        source.read_exact(&mut _reserved)?;
+
+        let name = crate::encoding::Ascii
+            .decode(&name)?
+            .trim_matches(|c| c == '\u{0}')
+            .to_owned();
+
+        let field_type = match field_type {
+            b'I' => FieldType::Long,
+            b'B' => FieldType::BinaryMemo,
+            c => FieldType::try_from(c as char)?,
+        };
+
+        Ok(Self {
+            name,
+            field_type,
+            displacement_field: [0u8; 4],
+            field_length,
+            num_decimal_places,
+            flags: FieldFlags::default(),
+            autoincrement_next_val,
+            // dBase 7 values always increase by one
+            autoincrement_step: 1u8,
+            null_bit: None,
+            varlength_bit: None,
         })
     }
 
+    /// Writes the field descriptor in the dBase 7 format
+    pub(crate) fn write_dbase7_to<T: Write>(&self, dest: &mut T) -> std::io::Result<()> {
+        dest.write_all(&name_bytes::<DBASE7_FIELD_NAME_LENGTH>(&self.name))?;
+        let field_type = match self.field_type {
+            FieldType::BinaryMemo => b'B',
+            field_type => u8::from(field_type),
+        };
+        dest.write_u8(field_type)?;
+        dest.write_u8(self.field_length)?;
+        dest.write_u8(self.num_decimal_places)?;
+        dest.write_all(&[0u8; 2])?;
+        // Production .mdx field flag
+        dest.write_u8(0)?;
+        dest.write_all(&[0u8; 2])?;
+        dest.write_u32::<LittleEndian>(self.autoincrement_next_val)?;
+        dest.write_all(&[0u8; 4])?;
+        Ok(())
+    }
+
     pub(crate) fn write_to<T: Write>(&self, dest: &mut T) -> std::io::Result<()> {
-        let num_bytes = self.name.as_bytes().len();
-        let mut name_bytes = [0u8; FIELD_NAME_LENGTH];
-        name_bytes[..num_bytes.min(FIELD_NAME_LENGTH)].copy_from_slice(self.name.as_bytes());
-        dest.write_all(&name_bytes)?;
+        dest.write_all(&name_bytes::<FIELD_NAME_LENGTH>(&self.name))?;
 
         dest.write_u8(u8::from(self.field_type))?;
         dest.write_all(&self.displacement_field)?;
         dest.write_u8(self.field_length)?;
         dest.write_u8(self.num_decimal_places)?;
         dest.write_u8(self.flags.0)?;
-        dest.write_all(&self.autoincrement_next_val)?;
+        dest.write_u32::<LittleEndian>(self.autoincrement_next_val)?;
         dest.write_u8(self.autoincrement_step)?;
 
-        let reserved = [0u8; 7];
+        let reserved = [0u8; 8];
         dest.write_all(&reserved)?;
 
         Ok(())
@@ -160,14 +286,81 @@ impl FieldInfo {
             field_length: 1,
             num_decimal_places: 0,
             flags: FieldFlags(0u8),
-            autoincrement_next_val: [0u8; 5],
+            autoincrement_next_val: 0,
             autoincrement_step: 0u8,
+            null_bit: None,
+            varlength_bit: None,
         }
     }
 
     pub(crate) fn is_deletion_flag(&self) -> bool {
         self.name == DELETION_FLAG_NAME
     }
+
+    /// Creates the Visual FoxPro system field that stores `num_bits` null flags
+    pub(crate) fn new_null_flags(num_bits: usize) -> Self {
+        Self {
+            name: NULL_FLAGS_NAME.to_owned(),
+            field_type: FieldType::NullFlags,
+            displacement_field: [0u8; 4],
+            field_length: num_bits.div_ceil(8).max(1) as u8,
+            num_decimal_places: 0,
+            flags: FieldFlags(FieldFlags::SYSTEM | FieldFlags::BINARY),
+            autoincrement_next_val: 0,
+            autoincrement_step: 0u8,
+            null_bit: None,
+            varlength_bit: None,
+        }
+    }
+
+    /// Returns whether the field is not part of the record data
+    /// (the deletion flag and the null flags)
+    pub(crate) fn is_hidden(&self) -> bool {
+        self.is_deletion_flag() || self.field_type == FieldType::NullFlags
+    }
+}
+
+/// Returns the name padded with zeros, or truncated, to N bytes
+fn name_bytes<const N: usize>(name: &str) -> [u8; N] {
+    let mut name_bytes = [0u8; N];
+    let num_bytes = name.len().min(N);
+    name_bytes[..num_bytes].copy_from_slice(&name.as_bytes()[..num_bytes]);
+    name_bytes
+}
+
+/// Gives to each variable length and nullable field the index of its bits
+/// in the `_NullFlags` field, returns the number of bits used
+pub(crate) fn assign_null_bits(fields_info: &mut [FieldInfo]) -> usize {
+    let mut num_bits = 0;
+    for field_info in fields_info.iter_mut() {
+        field_info.varlength_bit = None;
+        field_info.null_bit = None;
+        if field_info.is_hidden() {
+            continue;
+        }
+        if matches!(
+            field_info.field_type,
+            FieldType::Varchar | FieldType::Varbinary
+        ) {
+            field_info.varlength_bit = Some(num_bits as u16);
+            num_bits += 1;
+        }
+        if field_info.flags.is_nullable() {
+            field_info.null_bit = Some(num_bits as u16);
+            num_bits += 1;
+        }
+    }
+    num_bits
+}
+
+/// Makes sure that the `_NullFlags` field exists and is the last one
+/// if at least one field is nullable or has a variable length
+pub(crate) fn update_null_flags_field(fields_info: &mut Vec<FieldInfo>) {
+    fields_info.retain(|field_info| field_info.field_type != FieldType::NullFlags);
+    let num_bits = assign_null_bits(fields_info);
+    if num_bits > 0 {
+        fields_info.push(FieldInfo::new_null_flags(num_bits));
+    }
 }
 
 impl std::fmt::Display for FieldInfo {
@@ -181,8 +374,38 @@ impl std::fmt::Display for FieldInfo {
 }
 
 /// Flags describing a field
+///
+/// Only Visual FoxPro files use them
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
-pub(crate) struct FieldFlags(u8);
+pub struct FieldFlags(pub(crate) u8);
+
+impl FieldFlags {
+    pub(crate) const SYSTEM: u8 = 0x01;
+    pub(crate) const NULLABLE: u8 = 0x02;
+    pub(crate) const BINARY: u8 = 0x04;
+    /// Visual FoxPro sets it along with the binary flag (0x0C)
+    pub(crate) const AUTOINCREMENT: u8 = 0x08;
+
+    /// The field is a system field, hidden from the user
+    pub fn is_system(&self) -> bool {
+        (self.0 & Self::SYSTEM) != 0
+    }
+
+    /// The field can store null values
+    pub fn is_nullable(&self) -> bool {
+        (self.0 & Self::NULLABLE) != 0
+    }
+
+    /// The field stores binary data (no code page translation)
+    pub fn is_binary(&self) -> bool {
+        (self.0 & Self::BINARY) != 0
+    }
+
+    /// The field values are assigned automatically
+    pub fn is_autoincrement(&self) -> bool {
+        (self.0 & Self::AUTOINCREMENT) != 0
+    }
+}
 
 /// Errors that can happen when trying to convert a FieldValue into
 /// a more concrete type
@@ -253,38 +476,142 @@ macro_rules! impl_try_from_field_value_for_ {
     };
 }
 
-impl_try_from_field_value_for_!(FieldValue::Numeric => Option<f64>);
-
 impl_try_from_field_value_for_!(FieldValue::Float => Option<f32>);
 impl_try_from_field_value_for_!(FieldValue::Float(Some(v)) => f32);
 
 impl_try_from_field_value_for_!(FieldValue::Date => Option<field::Date>);
 impl_try_from_field_value_for_!(FieldValue::Date(Some(v)) => field::Date);
 
-impl_try_from_field_value_for_!(FieldValue::Character => Option<String>);
-impl_try_from_field_value_for_!(FieldValue::Character(Some(string)) => String);
+impl TryFrom<FieldValue> for Option<String> {
+    type Error = FieldConversionError;
+
+    fn try_from(value: FieldValue) -> Result<Self, Self::Error> {
+        match value {
+            FieldValue::Character(string) | FieldValue::Varchar(string) => Ok(string),
+            _ => Err(FieldConversionError::FieldTypeNotAsExpected {
+                expected: FieldType::Character,
+                actual: value.field_type(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<FieldValue> for String {
+    type Error = FieldConversionError;
+
+    fn try_from(value: FieldValue) -> Result<Self, Self::Error> {
+        match value {
+            FieldValue::Character(Some(string)) | FieldValue::Varchar(Some(string)) => Ok(string),
+            FieldValue::Character(None) | FieldValue::Varchar(None) => {
+                Err(FieldConversionError::NoneValue)
+            }
+            FieldValue::Memo(string) => Ok(string),
+            _ => Err(FieldConversionError::FieldTypeNotAsExpected {
+                expected: FieldType::Character,
+                actual: value.field_type(),
+            }),
+        }
+    }
+}
 
 impl_try_from_field_value_for_!(FieldValue::Logical => Option<bool>);
 impl_try_from_field_value_for_!(FieldValue::Logical(Some(b)) => bool);
 
-impl_try_from_field_value_for_!(FieldValue::Integer => i32);
+impl TryFrom<FieldValue> for Option<i32> {
+    type Error = FieldConversionError;
 
-impl TryFrom<FieldValue> for f64 {
+    fn try_from(value: FieldValue) -> Result<Self, Self::Error> {
+        match value {
+            FieldValue::Integer(v) | FieldValue::Long(v) | FieldValue::Autoincrement(v) => Ok(v),
+            _ => Err(FieldConversionError::FieldTypeNotAsExpected {
+                expected: FieldType::Integer,
+                actual: value.field_type(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<FieldValue> for i32 {
+    type Error = FieldConversionError;
+
+    fn try_from(value: FieldValue) -> Result<Self, Self::Error> {
+        Option::<i32>::try_from(value)?.ok_or(FieldConversionError::NoneValue)
+    }
+}
+
+impl TryFrom<FieldValue> for Option<f64> {
     type Error = FieldConversionError;
 
     fn try_from(value: FieldValue) -> Result<Self, Self::Error> {
         match value {
-            FieldValue::Numeric(Some(v)) => Ok(v),
-            FieldValue::Numeric(None) => Err(FieldConversionError::NoneValue),
-            FieldValue::Currency(c) => Ok(c),
-            FieldValue::Double(d) => Ok(d),
+            FieldValue::Numeric(v)
+            | FieldValue::Currency(v)
+            | FieldValue::Double(v)
+            | FieldValue::DBase7Double(v) => Ok(v),
             _ => Err(FieldConversionError::IncompatibleType),
         }
     }
 }
 
+impl TryFrom<FieldValue> for f64 {
+    type Error = FieldConversionError;
+
+    fn try_from(value: FieldValue) -> Result<Self, Self::Error> {
+        Option::<f64>::try_from(value)?.ok_or(FieldConversionError::NoneValue)
+    }
+}
+
 // Fox Pro types
-impl_try_from_field_value_for_!(FieldValue::DateTime => DateTime);
+impl TryFrom<FieldValue> for Option<DateTime> {
+    type Error = FieldConversionError;
+
+    fn try_from(value: FieldValue) -> Result<Self, Self::Error> {
+        match value {
+            FieldValue::DateTime(v) | FieldValue::Timestamp(v) => Ok(v),
+            _ => Err(FieldConversionError::FieldTypeNotAsExpected {
+                expected: FieldType::DateTime,
+                actual: value.field_type(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<FieldValue> for DateTime {
+    type Error = FieldConversionError;
+
+    fn try_from(value: FieldValue) -> Result<Self, Self::Error> {
+        Option::<DateTime>::try_from(value)?.ok_or(FieldConversionError::NoneValue)
+    }
+}
+
+impl TryFrom<FieldValue> for Option<Vec<u8>> {
+    type Error = FieldConversionError;
+
+    fn try_from(value: FieldValue) -> Result<Self, Self::Error> {
+        match value {
+            FieldValue::Varbinary(bytes) => Ok(bytes),
+            FieldValue::BinaryMemo(bytes)
+            | FieldValue::General(bytes)
+            | FieldValue::Picture(bytes)
+            | FieldValue::Blob(bytes) => Ok(Some(bytes)),
+            _ => Err(FieldConversionError::FieldTypeNotAsExpected {
+                expected: FieldType::Varbinary,
+                actual: value.field_type(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<FieldValue> for Vec<u8> {
+    type Error = FieldConversionError;
+
+    fn try_from(value: FieldValue) -> Result<Self, Self::Error> {
+        match Option::<Vec<u8>>::try_from(value)? {
+            Some(bytes) => Ok(bytes),
+            None => Err(FieldConversionError::NoneValue),
+        }
+    }
+}
 
 macro_rules! impl_from_type_for_field_value (
     ($t:ty => FieldValue::$variant:ident) => {
@@ -319,7 +646,14 @@ impl_from_type_for_field_value!(Option<Date> => FieldValue::Date);
 impl_from_type_for_field_value!(Date => FieldValue::Date(Some(v)));
 
 // Fox Pro types
-impl_from_type_for_field_value!(DateTime => FieldValue::DateTime);
+impl_from_type_for_field_value!(Option<i32> => FieldValue::Integer);
+impl_from_type_for_field_value!(i32 => FieldValue::Integer(Some(v)));
+
+impl_from_type_for_field_value!(Option<DateTime> => FieldValue::DateTime);
+impl_from_type_for_field_value!(DateTime => FieldValue::DateTime(Some(v)));
+
+impl_from_type_for_field_value!(Option<Vec<u8>> => FieldValue::Varbinary);
+impl_from_type_for_field_value!(Vec<u8> => FieldValue::Varbinary(Some(v)));
 
 #[cfg(test)]
 mod test {
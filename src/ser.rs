@@ -3,12 +3,12 @@ use std::io::Write;
 
 use crate::record::field::FieldType;
 use crate::writing::FieldWriter;
-use crate::{Date, FieldIOError};
+use crate::{Date, DateTime, FieldIOError};
 use crate::{ErrorKind, WritableRecord};
 
 impl<T> WritableRecord for T
 where
-    T: Serialize,
+    T: ?Sized + Serialize,
 {
     fn write_using<'a, W: Write>(
         &self,
@@ -93,13 +93,25 @@ impl<'a, W: Write> Serializer for &mut FieldWriter<'a, W> {
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
         if let Some(field_info) = self.fields_info.peek() {
             match field_info.field_type {
-                FieldType::Character => self.write_next_field_value::<Option<String>>(&None),
+                FieldType::Character | FieldType::Varchar | FieldType::Memo => {
+                    self.write_next_field_value::<Option<String>>(&None)
+                }
                 FieldType::Numeric => self.write_next_field_value::<Option<f64>>(&None),
                 FieldType::Float => self.write_next_field_value::<Option<f32>>(&None),
                 FieldType::Date => self.write_next_field_value::<Option<Date>>(&None),
                 FieldType::Logical => self.write_next_field_value::<Option<bool>>(&None),
+                FieldType::Integer | FieldType::Long | FieldType::Autoincrement => {
+                    self.write_next_field_value::<Option<i32>>(&None)
+                }
+                FieldType::Currency | FieldType::Double | FieldType::DBase7Double => {
+                    self.write_next_field_value::<Option<f64>>(&None)
+                }
+                FieldType::DateTime | FieldType::Timestamp => {
+                    self.write_next_field_value::<Option<DateTime>>(&None)
+                }
+                FieldType::Varbinary => self.write_next_field_value::<Option<Vec<u8>>>(&None),
                 _ => Err(FieldIOError::new(
-                    ErrorKind::Message(format!("This field cannot store None values")),
+                    ErrorKind::Message("This field cannot store None values".to_string()),
                     Some((*field_info).to_owned()),
                 )),
             }
@@ -108,9 +120,9 @@ impl<'a, W: Write> Serializer for &mut FieldWriter<'a, W> {
         }
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
@@ -132,18 +144,18 @@ impl<'a, W: Write> Serializer for &mut FieldWriter<'a, W> {
         unimplemented!("dBase cannot serialize unit_variant")
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         _name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -151,7 +163,7 @@ impl<'a, W: Write> Serializer for &mut FieldWriter<'a, W> {
         _value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unimplemented!()
     }
@@ -204,9 +216,9 @@ impl<'a, W: Write> Serializer for &mut FieldWriter<'a, W> {
         unimplemented!()
     }
 
-    fn collect_str<T: ?Sized>(self, _value: &T) -> Result<Self::Ok, Self::Error>
+    fn collect_str<T>(self, _value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: std::fmt::Display,
+        T: ?Sized + std::fmt::Display,
     {
         unimplemented!()
     }
@@ -216,13 +228,9 @@ impl<'a, W: Write> serde::ser::SerializeStructVariant for &mut FieldWriter<'a, W
     type Ok = ();
     type Error = FieldIOError;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        _key: &'static str,
-        _value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _key: &'static str, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unimplemented!()
     }
@@ -236,13 +244,9 @@ impl<'a, W: Write> serde::ser::SerializeStruct for &mut FieldWriter<'a, W> {
     type Ok = ();
     type Error = FieldIOError;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        _key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(&mut **self)
     }
@@ -256,9 +260,9 @@ impl<'a, W: Write> serde::ser::SerializeSeq for &mut FieldWriter<'a, W> {
     type Ok = ();
     type Error = FieldIOError;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(&mut **self)
     }
@@ -272,16 +276,16 @@ impl<'a, W: Write> serde::ser::SerializeMap for &mut FieldWriter<'a, W> {
     type Ok = ();
     type Error = FieldIOError;
 
-    fn serialize_key<T: ?Sized>(&mut self, _key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, _key: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unimplemented!()
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unimplemented!()
     }
@@ -295,9 +299,9 @@ impl<'a, W: Write> serde::ser::SerializeTupleVariant for &mut FieldWriter<'a, W>
     type Ok = ();
     type Error = FieldIOError;
 
-    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unimplemented!()
     }
@@ -311,9 +315,9 @@ impl<'a, W: Write> serde::ser::SerializeTupleStruct for &mut FieldWriter<'a, W>
     type Ok = ();
     type Error = FieldIOError;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(&mut **self)
     }
@@ -327,9 +331,9 @@ impl<'a, W: Write> serde::ser::SerializeTuple for &mut FieldWriter<'a, W> {
     type Ok = ();
     type Error = FieldIOError;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(&mut **self)
     }
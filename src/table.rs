@@ -0,0 +1,750 @@
+//! Module with the struct that allows to modify an existing .dbf file in place
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use byteorder::WriteBytesExt;
+
+use crate::header::Header;
+use crate::index::cdx::update_structural_index;
+use crate::reading::{
+    null_flags_position, read_cpg_file, ReadableRecord, DELETED_RECORD_MARKER, VALID_RECORD_MARKER,
+};
+use crate::record::field::{
+    memo_index_from_bytes, memo_index_to_bytes, MemoDataWriter, MemoFileType, MemoReader,
+    MemoWriter,
+};
+use crate::writing::{WritableAsDbaseField, WritableRecord, FILE_TERMINATOR};
+use crate::{Error, ErrorKind, FieldInfo, FieldWriter, Reader, Record};
+
+/// Handle to an existing dBase file opened for reading and writing
+///
+/// Records are modified in place: as each record occupies a fixed-width slot
+/// in the file, updating one does not require to rewrite the whole file.
+///
+/// The date of last update stored in the header is refreshed when the table is closed.
+/// If the table has a structural compound index (.cdx), it is rebuilt then too
+/// (see [reindex](crate::index::cdx::reindex)). When it cannot be, because the table
+/// was not opened from a path or the keys of a tag cannot be computed, the `has_structural_cdx`
+/// flag is cleared so that the outdated index is no longer used.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> Result<(), dbase::Error> {
+/// # let path = std::env::temp_dir().join("table_doc_example.dbf");
+/// # std::fs::copy("tests/data/stations.dbf", &path).unwrap();
+/// let mut table = dbase::Table::open(&path)?;
+/// let station = table.get(0)?;
+/// table.update_field(0, "name", &String::from("Van Dorn"))?;
+/// table.close()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Table<T: Read + Write + Seek> {
+    reader: Reader<T>,
+    memo_writer: Option<MemoWriter<T>>,
+    /// Holds the bytes of the record (or field) being written
+    record_buffer: Cursor<Vec<u8>>,
+    /// Buffer used by the FieldWriter
+    field_buffer: Cursor<Vec<u8>>,
+    /// Path of the file, if the table was opened from one
+    path: Option<PathBuf>,
+    modified: bool,
+    closed: bool,
+}
+
+impl<T: Read + Write + Seek> Table<T> {
+    /// Creates a new table from the source, the header and fields info are read
+    /// from the source.
+    ///
+    /// Memo fields cannot be read nor written when using this function,
+    /// use [Table::open] for that.
+    pub fn new(source: T) -> Result<Self, Error> {
+        let reader = Reader::new(source)?;
+        Ok(Self::with_reader(reader, None))
+    }
+
+    fn with_reader(reader: Reader<T>, memo_writer: Option<MemoWriter<T>>) -> Self {
+        Self {
+            reader,
+            memo_writer,
+            record_buffer: Cursor::new(Vec::new()),
+            field_buffer: Cursor::new(vec![0u8; 255]),
+            path: None,
+            modified: false,
+            closed: false,
+        }
+    }
+
+    /// Returns the header of the file
+    pub fn header(&self) -> &Header {
+        self.reader.header()
+    }
+
+    /// Returns the fields contained in the file
+    pub fn fields(&self) -> &[FieldInfo] {
+        self.reader.fields()
+    }
+
+    /// Returns the number of records in the file
+    pub fn num_records(&self) -> usize {
+        self.reader.header.num_records as usize
+    }
+
+    /// Reads the record at `index`
+    pub fn get(&mut self, index: usize) -> Result<Record, Error> {
+        self.get_as::<Record>(index)
+    }
+
+    /// Reads the record at `index` as the type you want
+    pub fn get_as<R: ReadableRecord>(&mut self, index: usize) -> Result<R, Error> {
+        self.reader
+            .read_record_at::<R>(index)
+            .map(|(_meta, record)| record)
+    }
+
+    /// Returns whether the record at `index` is marked as deleted
+    pub fn is_deleted(&mut self, index: usize) -> Result<bool, Error> {
+        self.check_index(index)?;
+        let mut deletion_flag = [0u8; 1];
+        let position = self.reader.header.record_position(index);
+        self.reader
+            .source
+            .seek(SeekFrom::Start(position))
+            .and_then(|_| self.reader.source.read_exact(&mut deletion_flag))
+            .map_err(|error| Error::io_error(error, index))?;
+        Ok(deletion_flag[0] == DELETED_RECORD_MARKER)
+    }
+
+    /// Marks the record at `index` as deleted,
+    /// this is the equivalent of the dBase `DELETE` command.
+    ///
+    /// The record stays in the file until it is packed (see [Table::pack]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// # let path = std::env::temp_dir().join("table_delete_doc_example.dbf");
+    /// # std::fs::copy("tests/data/stations.dbf", &path).unwrap();
+    /// let mut table = dbase::Table::open(&path)?;
+    /// table.delete(0)?;
+    /// assert!(table.is_deleted(0)?);
+    /// table.recall(0)?;
+    /// assert!(!table.is_deleted(0)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn delete(&mut self, index: usize) -> Result<(), Error> {
+        self.write_deletion_flag(index, DELETED_RECORD_MARKER)
+    }
+
+    /// Removes the deletion mark of the record at `index`,
+    /// this is the equivalent of the dBase `RECALL` command.
+    pub fn recall(&mut self, index: usize) -> Result<(), Error> {
+        self.write_deletion_flag(index, VALID_RECORD_MARKER)
+    }
+
+    fn write_deletion_flag(&mut self, index: usize, marker: u8) -> Result<(), Error> {
+        self.check_index(index)?;
+        self.record_buffer.get_mut().clear();
+        self.record_buffer.get_mut().push(marker);
+        self.write_buffer_at(index, 0)
+    }
+
+    /// Overwrites the record at `index` with the given `record`
+    ///
+    /// The deletion flag of the record is left as is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// # let path = std::env::temp_dir().join("table_update_doc_example.dbf");
+    /// # std::fs::copy("tests/data/stations.dbf", &path).unwrap();
+    /// let mut table = dbase::Table::open(&path)?;
+    /// let mut station = table.get(1)?;
+    /// station.insert("line".to_owned(), dbase::FieldValue::Character(Some("red".to_owned())));
+    /// table.update(1, &station)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update<R: WritableRecord>(&mut self, index: usize, record: &R) -> Result<(), Error> {
+        self.check_index(index)?;
+        self.record_buffer.get_mut().clear();
+        self.record_buffer.set_position(0);
+
+        // The first field is the deletion flag, which we do not overwrite
+        let fields_info = &self.reader.fields_info[1..];
+        let mut field_writer = FieldWriter {
+            dst: &mut self.record_buffer,
+            fields_info: fields_info.iter().peekable(),
+            buffer: &mut self.field_buffer,
+            encoding: &self.reader.encoding,
+            memo_writer: self
+                .memo_writer
+                .as_mut()
+                .map(|writer| writer as &mut dyn MemoDataWriter),
+            null_flags: Vec::new(),
+            autoincrement_values: None,
+        };
+
+        record
+            .write_using(&mut field_writer)
+            .map_err(|error| Error::new(error, index))?;
+
+        if !field_writer.all_fields_were_written() {
+            return Err(Error {
+                record_num: index,
+                field: None,
+                kind: ErrorKind::NotEnoughFields,
+            });
+        }
+
+        self.write_buffer_at(index, 1)
+    }
+
+    /// Overwrites the value of the field named `field_name` of the record at `index`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// # let path = std::env::temp_dir().join("table_update_field_doc_example.dbf");
+    /// # std::fs::copy("tests/data/stations.dbf", &path).unwrap();
+    /// let mut table = dbase::Table::open(&path)?;
+    /// table.update_field(2, "line", &String::from("red"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update_field<V: WritableAsDbaseField>(
+        &mut self,
+        index: usize,
+        field_name: &str,
+        value: &V,
+    ) -> Result<(), Error> {
+        self.check_index(index)?;
+        let field_index = self
+            .reader
+            .fields_info
+            .iter()
+            .position(|info| !info.is_hidden() && info.name.eq_ignore_ascii_case(field_name))
+            .ok_or_else(|| Error {
+                record_num: index,
+                field: None,
+                kind: ErrorKind::FieldNotFound(field_name.to_owned()),
+            })?;
+        let offset_in_record: usize = self.reader.fields_info[..field_index]
+            .iter()
+            .map(|info| info.field_length as usize)
+            .sum();
+
+        self.record_buffer.get_mut().clear();
+        self.record_buffer.set_position(0);
+
+        let fields_info = &self.reader.fields_info[field_index..=field_index];
+        let mut field_writer = FieldWriter {
+            dst: &mut self.record_buffer,
+            fields_info: fields_info.iter().peekable(),
+            buffer: &mut self.field_buffer,
+            encoding: &self.reader.encoding,
+            memo_writer: self
+                .memo_writer
+                .as_mut()
+                .map(|writer| writer as &mut dyn MemoDataWriter),
+            null_flags: Vec::new(),
+            autoincrement_values: None,
+        };
+        field_writer
+            .write_next_field_value(value)
+            .map_err(|error| Error::new(error, index))?;
+        let null_flags = std::mem::take(&mut field_writer.null_flags);
+
+        self.write_buffer_at(index, offset_in_record)?;
+        let field_info = &self.reader.fields_info[field_index];
+        for bit in [field_info.null_bit, field_info.varlength_bit]
+            .into_iter()
+            .flatten()
+        {
+            let is_set = null_flags
+                .get(usize::from(bit / 8))
+                .is_some_and(|byte| byte & (1 << (bit % 8)) != 0);
+            self.write_null_flag(index, bit, is_set)?;
+        }
+        Ok(())
+    }
+
+    /// Close the table
+    ///
+    /// If any record was modified, the date of last update is refreshed
+    /// and the structural index of the table is rebuilt.
+    ///
+    /// Automatically closed when the table is dropped,
+    /// use it if you want to handle error that can happen when the table is closing
+    ///
+    /// Calling close on an already closed table is a no-op
+    pub fn close(&mut self) -> Result<(), Error> {
+        if !self.closed {
+            if self.modified {
+                let num_records = self.num_records();
+                self.reader.header.update_date();
+                self.write_header()?;
+                if let Some(memo_writer) = &mut self.memo_writer {
+                    memo_writer
+                        .close()
+                        .map_err(|error| Error::io_error(error, num_records))?;
+                }
+                if self.reader.header.table_flags.has_structural_cdx() {
+                    self.update_structural_index()?;
+                }
+            }
+            self.closed = true;
+        }
+        Ok(())
+    }
+
+    /// Writes the records that are not marked as deleted to `dst`
+    ///
+    /// The header and fields definitions are copied as they are, except for
+    /// the number of records, the date of last update and the `has_structural_cdx` flag,
+    /// as the index of the table is not copied.
+    /// Records are copied byte for byte, so the memo indices still point to
+    /// the current memo file, use [Table::pack_into_with_memo] to also pack the memo file.
+    ///
+    /// This table is not modified.
+    pub fn pack_into<W: Write + Seek>(&mut self, dst: W) -> Result<(), Error> {
+        self.pack_into_impl(dst, None)
+    }
+
+    /// Writes the records that are not marked as deleted to `dst`,
+    /// and the memo data they reference to `memo_dst`
+    ///
+    /// Memo blocks that were only referenced by deleted records (or not at all)
+    /// are not copied.
+    ///
+    /// This table is not modified.
+    pub fn pack_into_with_memo<W: Write + Seek>(
+        &mut self,
+        dst: W,
+        memo_dst: W,
+    ) -> Result<(), Error> {
+        let mut memo_writer = self.new_memo_writer(memo_dst)?;
+        self.pack_into_impl(dst, Some(&mut memo_writer))?;
+        memo_writer
+            .close()
+            .map_err(|error| Error::io_error(error, self.num_records()))
+    }
+
+    fn pack_into_impl<W: Write + Seek>(
+        &mut self,
+        mut dst: W,
+        memo_writer: Option<&mut dyn MemoDataWriter>,
+    ) -> Result<(), Error> {
+        // The header and fields definitions are copied as is
+        let mut header_and_fields = vec![0u8; self.reader.header.offset_to_first_record as usize];
+        self.reader
+            .source
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| self.reader.source.read_exact(&mut header_and_fields))
+            .and_then(|_| dst.write_all(&header_and_fields))
+            .map_err(|error| Error::io_error(error, 0))?;
+
+        let num_kept = self.pack_records(memo_writer, |_, _, record_bytes| {
+            dst.write_all(record_bytes)
+        })?;
+
+        let mut header = self.reader.header;
+        header.num_records = num_kept;
+        header.update_date();
+        // The index of the table is not copied
+        header.table_flags.set_has_structural_cdx(false);
+        dst.write_u8(FILE_TERMINATOR)
+            .and_then(|_| dst.seek(SeekFrom::Start(0)))
+            .and_then(|_| header.write_to(&mut dst))
+            .and_then(|_| dst.seek(SeekFrom::End(0)))
+            .and_then(|_| dst.flush())
+            .map(|_| ())
+            .map_err(|error| Error::io_error(error, num_kept as usize))
+    }
+
+    /// Calls `write_record` for each record that is not marked as deleted,
+    /// with the position of the record in the packed file and its bytes.
+    ///
+    /// When a `memo_writer` is given, the memo entries of these records are copied to it,
+    /// and their memo indices are updated.
+    ///
+    /// Returns the number of records kept
+    fn pack_records<F>(
+        &mut self,
+        mut memo_writer: Option<&mut dyn MemoDataWriter>,
+        mut write_record: F,
+    ) -> Result<u32, Error>
+    where
+        F: FnMut(&mut T, u64, &[u8]) -> std::io::Result<()>,
+    {
+        let memo_fields = self.memo_fields_location();
+        let mut record_bytes = vec![0u8; self.reader.header.size_of_record as usize];
+        let mut num_kept = 0u32;
+        for index in 0..self.num_records() {
+            let position = self.reader.header.record_position(index);
+            self.reader
+                .source
+                .seek(SeekFrom::Start(position))
+                .and_then(|_| self.reader.source.read_exact(&mut record_bytes))
+                .map_err(|error| Error::io_error(error, index))?;
+
+            if record_bytes[0] == DELETED_RECORD_MARKER {
+                continue;
+            }
+
+            if let Some(memo_writer) = memo_writer.as_mut() {
+                for (field_info, offset) in &memo_fields {
+                    let field_bytes =
+                        &mut record_bytes[*offset..*offset + field_info.field_length as usize];
+                    copy_memo_entry(
+                        &mut self.reader.memo_reader,
+                        &mut **memo_writer,
+                        field_bytes,
+                    )
+                    .map_err(|kind| Error {
+                        record_num: index,
+                        field: Some(field_info.clone()),
+                        kind,
+                    })?;
+                }
+            }
+
+            let new_position = self.reader.header.record_position(num_kept as usize);
+            write_record(&mut self.reader.source, new_position, &record_bytes)
+                .map_err(|error| Error::io_error(error, index))?;
+            num_kept += 1;
+        }
+        Ok(num_kept)
+    }
+
+    /// Returns the memo fields and their offset in the record
+    fn memo_fields_location(&self) -> Vec<(FieldInfo, usize)> {
+        let mut offset = 0;
+        let mut locations = vec![];
+        for field_info in &self.reader.fields_info {
+            if field_info.field_type.is_memo() {
+                locations.push((field_info.clone(), offset));
+            }
+            offset += field_info.field_length as usize;
+        }
+        locations
+    }
+
+    /// Creates a memo writer with the same format as the memo file of the table
+    fn new_memo_writer<W: Write + Seek>(&self, memo_dst: W) -> Result<MemoWriter<W>, Error> {
+        let memo_type = self
+            .reader
+            .header
+            .file_type
+            .supported_memo_type()
+            .unwrap_or(MemoFileType::DbaseMemo);
+        let result = match &self.reader.memo_reader {
+            Some(memo_reader) => {
+                MemoWriter::with_block_size(memo_type, memo_reader.block_size(), memo_dst)
+            }
+            None => MemoWriter::new(memo_type, memo_dst),
+        };
+        result.map_err(|error| Error::io_error(error, 0))
+    }
+
+    /// Rebuilds the structural index of the table, or clears its flag when it cannot be rebuilt
+    fn update_structural_index(&mut self) -> Result<(), Error> {
+        let rebuilt = match &self.path {
+            Some(path) => update_structural_index(path)?,
+            None => false,
+        };
+        if !rebuilt {
+            self.reader.header.table_flags.set_has_structural_cdx(false);
+            self.write_header()?;
+        }
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> Result<(), Error> {
+        let num_records = self.num_records();
+        let source = &mut self.reader.source;
+        source
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| self.reader.header.write_to(source))
+            .and_then(|_| source.flush())
+            .map_err(|error| Error::io_error(error, num_records))
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), Error> {
+        if index >= self.num_records() {
+            Err(Error {
+                record_num: index,
+                field: None,
+                kind: ErrorKind::RecordIndexOutOfRange(index),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sets or clears the `bit` of the `_NullFlags` field of the record at `index`
+    fn write_null_flag(&mut self, index: usize, bit: u16, is_set: bool) -> Result<(), Error> {
+        let null_flags_position = null_flags_position(&self.reader.fields_info)
+            .expect("a field has a null bit, so there is a _NullFlags field");
+        let position = self.reader.header.record_position(index)
+            + (null_flags_position + usize::from(bit / 8)) as u64;
+        let source = &mut self.reader.source;
+        let mut byte = 0u8;
+        source
+            .seek(SeekFrom::Start(position))
+            .and_then(|_| source.read_exact(std::slice::from_mut(&mut byte)))
+            .map_err(|error| Error::io_error(error, index))?;
+        if is_set {
+            byte |= 1 << (bit % 8);
+        } else {
+            byte &= !(1 << (bit % 8));
+        }
+        source
+            .seek(SeekFrom::Start(position))
+            .and_then(|_| source.write_u8(byte))
+            .map_err(|error| Error::io_error(error, index))
+    }
+
+    /// Writes the content of the record buffer in the slot of the record at `index`,
+    /// starting `offset_in_record` bytes after the start of the slot
+    fn write_buffer_at(&mut self, index: usize, offset_in_record: usize) -> Result<(), Error> {
+        let position = self.reader.header.record_position(index) + offset_in_record as u64;
+        self.modified = true;
+        self.reader
+            .source
+            .seek(SeekFrom::Start(position))
+            .and_then(|_| self.reader.source.write_all(self.record_buffer.get_ref()))
+            .map_err(|error| Error::io_error(error, index))
+    }
+}
+
+impl Table<File> {
+    /// Opens an existing dBase file for reading and writing
+    ///
+    /// If the file has memo fields, the associated memo file is opened too.
+    ///
+    /// Like [Reader::from_path], when a `.cpg` file is next to the table, the encoding it names
+    /// is used instead of the one of the code page mark of the file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = open_read_write(path).map_err(|error| Error::io_error(error, 0))?;
+        let encoding = read_cpg_file(path)?;
+        let mut reader = Reader::new_impl(file, encoding)?;
+        let memo_writer = Self::open_memo_file(&mut reader, path)?;
+        let mut table = Self::with_reader(reader, memo_writer);
+        table.path = Some(path.to_path_buf());
+        Ok(table)
+    }
+
+    /// Removes the records that are marked as deleted from the file,
+    /// this is the equivalent of the dBase `PACK` command.
+    ///
+    /// The records that are kept are moved in place, byte for byte, and the file is truncated.
+    /// If the table has memo fields, the memo file is rewritten
+    /// so that it only contains the data of the remaining records.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// # let path = std::env::temp_dir().join("table_pack_doc_example.dbf");
+    /// # std::fs::copy("tests/data/stations.dbf", &path).unwrap();
+    /// let mut table = dbase::Table::open(&path)?;
+    /// table.pack()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pack(&mut self) -> Result<(), Error> {
+        let num_kept = match &self.memo_path() {
+            Some(memo_path) => {
+                let mut packed_memo_path = memo_path.clone().into_os_string();
+                packed_memo_path.push(".pack");
+                let packed_memo_path = PathBuf::from(packed_memo_path);
+
+                let packed_memo_file = File::create(&packed_memo_path).map_err(|error| Error {
+                    record_num: 0,
+                    field: None,
+                    kind: ErrorKind::ErrorOpeningMemoFile(error),
+                })?;
+                let mut memo_writer = self.new_memo_writer(BufWriter::new(packed_memo_file))?;
+                let num_kept = self.pack_records_in_place(Some(&mut memo_writer))?;
+                memo_writer
+                    .close()
+                    .map_err(|error| Error::io_error(error, num_kept as usize))?;
+                drop(memo_writer);
+
+                // Release the handles to the old memo file before replacing it
+                self.reader.memo_reader = None;
+                self.memo_writer = None;
+                std::fs::rename(&packed_memo_path, memo_path)
+                    .map_err(|error| Error::io_error(error, num_kept as usize))?;
+                self.reopen_memo_file()?;
+                num_kept
+            }
+            None => self.pack_records_in_place(None)?,
+        };
+
+        self.truncate(num_kept)
+    }
+
+    /// Removes all the records from the file,
+    /// this is the equivalent of the dBase `ZAP` command.
+    ///
+    /// If the table has memo fields, the memo file is emptied too.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// # let path = std::env::temp_dir().join("table_zap_doc_example.dbf");
+    /// # std::fs::copy("tests/data/stations.dbf", &path).unwrap();
+    /// let mut table = dbase::Table::open(&path)?;
+    /// table.zap()?;
+    /// assert_eq!(table.num_records(), 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn zap(&mut self) -> Result<(), Error> {
+        if let Some(memo_path) = self.memo_path() {
+            let memo_type = self
+                .reader
+                .header
+                .file_type
+                .supported_memo_type()
+                .expect("memo path is only known when memo are supported");
+            let block_size = self
+                .reader
+                .memo_reader
+                .as_ref()
+                .map(|memo_reader| memo_reader.block_size())
+                .expect("memo path is only known when the memo file is opened");
+
+            // Release the handles to the old memo file before replacing it
+            self.reader.memo_reader = None;
+            self.memo_writer = None;
+            let memo_file = File::create(&memo_path).map_err(|error| Error {
+                record_num: 0,
+                field: None,
+                kind: ErrorKind::ErrorOpeningMemoFile(error),
+            })?;
+            MemoWriter::with_block_size(memo_type, block_size, memo_file)
+                .and_then(|mut memo_writer| memo_writer.close())
+                .map_err(|error| Error::io_error(error, 0))?;
+            self.reopen_memo_file()?;
+        }
+        self.truncate(0)
+    }
+
+    /// Sets the number of records to `num_records` and
+    /// removes the data after the last record from the file
+    fn truncate(&mut self, num_records: u32) -> Result<(), Error> {
+        self.reader.header.num_records = num_records;
+        self.modified = true;
+        let end_of_records = self.reader.header.record_position(num_records as usize);
+        let source = &mut self.reader.source;
+        source
+            .seek(SeekFrom::Start(end_of_records))
+            .and_then(|_| source.write_u8(FILE_TERMINATOR))
+            .and_then(|_| source.set_len(end_of_records + 1))
+            .map_err(|error| Error::io_error(error, num_records as usize))?;
+        self.write_header()
+    }
+
+    /// Returns the path of the memo file, if the table has one opened
+    fn memo_path(&self) -> Option<PathBuf> {
+        match (&self.path, &self.reader.memo_reader) {
+            (Some(path), Some(_)) => self
+                .reader
+                .header
+                .file_type
+                .supported_memo_type()
+                .map(|memo_type| memo_type.file_path_for(path)),
+            _ => None,
+        }
+    }
+
+    fn reopen_memo_file(&mut self) -> Result<(), Error> {
+        let path = self
+            .path
+            .clone()
+            .expect("memo file can only be reopened from the path");
+        self.memo_writer = Self::open_memo_file(&mut self.reader, &path)?;
+        Ok(())
+    }
+
+    /// Moves the records that are not marked as deleted
+    /// at the beginning of the file
+    fn pack_records_in_place(
+        &mut self,
+        memo_writer: Option<&mut dyn MemoDataWriter>,
+    ) -> Result<u32, Error> {
+        self.pack_records(memo_writer, |source, position, record_bytes| {
+            source.seek(SeekFrom::Start(position))?;
+            source.write_all(record_bytes)
+        })
+    }
+
+    /// Opens the memo file of the table for reading and appending
+    fn open_memo_file(
+        reader: &mut Reader<File>,
+        path: &Path,
+    ) -> Result<Option<MemoWriter<File>>, Error> {
+        reader.open_memo_file_with(path, open_read_write)?;
+
+        let memo_writer = match reader.memo_reader {
+            Some(_) => {
+                // The memo reader has its own handle to the memo file
+                let memo_type = reader
+                    .header
+                    .file_type
+                    .supported_memo_type()
+                    .expect("memo reader exists only when memo are supported");
+                let memo_file =
+                    open_read_write(&memo_type.file_path_for(path)).map_err(|error| Error {
+                        record_num: 0,
+                        field: None,
+                        kind: ErrorKind::ErrorOpeningMemoFile(error),
+                    })?;
+                let memo_writer = MemoWriter::append_to(memo_type, memo_file)
+                    .map_err(|error| Error::io_error(error, 0))?;
+                Some(memo_writer)
+            }
+            None => None,
+        };
+        Ok(memo_writer)
+    }
+}
+
+fn open_read_write(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().read(true).write(true).open(path)
+}
+
+/// Copies the memo entry referenced by the Memo field bytes
+/// and updates the bytes with the new memo index
+fn copy_memo_entry<T: Read + Seek>(
+    memo_reader: &mut Option<MemoReader<T>>,
+    memo_writer: &mut dyn MemoDataWriter,
+    field_bytes: &mut [u8],
+) -> Result<(), ErrorKind> {
+    let index = memo_index_from_bytes(field_bytes)?;
+    if index == 0 {
+        return Ok(());
+    }
+    let memo_reader = memo_reader.as_mut().ok_or(ErrorKind::MissingMemoFile)?;
+    let entry = memo_reader.read_raw_entry_at(index)?;
+    let new_index = memo_writer.write_raw_entry(entry)?;
+    memo_index_to_bytes(new_index, field_bytes);
+    Ok(())
+}
+
+impl<T: Read + Write + Seek> Drop for Table<T> {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
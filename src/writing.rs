@@ -1,19 +1,24 @@
 //! Module with all structs & functions charged of writing .dbf file content
-use std::fs::File;
-use std::io::{BufWriter, Cursor, Seek, SeekFrom, Write};
-use std::path::Path;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 
-use byteorder::WriteBytesExt;
+use byteorder::{LittleEndian, WriteBytesExt};
 
 use crate::encoding::{AsCodePageMark, DynEncoding};
 use crate::header::Header;
-use crate::reading::TERMINATOR_VALUE;
-use crate::reading::{TableInfo, BACKLINK_SIZE};
-use crate::record::{field::FieldType, FieldInfo, FieldName};
-use crate::{Encoding, Error, ErrorKind, FieldIOError, Record, UnicodeLossy};
+use crate::index::cdx::{update_structural_index, CdxWriter, TagDefinition, MAX_KEY_LENGTH};
+use crate::index::key::{pad_character_values, KeyExpression};
+use crate::reading::{read_cpg_file, TableInfo, BACKLINK_SIZE};
+use crate::reading::{DELETED_RECORD_MARKER, TERMINATOR_VALUE, VALID_RECORD_MARKER};
+use crate::record::field::{FieldType, MemoDataWriter, MemoFileType, MemoHeader, MemoWriter};
+use crate::record::{assign_null_bits, update_null_flags_field, FieldFlags, FieldInfo, FieldName};
+use crate::{
+    Encoding, Error, ErrorKind, FieldIOError, FieldValue, Reader, Record, RecordMeta, UnicodeLossy,
+};
 
 /// A dbase file ends with this byte
-const FILE_TERMINATOR: u8 = 0x1A;
+pub(crate) const FILE_TERMINATOR: u8 = 0x1A;
 
 /// Builder to be used to create a [TableWriter](struct.TableWriter.html).
 ///
@@ -40,6 +45,17 @@ pub struct TableWriterBuilder {
     v: Vec<FieldInfo>,
     hdr: Header,
     encoding: DynEncoding,
+    field_properties: Vec<u8>,
+    index_tags: Vec<TagDefinition>,
+    /// Why the first invalid index tag could not be added, returned when building the writer
+    index_tag_error: Option<ErrorKind>,
+    write_cpg_file: bool,
+}
+
+impl Default for TableWriterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TableWriterBuilder {
@@ -50,7 +66,11 @@ impl TableWriterBuilder {
         Self {
             v: vec![],
             hdr: Header::new(0, 0, 0),
-            encoding: DynEncoding::new(UnicodeLossy),
+            encoding: DynEncoding::new_send(UnicodeLossy),
+            field_properties: vec![],
+            index_tags: vec![],
+            index_tag_error: None,
+            write_cpg_file: false,
         }
     }
 
@@ -60,6 +80,10 @@ impl TableWriterBuilder {
             v: vec![],
             hdr: Header::new(0, 0, 0),
             encoding: DynEncoding::new(encoding),
+            field_properties: vec![],
+            index_tags: vec![],
+            index_tag_error: None,
+            write_cpg_file: false,
         }
     }
 
@@ -96,13 +120,26 @@ impl TableWriterBuilder {
         let mut hdr = table_info.header;
         hdr.update_date();
         hdr.num_records = 0;
+        // The index of the table is not copied
+        hdr.table_flags.set_has_structural_cdx(false);
         Self {
             v: fields_info,
             hdr,
             encoding: table_info.encoding,
+            field_properties: table_info.field_properties,
+            index_tags: vec![],
+            index_tag_error: None,
+            write_cpg_file: false,
         }
     }
 
+    /// Makes [build_with_file_dest](Self::build_with_file_dest) write a `.cpg` file
+    /// naming the encoding next to the table, as GIS software expects for shapefiles
+    pub fn write_cpg_file(mut self) -> Self {
+        self.write_cpg_file = true;
+        self
+    }
+
     /// Changes the encoding of the writer.
     pub fn set_encoding<E: Encoding + 'static>(mut self, encoding: E) -> Self {
         self.encoding = DynEncoding::new(encoding);
@@ -164,9 +201,7 @@ impl TableWriterBuilder {
                 .size()
                 .expect("Internal error Integer field date should be known"),
         ));
-        self.hdr.file_type = crate::header::Version::FoxPro2 {
-            supports_memo: false,
-        };
+        self.set_fox_pro_file_type();
         self
     }
 
@@ -179,9 +214,7 @@ impl TableWriterBuilder {
                 .size()
                 .expect("Internal error datetime field date should be known"),
         ));
-        self.hdr.file_type = crate::header::Version::FoxPro2 {
-            supports_memo: false,
-        };
+        self.set_fox_pro_file_type();
         self
     }
 
@@ -194,9 +227,7 @@ impl TableWriterBuilder {
                 .size()
                 .expect("Internal error Double field date should be known"),
         ));
-        self.hdr.file_type = crate::header::Version::FoxPro2 {
-            supports_memo: false,
-        };
+        self.set_fox_pro_file_type();
         self
     }
 
@@ -209,34 +240,415 @@ impl TableWriterBuilder {
                 .size()
                 .expect("Internal error Currency field date should be known"),
         ));
-        self.hdr.file_type = crate::header::Version::FoxPro2 {
-            supports_memo: false,
+        self.set_fox_pro_file_type();
+        self
+    }
+
+    /// Adds a [Long](enum.FieldValue.html#variant.Long)
+    ///
+    /// Only dBase 7 files support this field type, the file type is changed accordingly.
+    pub fn add_long_field(self, name: FieldName) -> Self {
+        self.add_dbase7_field(name, FieldType::Long)
+    }
+
+    /// Adds an [Autoincrement](enum.FieldValue.html#variant.Autoincrement)
+    ///
+    /// Only dBase 7 files support this field type, the file type is changed accordingly.
+    pub fn add_autoincrement_field(self, name: FieldName) -> Self {
+        self.add_dbase7_field(name, FieldType::Autoincrement)
+    }
+
+    /// Adds a [Timestamp](enum.FieldValue.html#variant.Timestamp)
+    ///
+    /// Only dBase 7 files support this field type, the file type is changed accordingly.
+    pub fn add_timestamp_field(self, name: FieldName) -> Self {
+        self.add_dbase7_field(name, FieldType::Timestamp)
+    }
+
+    /// Adds a [DBase7Double](enum.FieldValue.html#variant.DBase7Double)
+    ///
+    /// Only dBase 7 files support this field type, the file type is changed accordingly.
+    pub fn add_dbase7_double_field(self, name: FieldName) -> Self {
+        self.add_dbase7_field(name, FieldType::DBase7Double)
+    }
+
+    fn add_dbase7_field(mut self, name: FieldName, field_type: FieldType) -> Self {
+        let length = field_type
+            .size()
+            .expect("Internal error dBase 7 field size should be known");
+        let mut info = FieldInfo::new(name, field_type, length);
+        if field_type == FieldType::Autoincrement {
+            info.autoincrement_next_val = 1;
+            info.autoincrement_step = 1;
+        }
+        self.v.push(info);
+        self.hdr.file_type = crate::header::Version::DBase7 {
+            supports_memo: self.hdr.file_type.supported_memo_type().is_some(),
+        };
+        self
+    }
+
+    /// Adds a [Varchar](enum.FieldValue.html#variant.Varchar),
+    /// the length is the maximum number of bytes (not chars) that fields can hold
+    ///
+    /// Only Visual FoxPro files support variable length fields, the file type is changed accordingly.
+    pub fn add_varchar_field(mut self, name: FieldName, length: u8) -> Self {
+        self.v
+            .push(FieldInfo::new(name, FieldType::Varchar, length));
+        self.hdr.file_type = crate::header::Version::VisualFoxPro;
+        self
+    }
+
+    /// Adds a [Varbinary](enum.FieldValue.html#variant.Varbinary),
+    /// the length is the maximum number of bytes that fields can hold
+    ///
+    /// Only Visual FoxPro files support variable length fields, the file type is changed accordingly.
+    pub fn add_varbinary_field(mut self, name: FieldName, length: u8) -> Self {
+        let mut info = FieldInfo::new(name, FieldType::Varbinary, length);
+        info.flags.0 |= FieldFlags::BINARY;
+        self.v.push(info);
+        self.hdr.file_type = crate::header::Version::VisualFoxPro;
+        self
+    }
+
+    /// Marks the last added field as nullable
+    ///
+    /// Only Visual FoxPro files support null values, the file type is changed accordingly.
+    /// The null flags are stored in the hidden `_NullFlags` field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no field was added yet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dbase::{FieldName, TableWriterBuilder};
+    /// use std::convert::TryFrom;
+    /// use std::io::Cursor;
+    ///
+    /// let writer = TableWriterBuilder::new()
+    ///     .add_integer_field(FieldName::try_from("Age").unwrap())
+    ///     .nullable()
+    ///     .build_with_dest(Cursor::new(Vec::<u8>::new()));
+    /// ```
+    pub fn nullable(mut self) -> Self {
+        let field_info = self
+            .v
+            .last_mut()
+            .expect("nullable must be called after adding a field");
+        field_info.flags.0 |= FieldFlags::NULLABLE;
+        self.hdr.file_type = crate::header::Version::VisualFoxPro;
+        self
+    }
+
+    /// Makes the last added field, which must be an [Integer](FieldType::Integer) one,
+    /// an autoincrement field
+    ///
+    /// The writer gives the `next_value` to the first record for which a `None`
+    /// value is written in this field, and increases it by `step` for each following one.
+    ///
+    /// Only Visual FoxPro files support autoincrement Integer fields,
+    /// the file type is changed accordingly.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the last added field is not an Integer one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dbase::{FieldName, TableWriterBuilder};
+    /// use std::convert::TryFrom;
+    /// use std::io::Cursor;
+    ///
+    /// let writer = TableWriterBuilder::new()
+    ///     .add_integer_field(FieldName::try_from("Id").unwrap())
+    ///     .autoincrement(1, 1)
+    ///     .build_with_dest(Cursor::new(Vec::<u8>::new()));
+    /// ```
+    pub fn autoincrement(mut self, next_value: u32, step: u8) -> Self {
+        let field_info = self
+            .v
+            .last_mut()
+            .filter(|info| info.field_type == FieldType::Integer)
+            .expect("autoincrement must be called after adding an Integer field");
+        field_info.flags.0 |= FieldFlags::BINARY | FieldFlags::AUTOINCREMENT;
+        field_info.autoincrement_next_val = next_value;
+        field_info.autoincrement_step = step;
+        self.hdr.file_type = crate::header::Version::VisualFoxPro;
+        self
+    }
+
+    /// Adds a tag to the structural compound index (.cdx) of the table,
+    /// whose keys are made of the given fields, which must already be added
+    ///
+    /// The key of a single Numeric, Float, Integer, Double, Currency, Date or DateTime
+    /// field is stored in binary form. Otherwise the key is the concatenation of the
+    /// Character and Logical fields, of the Numeric, Float and Integer fields
+    /// converted with `STR`, and of the Date fields converted with `DTOS`.
+    /// As the keys are computed by evaluating this expression, the names of the fields
+    /// must only have letters, digits and underscores.
+    ///
+    /// The index is written when the writer is closed, next to the table by
+    /// [build_with_file_dest](Self::build_with_file_dest), or to the destination given to
+    /// [build_with_index_dest](Self::build_with_index_dest).
+    /// It can be read with [CdxIndex](crate::index::cdx::CdxIndex).
+    ///
+    /// # Errors
+    ///
+    /// The writer is not built, and an error is returned when building it, if the name of the tag
+    /// does not have between 1 and 10 ASCII characters, if one of the fields was not added
+    /// ([FieldNotFound](ErrorKind::FieldNotFound)), or if it cannot be part of a key
+    /// or the keys would be longer than 240 bytes
+    /// ([UnsupportedKeyExpression](ErrorKind::UnsupportedKeyExpression)).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dbase::{FieldName, TableWriterBuilder};
+    /// use std::convert::TryFrom;
+    /// use std::io::Cursor;
+    ///
+    /// let writer = TableWriterBuilder::new()
+    ///     .add_character_field(FieldName::try_from("LastName").unwrap(), 20)
+    ///     .add_character_field(FieldName::try_from("FirstName").unwrap(), 20)
+    ///     .add_index_tag("NAME", &["LastName", "FirstName"])
+    ///     .build_with_index_dest(Cursor::new(Vec::<u8>::new()), None, Cursor::new(Vec::<u8>::new()))
+    ///     .unwrap();
+    /// ```
+    pub fn add_index_tag(mut self, name: &str, fields: &[&str]) -> Self {
+        if self.index_tag_error.is_none() {
+            match self.index_tag(name, fields) {
+                Ok(tag) => self.index_tags.push(tag),
+                Err(kind) => self.index_tag_error = Some(kind),
+            }
+        }
+        self.set_fox_pro_file_type();
+        self
+    }
+
+    fn index_tag(&self, name: &str, fields: &[&str]) -> Result<TagDefinition, ErrorKind> {
+        if name.is_empty() || name.len() > 10 || !name.is_ascii() {
+            return Err(ErrorKind::Message(format!(
+                "The index tag name '{}' does not have between 1 and 10 ASCII characters",
+                name
+            )));
+        }
+        let expression = KeyExpression::from_fields(fields, &self.v)?;
+        if expression.key_length() > MAX_KEY_LENGTH {
+            return Err(ErrorKind::UnsupportedKeyExpression(expression.to_string()));
+        }
+        Ok(TagDefinition {
+            name: name.to_ascii_uppercase(),
+            expression,
+            filter: None,
+            unique: false,
+            descending: false,
+        })
+    }
+
+    /// Returns the error of the first index tag that could not be added
+    fn check_index_tags(&mut self) -> Result<(), Error> {
+        match self.index_tag_error.take() {
+            Some(kind) => Err(Error {
+                record_num: 0,
+                field: None,
+                kind,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Makes the file a FoxPro one, unless it already is a Visual FoxPro file
+    fn set_fox_pro_file_type(&mut self) {
+        if !self.hdr.file_type.is_visual_fox_pro() {
+            self.hdr.file_type = crate::header::Version::FoxPro2 {
+                supports_memo: self.hdr.file_type.supported_memo_type().is_some(),
+            };
+        }
+    }
+
+    /// Adds a [Memo](enum.FieldValue.html#variant.Memo)
+    ///
+    /// The content of memo fields is stored in a separate memo file
+    /// (.dbt for dBase, .fpt for FoxPro), the writer has to be built with
+    /// [build_with_file_dest](Self::build_with_file_dest) or
+    /// [build_with_memo_dest](Self::build_with_memo_dest).
+    pub fn add_memo_field(self, name: FieldName) -> Self {
+        self.add_field_stored_in_memo(name, FieldType::Memo)
+    }
+
+    /// Adds a [BinaryMemo](enum.FieldValue.html#variant.BinaryMemo),
+    /// a memo whose content is not decoded.
+    ///
+    /// Only Visual FoxPro files support binary memos, the file type is changed accordingly.
+    pub fn add_binary_memo_field(mut self, name: FieldName) -> Self {
+        self.hdr.file_type = crate::header::Version::VisualFoxPro;
+        self = self.add_field_stored_in_memo(name, FieldType::BinaryMemo);
+        if let Some(info) = self.v.last_mut() {
+            info.flags.0 |= FieldFlags::BINARY;
+        }
+        self
+    }
+
+    /// Adds a [General](enum.FieldValue.html#variant.General)
+    pub fn add_general_field(self, name: FieldName) -> Self {
+        self.add_field_stored_in_memo(name, FieldType::General)
+    }
+
+    /// Adds a [Picture](enum.FieldValue.html#variant.Picture)
+    pub fn add_picture_field(mut self, name: FieldName) -> Self {
+        self.set_fox_pro_file_type();
+        self.add_field_stored_in_memo(name, FieldType::Picture)
+    }
+
+    /// Adds a [Blob](enum.FieldValue.html#variant.Blob)
+    ///
+    /// Only Visual FoxPro files support blobs, the file type is changed accordingly.
+    pub fn add_blob_field(mut self, name: FieldName) -> Self {
+        self.hdr.file_type = crate::header::Version::VisualFoxPro;
+        self.add_field_stored_in_memo(name, FieldType::Blob)
+    }
+
+    fn add_field_stored_in_memo(mut self, name: FieldName, field_type: FieldType) -> Self {
+        // Visual FoxPro stores the block index as a binary u32
+        // other versions store it as a string
+        let length = if self.hdr.file_type.is_visual_fox_pro() {
+            4
+        } else {
+            10
         };
+        self.v.push(FieldInfo::new(name, field_type, length));
+        self.hdr.file_type = self.hdr.file_type.with_memo_support();
         self
     }
+
     /// Builds the writer and set the dst as where the file data will be written
     pub fn build_with_dest<W: Write + Seek>(self, dst: W) -> TableWriter<W> {
-        TableWriter::new(dst, self.v, self.hdr, self.encoding)
+        TableWriter::new(dst, None, self.build_table_info())
+    }
+
+    /// Builds the writer and set the dst as where the file data will be written,
+    /// and memo_dst as where the memo file data will be written
+    ///
+    /// The memo destination does not have to be of the same type as the table one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dbase::{FieldName, TableWriterBuilder};
+    /// use std::convert::TryFrom;
+    /// use std::io::Cursor;
+    ///
+    /// let mut memo_dst = Cursor::new(Vec::<u8>::new());
+    /// let writer = TableWriterBuilder::new()
+    ///     .add_memo_field(FieldName::try_from("Comment").unwrap())
+    ///     .build_with_memo_dest(Cursor::new(Vec::<u8>::new()), &mut memo_dst)
+    ///     .unwrap();
+    /// ```
+    pub fn build_with_memo_dest<W: Write + Seek, M: Write + Seek>(
+        self,
+        dst: W,
+        memo_dst: M,
+    ) -> Result<TableWriter<W, M>, Error> {
+        let memo_type = self.memo_type();
+        let memo_writer =
+            MemoWriter::new(memo_type, memo_dst).map_err(|error| Error::io_error(error, 0))?;
+        Ok(TableWriter::new(
+            dst,
+            Some(memo_writer),
+            self.build_table_info(),
+        ))
+    }
+
+    /// Builds the writer and set the dst as where the file data will be written,
+    /// memo_dst as where the memo file data will be written, if the table has memo fields,
+    /// and index_dst as where the compound index of the tags added with
+    /// [add_index_tag](Self::add_index_tag) will be written
+    pub fn build_with_index_dest<W: Write + Seek>(
+        mut self,
+        dst: W,
+        memo_dst: Option<W>,
+        index_dst: W,
+    ) -> Result<TableWriter<W>, Error> {
+        self.check_index_tags()?;
+        let index_tags = self.index_tags.clone();
+        let mut writer = match memo_dst {
+            Some(memo_dst) => self.build_with_memo_dest(dst, memo_dst)?,
+            None => self.build_with_dest(dst),
+        };
+        writer.header.table_flags.set_has_structural_cdx(true);
+        writer.index_writer = Some(CdxWriter::new(index_dst, index_tags));
+        Ok(writer)
     }
 
     /// Helper function to set create a file at the given path
     /// and make the writer write to the newly created file.
     ///
+    /// If the record definition has Memo fields, the memo file is created
+    /// next to it, as well as the compound index (.cdx) if index tags were added
+    /// and the `.cpg` file if [write_cpg_file](Self::write_cpg_file) was called.
+    ///
     /// This function wraps the `File` in a `BufWriter` to increase performance.
     pub fn build_with_file_dest<P: AsRef<Path>>(
-        self,
+        mut self,
         path: P,
     ) -> Result<TableWriter<BufWriter<File>>, Error> {
+        self.check_index_tags()?;
+        let path = path.as_ref();
         let file = File::create(path).map_err(|err| Error::io_error(err, 0))?;
         let dst = BufWriter::new(file);
-        Ok(self.build_with_dest(dst))
+
+        if let Some(name) = self
+            .encoding
+            .code_page_mark()
+            .cpg_name()
+            .filter(|_| self.write_cpg_file)
+        {
+            std::fs::write(path.with_extension("cpg"), name)
+                .map_err(|err| Error::io_error(err, 0))?;
+        }
+
+        let at_least_one_field_is_memo = self.v.iter().any(|f_info| f_info.field_type.is_memo());
+
+        let memo_dst = if at_least_one_field_is_memo {
+            let memo_type = self.memo_type();
+            let memo_file = File::create(memo_type.file_path_for(path)).map_err(|error| Error {
+                record_num: 0,
+                field: None,
+                kind: ErrorKind::ErrorOpeningMemoFile(error),
+            })?;
+            Some(BufWriter::new(memo_file))
+        } else {
+            None
+        };
+
+        if !self.index_tags.is_empty() {
+            let index_file =
+                File::create(path.with_extension("cdx")).map_err(|err| Error::io_error(err, 0))?;
+            self.build_with_index_dest(dst, memo_dst, BufWriter::new(index_file))
+        } else if let Some(memo_dst) = memo_dst {
+            self.build_with_memo_dest(dst, memo_dst)
+        } else {
+            Ok(self.build_with_dest(dst))
+        }
     }
 
-    pub fn build_table_info(self) -> TableInfo {
+    fn memo_type(&self) -> MemoFileType {
+        self.hdr
+            .file_type
+            .supported_memo_type()
+            .unwrap_or(MemoFileType::DbaseMemo)
+    }
+
+    pub fn build_table_info(mut self) -> TableInfo {
+        update_null_flags_field(&mut self.v);
         TableInfo {
             header: self.hdr,
             fields_info: self.v,
             encoding: self.encoding,
+            field_properties: self.field_properties,
         }
     }
 }
@@ -258,18 +670,28 @@ mod private {
     impl_sealed_for!(f64);
     impl_sealed_for!(f32);
     impl_sealed_for!(i32);
+    impl_sealed_for!(Option<i32>);
     impl_sealed_for!(Option<f64>);
     impl_sealed_for!(Option<f32>);
     impl_sealed_for!(crate::record::field::Date);
     impl_sealed_for!(Option<crate::record::field::Date>);
     impl_sealed_for!(crate::record::field::FieldValue);
     impl_sealed_for!(crate::record::field::DateTime);
+    impl_sealed_for!(Option<crate::record::field::DateTime>);
+    impl_sealed_for!(Vec<u8>);
+    impl_sealed_for!(Option<Vec<u8>>);
 }
 
 /// Trait implemented by types we can write as dBase types
 ///
 /// This trait is 'private' and cannot be implemented on your custom types.
 pub trait WritableAsDbaseField: private::Sealed {
+    /// Returns whether the value is a None, which is stored in the null flags
+    /// of the record when the field is nullable
+    fn is_none(&self) -> bool {
+        false
+    }
+
     fn write_as<E: Encoding, W: Write>(
         &self,
         field_info: &FieldInfo,
@@ -319,6 +741,13 @@ pub struct FieldWriter<'a, W: Write> {
     pub(crate) fields_info: std::iter::Peekable<std::slice::Iter<'a, FieldInfo>>,
     pub(crate) buffer: &'a mut Cursor<Vec<u8>>,
     pub(crate) encoding: &'a DynEncoding,
+    /// The destination where the Memo field data is written
+    pub(crate) memo_writer: Option<&'a mut dyn MemoDataWriter>,
+    /// The null flags of the record, written in the `_NullFlags` field
+    pub(crate) null_flags: Vec<u8>,
+    /// The next value of each field (only meaningful for autoincrement ones),
+    /// `None` when values must not be assigned automatically
+    pub(crate) autoincrement_values: Option<&'a mut [u32]>,
 }
 
 impl<'a, W: Write> FieldWriter<'a, W> {
@@ -337,6 +766,9 @@ impl<'a, W: Write> FieldWriter<'a, W> {
     /// Values for which the number of bytes written would exceed the specified field_length
     /// (if it had to be specified) will be truncated
     ///
+    /// Writing a `None` value in an autoincrement field of a [TableWriter]
+    /// writes the next value of the field instead.
+    ///
     /// Trying to write more values than was declared when creating the writer will cause
     /// an `EndOfRecord` error.
     pub fn write_next_field_value<T: WritableAsDbaseField>(
@@ -346,9 +778,47 @@ impl<'a, W: Write> FieldWriter<'a, W> {
         if let Some(field_info) = self.fields_info.next() {
             self.buffer.set_position(0);
 
-            field_value
-                .write_as(field_info, self.encoding, &mut self.buffer)
-                .map_err(|kind| FieldIOError::new(kind, Some(field_info.clone())))?;
+            let assigned_value = if field_value.is_none() {
+                self.next_autoincrement_value(field_info)
+            } else {
+                None
+            };
+            match assigned_value {
+                Some(value) => value.write_as(field_info, self.encoding, &mut self.buffer),
+                None => field_value.write_as(field_info, self.encoding, &mut self.buffer),
+            }
+            .map_err(|kind| FieldIOError::new(kind, Some(field_info.clone())))?;
+
+            if matches!(
+                field_info.field_type,
+                FieldType::Character | FieldType::Varchar
+            ) && self.buffer.position() > u64::from(field_info.field_length)
+            {
+                // Do not cut a double-byte character in half,
+                // the remaining bytes are padded below
+                let length = self.encoding.code_page_mark().truncated_length(
+                    &self.buffer.get_ref()[..self.buffer.position() as usize],
+                    usize::from(field_info.field_length),
+                );
+                self.buffer.set_position(length as u64);
+            }
+
+            if field_info.field_type.is_memo() {
+                self.write_memo_index(field_info)
+                    .map_err(|kind| FieldIOError::new(kind, Some(field_info.clone())))?;
+            }
+
+            if let Some(bit) = field_info
+                .null_bit
+                .filter(|_| field_value.is_none() && assigned_value.is_none())
+            {
+                self.set_null_flag(bit);
+            }
+
+            if let Some(bit) = field_info.varlength_bit {
+                self.write_varlength(field_info, bit)
+                    .map_err(|error| FieldIOError::new(error.into(), Some(field_info.clone())))?;
+            }
 
             let bytes_written = self.buffer.position();
             let bytes_to_pad = i64::from(field_info.field_length) - bytes_written as i64;
@@ -368,12 +838,81 @@ impl<'a, W: Write> FieldWriter<'a, W> {
                 .map_err(|error| {
                     FieldIOError::new(ErrorKind::IoError(error), Some(field_info.clone()))
                 })?;
-            Ok(())
+            self.write_null_flags_if_next()
         } else {
             Err(FieldIOError::new(ErrorKind::TooManyFields, None))
         }
     }
 
+    /// Pads a variable length field that does not use its full length
+    /// and stores the length in its last byte
+    fn write_varlength(&mut self, field_info: &FieldInfo, bit: u16) -> std::io::Result<()> {
+        let length = self.buffer.position();
+        if length < u64::from(field_info.field_length) {
+            let padding = u64::from(field_info.field_length) - 1 - length;
+            for _ in 0..padding {
+                self.buffer.write_u8(0)?;
+            }
+            self.buffer.write_u8(length as u8)?;
+            self.set_null_flag(bit);
+        }
+        Ok(())
+    }
+
+    fn set_null_flag(&mut self, bit: u16) {
+        let byte_index = usize::from(bit / 8);
+        if self.null_flags.len() <= byte_index {
+            self.null_flags.resize(byte_index + 1, 0);
+        }
+        self.null_flags[byte_index] |= 1 << (bit % 8);
+    }
+
+    /// Writes the `_NullFlags` field if it is the next one,
+    /// it is not something the user has to write
+    fn write_null_flags_if_next(&mut self) -> Result<(), FieldIOError> {
+        if let Some(field_info) = self
+            .fields_info
+            .next_if(|info| info.field_type == FieldType::NullFlags)
+        {
+            self.null_flags.resize(field_info.field_length as usize, 0);
+            self.dst.write_all(&self.null_flags).map_err(|error| {
+                FieldIOError::new(ErrorKind::IoError(error), Some(field_info.clone()))
+            })?;
+            self.null_flags.clear();
+        }
+        Ok(())
+    }
+
+    /// Moves the memo content that was written in the buffer to the memo file
+    /// and replaces it with the index of the memo block that holds it
+    fn write_memo_index(&mut self, field_info: &FieldInfo) -> Result<(), ErrorKind> {
+        let memo_writer = self
+            .memo_writer
+            .as_mut()
+            .ok_or(ErrorKind::MissingMemoFile)?;
+        let num_bytes = self.buffer.position() as usize;
+        let index = if num_bytes == 0 {
+            None
+        } else {
+            Some(memo_writer.write_data(&self.buffer.get_ref()[..num_bytes])?)
+        };
+
+        self.buffer.set_position(0);
+        if field_info.field_length > 4 {
+            if let Some(index) = index {
+                write!(
+                    self.buffer,
+                    "{:>width$}",
+                    index,
+                    width = field_info.field_length as usize
+                )?;
+            }
+        } else {
+            self.buffer.write_u32::<LittleEndian>(index.unwrap_or(0))?;
+        }
+        Ok(())
+    }
+
     #[cfg(feature = "serde")]
     pub(crate) fn write_next_field_raw(&mut self, value: &[u8]) -> Result<(), FieldIOError> {
         if let Some(field_info) = self.fields_info.next() {
@@ -397,17 +936,30 @@ impl<'a, W: Write> FieldWriter<'a, W> {
                         FieldIOError::new(ErrorKind::IoError(error), Some(field_info.clone()))
                     })?;
             }
-            Ok(())
+            self.write_null_flags_if_next()
         } else {
             Err(FieldIOError::new(ErrorKind::EndOfRecord, None))
         }
     }
 
+    /// Returns the value to write in the autoincrement field that was just taken
+    /// from the iterator, and advances its counter
+    fn next_autoincrement_value(&mut self, field_info: &FieldInfo) -> Option<i32> {
+        if !field_info.is_autoincrement() {
+            return None;
+        }
+        let values = self.autoincrement_values.as_mut()?;
+        let index = values.len() - self.fields_info.len() - 1;
+        let value = values[index];
+        values[index] = value.wrapping_add(u32::from(field_info.autoincrement_step.max(1)));
+        Some(value as i32)
+    }
+
     fn write_deletion_flag(&mut self) -> std::io::Result<()> {
-        self.dst.write_u8(b' ')
+        self.dst.write_u8(VALID_RECORD_MARKER)
     }
 
-    fn all_fields_were_written(&mut self) -> bool {
+    pub(crate) fn all_fields_were_written(&mut self) -> bool {
         self.fields_info.peek().is_none()
     }
 }
@@ -416,7 +968,7 @@ impl<'a, W: Write> FieldWriter<'a, W> {
 ///
 /// The only way to create a TableWriter is to use its
 /// [TableWriterBuilder](struct.TableWriterBuilder.html)
-pub struct TableWriter<W: Write + Seek> {
+pub struct TableWriter<W: Write + Seek, M: Write + Seek = W> {
     dst: W,
     fields_info: Vec<FieldInfo>,
     /// contains the header of the input file
@@ -426,25 +978,70 @@ pub struct TableWriter<W: Write + Seek> {
     buffer: Cursor<Vec<u8>>,
     closed: bool,
     encoding: DynEncoding,
+    /// Where the Memo fields data is written
+    memo_writer: Option<MemoWriter<M>>,
+    /// Whether the records are appended to an existing file,
+    /// in which case the header and fields are already written
+    appending: bool,
+    /// The dBase 7 field properties, written after the fields
+    field_properties: Vec<u8>,
+    /// The next value of each field, stored in the fields descriptors when closing
+    autoincrement_values: Vec<u32>,
+    /// The record being written, copied to dst once complete
+    record_buffer: Vec<u8>,
+    /// Where the structural compound index is written, if the table has one
+    index_writer: Option<CdxWriter<W>>,
+    /// Path of the table the records are appended to, to rebuild its structural index
+    table_path: Option<PathBuf>,
 }
 
-impl<W: Write + Seek> TableWriter<W> {
-    fn new(
-        dst: W,
-        fields_info: Vec<FieldInfo>,
-        origin_header: Header,
-        encoding: DynEncoding,
-    ) -> Self {
+impl<W: Write + Seek, M: Write + Seek> TableWriter<W, M> {
+    fn new(dst: W, memo_writer: Option<MemoWriter<M>>, table_info: TableInfo) -> Self {
+        let autoincrement_values = table_info
+            .fields_info
+            .iter()
+            .map(|field_info| field_info.autoincrement_next_val)
+            .collect();
         Self {
             dst,
-            fields_info,
-            header: origin_header,
+            memo_writer,
+            fields_info: table_info.fields_info,
+            header: table_info.header,
             buffer: Cursor::new(vec![0u8; 255]),
             closed: false,
-            encoding,
+            encoding: table_info.encoding,
+            appending: false,
+            field_properties: table_info.field_properties,
+            autoincrement_values,
+            record_buffer: vec![],
+            index_writer: None,
+            table_path: None,
         }
     }
 
+    /// Creates a writer that appends records to the existing file `dst`
+    /// whose info were read in `table_info`
+    fn appending(
+        mut dst: W,
+        memo_writer: Option<MemoWriter<M>>,
+        table_info: TableInfo,
+    ) -> Result<Self, Error> {
+        let mut table_info = table_info;
+        let fields_info = &mut table_info.fields_info;
+        if fields_info.first().is_some_and(FieldInfo::is_deletion_flag) {
+            fields_info.remove(0);
+        }
+        assign_null_bits(fields_info);
+        let header = &table_info.header;
+        let end_of_records = header.record_position(header.num_records as usize);
+        dst.seek(SeekFrom::Start(end_of_records))
+            .map_err(|error| Error::io_error(error, header.num_records as usize))?;
+
+        let mut writer = Self::new(dst, memo_writer, table_info);
+        writer.appending = true;
+        Ok(writer)
+    }
+
     /// Writes a record the inner destination
     ///
     /// # Example
@@ -467,16 +1064,23 @@ impl<W: Write + Seek> TableWriter<W> {
     /// # }
     /// ```
     pub fn write_record<R: WritableRecord>(&mut self, record: &R) -> Result<(), Error> {
-        if self.header.num_records == 0 {
+        if self.header.num_records == 0 && !self.appending {
             // reserve the header
             self.write_header()?;
         }
 
+        self.record_buffer.clear();
         let mut field_writer = FieldWriter {
-            dst: &mut self.dst,
+            dst: &mut self.record_buffer,
             fields_info: self.fields_info.iter().peekable(),
             buffer: &mut self.buffer,
             encoding: &self.encoding,
+            memo_writer: self
+                .memo_writer
+                .as_mut()
+                .map(|writer| writer as &mut dyn MemoDataWriter),
+            null_flags: Vec::new(),
+            autoincrement_values: Some(&mut self.autoincrement_values),
         };
 
         let current_record_num = self.header.num_records as usize;
@@ -497,10 +1101,79 @@ impl<W: Write + Seek> TableWriter<W> {
             });
         }
 
+        self.dst
+            .write_all(&self.record_buffer)
+            .map_err(|error| Error::io_error(error, current_record_num))?;
+        if self.index_writer.is_some() {
+            self.index_record(current_record_num)
+                .map_err(|kind| Error {
+                    record_num: current_record_num,
+                    field: None,
+                    kind,
+                })?;
+        }
+
         self.header.num_records += 1;
         Ok(())
     }
 
+    /// Gives the values of the indexed fields of the record that was just written
+    /// to the index writer
+    fn index_record(&mut self, record_index: usize) -> Result<(), ErrorKind> {
+        let Some(index_writer) = &mut self.index_writer else {
+            return Ok(());
+        };
+        let mut offsets = Vec::with_capacity(self.fields_info.len());
+        // The deletion flag comes first
+        let mut offset = 1;
+        for field_info in &self.fields_info {
+            offsets.push(offset);
+            offset += field_info.field_length as usize;
+        }
+        let null_flags = self
+            .fields_info
+            .iter()
+            .position(|info| info.field_type == FieldType::NullFlags)
+            .map(|i| &self.record_buffer[offsets[i]..]);
+        let is_flag_set = |bit: Option<u16>| match (bit, null_flags) {
+            (Some(bit), Some(null_flags)) => {
+                null_flags[usize::from(bit / 8)] & (1 << (bit % 8)) != 0
+            }
+            _ => false,
+        };
+
+        let mut values = Record::default();
+        for (field_info, offset) in self.fields_info.iter().zip(offsets.iter().copied()) {
+            if !index_writer.uses_field(&field_info.name) {
+                continue;
+            }
+            let mut field_bytes =
+                &self.record_buffer[offset..offset + field_info.field_length as usize];
+            let value = if is_flag_set(field_info.null_bit) {
+                FieldValue::null(field_info.field_type)
+            } else {
+                if is_flag_set(field_info.varlength_bit) {
+                    // The last byte holds the length actually used
+                    let length = field_bytes.last().copied().unwrap_or(0);
+                    field_bytes = &field_bytes[..usize::from(length).min(field_bytes.len() - 1)];
+                }
+                FieldValue::read_from::<Cursor<Vec<u8>>, _>(
+                    field_bytes,
+                    &mut None,
+                    field_info,
+                    &self.encoding,
+                )?
+            };
+            values.insert(field_info.name.clone(), value);
+        }
+        pad_character_values(&mut values, &self.fields_info);
+        let meta = RecordMeta {
+            index: record_index,
+            is_deleted: self.record_buffer[0] == DELETED_RECORD_MARKER,
+        };
+        index_writer.add_record(&values, meta, &self.encoding)
+    }
+
     /// Writes the records to the inner destination
     ///
     /// Values for which the number of bytes written would exceed the specified field_length
@@ -554,25 +1227,112 @@ impl<W: Write + Seek> TableWriter<W> {
     /// Calling close on an already closed writer is a no-op
     pub fn close(&mut self) -> Result<(), Error> {
         if !self.closed {
+            for (field_info, next_value) in self
+                .fields_info
+                .iter_mut()
+                .zip(self.autoincrement_values.iter())
+            {
+                field_info.autoincrement_next_val = *next_value;
+            }
             self.dst
                 .seek(SeekFrom::Start(0))
                 .map_err(|error| Error::io_error(error, self.header.num_records as usize))?;
-            self.update_header();
-            self.write_header()?;
+            if self.appending {
+                // The fields are already written, and must be left untouched,
+                // except for the next value of autoincrement fields
+                self.header.update_date();
+                self.header
+                    .write_to(&mut self.dst)
+                    .map_err(|error| Error::io_error(error, self.header.num_records as usize))?;
+                self.write_autoincrement_next_values()
+                    .map_err(|error| Error::io_error(error, self.header.num_records as usize))?;
+            } else {
+                self.update_header();
+                self.write_header()?;
+            }
+            // Any previous terminator is overwritten by the records
+            let end_of_records = self
+                .header
+                .record_position(self.header.num_records as usize);
             self.dst
-                .seek(SeekFrom::End(0))
+                .seek(SeekFrom::Start(end_of_records))
                 .map_err(|error| Error::io_error(error, self.header.num_records as usize))?;
             self.dst
                 .write_u8(FILE_TERMINATOR)
                 .map_err(|error| Error::io_error(error, self.header.num_records as usize))?;
+            if let Some(memo_writer) = &mut self.memo_writer {
+                memo_writer
+                    .close()
+                    .map_err(|error| Error::io_error(error, self.header.num_records as usize))?;
+            }
+            if let Some(index_writer) = &mut self.index_writer {
+                index_writer
+                    .close()
+                    .map_err(|error| Error::io_error(error, self.header.num_records as usize))?;
+            }
+            if self.appending && self.header.table_flags.has_structural_cdx() {
+                self.update_structural_index()?;
+            }
             self.closed = true;
         }
         Ok(())
     }
 
+    /// Rebuilds the structural index of the table the records were appended to,
+    /// or clears its flag when it cannot be rebuilt
+    fn update_structural_index(&mut self) -> Result<(), Error> {
+        let num_records = self.header.num_records as usize;
+        self.dst
+            .flush()
+            .map_err(|error| Error::io_error(error, num_records))?;
+        let rebuilt = match &self.table_path {
+            Some(path) => update_structural_index(path)?,
+            None => false,
+        };
+        if !rebuilt {
+            self.header.table_flags.set_has_structural_cdx(false);
+            let dst = &mut self.dst;
+            dst.seek(SeekFrom::Start(0))
+                .and_then(|_| self.header.write_to(dst))
+                .and_then(|_| dst.flush())
+                .map_err(|error| Error::io_error(error, num_records))?;
+        }
+        Ok(())
+    }
+
+    /// Overwrites the next value stored in the descriptors of the autoincrement fields
+    fn write_autoincrement_next_values(&mut self) -> std::io::Result<()> {
+        let (descriptor_size, next_value_offset) = if self.header.file_type.is_dbase7() {
+            (
+                FieldInfo::DBASE7_SIZE,
+                FieldInfo::DBASE7_AUTOINCREMENT_OFFSET,
+            )
+        } else {
+            (FieldInfo::SIZE, FieldInfo::AUTOINCREMENT_OFFSET)
+        };
+        for (index, field_info) in self.fields_info.iter().enumerate() {
+            if !field_info.is_autoincrement() {
+                continue;
+            }
+            let position =
+                (self.header.size() + index * descriptor_size) as u64 + next_value_offset;
+            self.dst.seek(SeekFrom::Start(position))?;
+            self.dst
+                .write_u32::<LittleEndian>(field_info.autoincrement_next_val)?;
+        }
+        Ok(())
+    }
+
     fn update_header(&mut self) {
-        let mut offset_to_first_record =
-            Header::SIZE + (self.fields_info.len() * FieldInfo::SIZE) + std::mem::size_of::<u8>();
+        let field_descriptor_size = if self.header.file_type.is_dbase7() {
+            FieldInfo::DBASE7_SIZE
+        } else {
+            FieldInfo::SIZE
+        };
+        let mut offset_to_first_record = self.header.size()
+            + (self.fields_info.len() * field_descriptor_size)
+            + std::mem::size_of::<u8>()
+            + self.field_properties.len();
 
         if self.header.file_type.is_visual_fox_pro() {
             offset_to_first_record += BACKLINK_SIZE as usize;
@@ -585,7 +1345,11 @@ impl<W: Write + Seek> TableWriter<W> {
 
         self.header.offset_to_first_record = offset_to_first_record as u16;
         self.header.size_of_record = size_of_record;
-        self.header.code_page_mark = self.encoding.code_page_mark();
+        // Keep the language driver of the table when it uses the code page of the encoding
+        let code_page_mark = self.encoding.code_page_mark();
+        if self.header.code_page_mark.code_page() != code_page_mark.code_page() {
+            self.header.code_page_mark = code_page_mark;
+        }
     }
 
     fn write_header(&mut self) -> Result<(), Error> {
@@ -593,14 +1357,21 @@ impl<W: Write + Seek> TableWriter<W> {
             .write_to(&mut self.dst)
             .map_err(|error| Error::io_error(error, 0))?;
 
+        let is_dbase7 = self.header.file_type.is_dbase7();
         for record_info in &self.fields_info {
-            record_info
-                .write_to(&mut self.dst)
-                .map_err(|error| Error::io_error(error, 0))?;
+            if is_dbase7 {
+                record_info.write_dbase7_to(&mut self.dst)
+            } else {
+                record_info.write_to(&mut self.dst)
+            }
+            .map_err(|error| Error::io_error(error, 0))?;
         }
         self.dst
             .write_u8(TERMINATOR_VALUE)
             .map_err(|error| Error::io_error(error, 0))?;
+        self.dst
+            .write_all(&self.field_properties)
+            .map_err(|error| Error::io_error(error, 0))?;
 
         // TODO foxpro adds this backlink thing
         //  Since we don't have a spec for we just write zeros
@@ -616,7 +1387,90 @@ impl<W: Write + Seek> TableWriter<W> {
     }
 }
 
-impl<T: Write + Seek> Drop for TableWriter<T> {
+impl<W: Read + Write + Seek> TableWriter<W> {
+    /// Creates a writer that appends records to an existing dBase file
+    ///
+    /// The header and fields of the file are read from `dst`, and the records
+    /// are written after the last record of the file.
+    /// When the writer is closed, the number of records in the header is updated.
+    ///
+    /// Memo fields cannot be written using this function, use [TableWriter::append_to_path]
+    /// (which also uses the encoding of the `.cpg` file next to the table).
+    /// The structural index of the table cannot be found from `dst` either,
+    /// so its `has_structural_cdx` flag is cleared.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), dbase::Error> {
+    /// let mut file = std::io::Cursor::new(std::fs::read("tests/data/stations.dbf").unwrap());
+    /// let mut station = dbase::Reader::new(&mut file)?.read()?.remove(0);
+    /// station.insert("name".to_owned(), dbase::FieldValue::Character(Some("Ballston".to_owned())));
+    ///
+    /// let mut writer = dbase::TableWriter::append_to(&mut file)?;
+    /// writer.write_record(&station)?;
+    /// writer.close()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn append_to(mut dst: W) -> Result<Self, Error> {
+        dst.seek(SeekFrom::Start(0))
+            .map_err(|error| Error::io_error(error, 0))?;
+        let table_info = Reader::new(&mut dst)?.into_table_info();
+        Self::appending(dst, None, table_info)
+    }
+}
+
+impl TableWriter<BufWriter<File>> {
+    /// Creates a writer that appends records to the existing dBase file at `path`
+    ///
+    /// If the file has memo fields, the memo data is appended to the associated memo file.
+    /// If it has a structural compound index (.cdx), the index is rebuilt when the writer
+    /// is closed, or the `has_structural_cdx` flag is cleared when the keys of its tags
+    /// cannot be computed (see [reindex](crate::index::cdx::reindex)).
+    ///
+    /// Like [Reader::from_path], when a `.cpg` file is next to the table, the encoding it names
+    /// is used instead of the one of the code page mark of the file.
+    ///
+    /// See [TableWriter::append_to]
+    pub fn append_to_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let open_read_write = |path: &Path| OpenOptions::new().read(true).write(true).open(path);
+        let path = path.as_ref();
+        let mut file = open_read_write(path).map_err(|error| Error::io_error(error, 0))?;
+        let encoding = read_cpg_file(path)?;
+        let table_info = Reader::new_impl(&mut file, encoding)?.into_table_info();
+
+        let at_least_one_field_is_memo = table_info
+            .fields_info
+            .iter()
+            .any(|f_info| f_info.field_type.is_memo());
+        let memo_type = table_info.header.file_type.supported_memo_type();
+
+        let memo_writer = match memo_type {
+            Some(memo_type) if at_least_one_field_is_memo => {
+                let mut memo_file =
+                    open_read_write(&memo_type.file_path_for(path)).map_err(|error| Error {
+                        record_num: 0,
+                        field: None,
+                        kind: ErrorKind::ErrorOpeningMemoFile(error),
+                    })?;
+                let memo_header = MemoHeader::read_from(&mut memo_file, memo_type)
+                    .map_err(|error| Error::io_error(error, 0))?;
+                let memo_writer =
+                    MemoWriter::with_header(memo_type, memo_header, BufWriter::new(memo_file))
+                        .map_err(|error| Error::io_error(error, 0))?;
+                Some(memo_writer)
+            }
+            _ => None,
+        };
+
+        let mut writer = Self::appending(BufWriter::new(file), memo_writer, table_info)?;
+        writer.table_path = Some(path.to_path_buf());
+        Ok(writer)
+    }
+}
+
+impl<W: Write + Seek, M: Write + Seek> Drop for TableWriter<W, M> {
     fn drop(&mut self) {
         let _ = self.close();
     }
@@ -4,8 +4,9 @@ extern crate dbase;
 use std::io::{Cursor, Read, Seek, Write};
 
 use dbase::{
-    Date, DateTime, FieldIOError, FieldIterator, FieldName, FieldValue, FieldWriter,
-    ReadableRecord, Reader, Record, TableWriterBuilder, Time, WritableRecord,
+    Date, DateTime, DecodeErrorPolicy, DeletionPolicy, FieldIOError, FieldIterator, FieldName,
+    FieldType, FieldValue, FieldWriter, ReadableRecord, Reader, Record, RecordMeta, Table,
+    TableWriter, TableWriterBuilder, Time, WritableRecord,
 };
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Debug;
@@ -172,15 +173,18 @@ fn from_scratch_fox_pro_record() {
         .add_datetime_field(FieldName::try_from("datetime").unwrap());
 
     let mut record = Record::default();
-    record.insert(String::from("integer"), FieldValue::Integer(17));
-    record.insert(String::from("double"), FieldValue::Double(54621.154));
-    record.insert(String::from("currency"), FieldValue::Currency(4567.134));
+    record.insert(String::from("integer"), FieldValue::Integer(Some(17)));
+    record.insert(String::from("double"), FieldValue::Double(Some(54621.154)));
+    record.insert(
+        String::from("currency"),
+        FieldValue::Currency(Some(4567.134)),
+    );
     record.insert(
         String::from("datetime"),
-        FieldValue::DateTime(DateTime::new(
-            Date::new(01, 06, 2006),
+        FieldValue::DateTime(Some(DateTime::new(
+            Date::new(1, 6, 2006),
             Time::new(12, 50, 20),
-        )),
+        ))),
     );
 
     let records = vec![record];
@@ -206,7 +210,7 @@ fn from_scratch_fox_pro_struct_record() {
         .add_integer_field(FieldName::try_from("integer").unwrap());
 
     let records = vec![FoxProRecord {
-        datetime: DateTime::new(Date::new(12, 02, 1999), Time::new(21, 20, 35)),
+        datetime: DateTime::new(Date::new(12, 2, 1999), Time::new(21, 20, 35)),
         double: 8649.48851,
         currency: 3489.9612314,
         integer: 42069,
@@ -215,6 +219,160 @@ fn from_scratch_fox_pro_struct_record() {
     write_read_compare(&records, writer_builder);
 }
 
+#[test]
+fn from_scratch_visual_fox_pro_nullable_record() {
+    let writer_builder = TableWriterBuilder::new()
+        .add_integer_field(FieldName::try_from("integer").unwrap())
+        .nullable()
+        .add_double_field(FieldName::try_from("double").unwrap())
+        .nullable()
+        .add_currency_field(FieldName::try_from("currency").unwrap())
+        .add_datetime_field(FieldName::try_from("datetime").unwrap())
+        .nullable()
+        .add_date_field(FieldName::try_from("date").unwrap())
+        .nullable();
+
+    let mut first = Record::default();
+    first.insert(String::from("integer"), FieldValue::Integer(None));
+    first.insert(String::from("double"), FieldValue::Double(Some(1.5)));
+    first.insert(String::from("currency"), FieldValue::Currency(Some(2.25)));
+    first.insert(String::from("datetime"), FieldValue::DateTime(None));
+    first.insert(String::from("date"), FieldValue::Date(None));
+
+    let mut second = Record::default();
+    second.insert(String::from("integer"), FieldValue::Integer(Some(7)));
+    second.insert(String::from("double"), FieldValue::Double(None));
+    second.insert(String::from("currency"), FieldValue::Currency(Some(0.0)));
+    second.insert(
+        String::from("datetime"),
+        FieldValue::DateTime(Some(DateTime::new(
+            Date::new(3, 4, 2021),
+            Time::new(5, 6, 7),
+        ))),
+    );
+    second.insert(
+        String::from("date"),
+        FieldValue::Date(Some(Date::new(3, 4, 2021))),
+    );
+
+    let records = vec![first, second];
+    write_read_compare(&records, writer_builder);
+}
+
+#[test]
+fn from_scratch_visual_fox_pro_varchar_varbinary() {
+    let writer_builder = || {
+        TableWriterBuilder::new()
+            .add_varchar_field(FieldName::try_from("name").unwrap(), 8)
+            .add_varbinary_field(FieldName::try_from("data").unwrap(), 4)
+            .add_varchar_field(FieldName::try_from("comment").unwrap(), 10)
+            .nullable()
+    };
+
+    let mut short = Record::default();
+    short.insert(
+        "name".to_owned(),
+        FieldValue::Varchar(Some("ab ".to_owned())),
+    );
+    short.insert("data".to_owned(), FieldValue::Varbinary(Some(vec![0, 1])));
+    short.insert("comment".to_owned(), FieldValue::Varchar(None));
+
+    let mut full = Record::default();
+    full.insert(
+        "name".to_owned(),
+        FieldValue::Varchar(Some("abcdefgh".to_owned())),
+    );
+    full.insert(
+        "data".to_owned(),
+        FieldValue::Varbinary(Some(vec![4, 3, 2, 1])),
+    );
+    full.insert(
+        "comment".to_owned(),
+        FieldValue::Varchar(Some(String::new())),
+    );
+
+    let records = vec![short, full];
+    write_read_compare(&records, writer_builder());
+
+    let mut dst = Cursor::new(Vec::<u8>::new());
+    writer_builder()
+        .build_with_dest(&mut dst)
+        .write_records(&records)
+        .unwrap();
+    dst.set_position(0);
+    let mut table = Table::new(dst).unwrap();
+    table.update_field(0, "name", &"abcdefgh").unwrap();
+    table.update_field(1, "name", &"xy").unwrap();
+    let names = (0..2)
+        .map(|i| table.get(i).unwrap().remove("name").unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            FieldValue::Varchar(Some("abcdefgh".to_owned())),
+            FieldValue::Varchar(Some("xy".to_owned()))
+        ]
+    );
+}
+
+dbase_record! {
+    #[derive(Clone, Debug, PartialEq)]
+    struct NullableRecord {
+        id: i32,
+        score: Option<i32>
+    }
+}
+
+#[test]
+fn test_null_flags_field() {
+    let mut dst = Cursor::new(Vec::<u8>::new());
+    let writer = TableWriterBuilder::new()
+        .add_integer_field(FieldName::try_from("id").unwrap())
+        .add_integer_field(FieldName::try_from("score").unwrap())
+        .nullable()
+        .build_with_dest(&mut dst);
+    let records = vec![
+        NullableRecord { id: 1, score: None },
+        NullableRecord {
+            id: 2,
+            score: Some(0),
+        },
+    ];
+    writer.write_records(&records).unwrap();
+    dst.set_position(0);
+
+    let mut reader = Reader::new(&mut dst).unwrap();
+    let null_flags = reader.fields().last().unwrap();
+    assert_eq!(null_flags.name(), "_NullFlags");
+    assert!(null_flags.flags().is_system());
+    assert!(reader.fields()[2].flags().is_nullable());
+    assert_eq!(reader.read_as::<NullableRecord>().unwrap(), records);
+
+    // The null flags are hidden from the user
+    reader.seek(0).unwrap();
+    let record = reader.read().unwrap().remove(0);
+    assert_eq!(record.as_ref().len(), 2);
+    assert_eq!(record.get("score"), Some(&FieldValue::Integer(None)));
+
+    dst.set_position(0);
+    let mut table = Table::new(dst).unwrap();
+    table.update_field(0, "score", &Some(42)).unwrap();
+    table
+        .update_field(1, "score", &Option::<i32>::None)
+        .unwrap();
+    assert_eq!(
+        table.get_as::<NullableRecord>(0).unwrap(),
+        NullableRecord {
+            id: 1,
+            score: Some(42)
+        }
+    );
+    assert_eq!(
+        table.get_as::<NullableRecord>(1).unwrap(),
+        NullableRecord { id: 2, score: None }
+    );
+}
+
 dbase_record! {
     #[derive(Clone, Debug, PartialEq)]
     struct User {
@@ -292,3 +450,1538 @@ fn non_unicode_codepages() {
         Some(&dbase::FieldValue::Character(Some("Äöü!§$%&/".to_string())))
     );
 }
+
+dbase_record! {
+    #[derive(Clone, Debug, PartialEq)]
+    struct Comment {
+        author: String,
+        text: String,
+    }
+}
+
+fn write_read_memo_file(writer_builder: TableWriterBuilder, file_name: &str, memo_ext: &str) {
+    let dbf_path = std::env::temp_dir().join(file_name);
+    let records = vec![
+        Comment {
+            author: "Ferrys".to_string(),
+            text: "Short comment".to_string(),
+        },
+        Comment {
+            author: "Alex".to_string(),
+            text: "A comment longer than what a Character field can hold. ".repeat(30),
+        },
+        Comment {
+            author: "Jamie".to_string(),
+            text: "".to_string(),
+        },
+    ];
+
+    let writer = writer_builder.build_with_file_dest(&dbf_path).unwrap();
+    writer.write_records(&records).unwrap();
+    assert!(dbf_path.with_extension(memo_ext).exists());
+
+    let mut reader = Reader::from_path(&dbf_path).unwrap();
+    let read_records = reader.read_as::<Comment>().unwrap();
+    assert_eq!(read_records, records);
+
+    let _ = std::fs::remove_file(&dbf_path);
+    let _ = std::fs::remove_file(dbf_path.with_extension(memo_ext));
+}
+
+#[test]
+fn from_scratch_dbase_memo() {
+    let writer_builder = TableWriterBuilder::new()
+        .add_character_field("Author".try_into().unwrap(), 20)
+        .add_memo_field("Text".try_into().unwrap());
+
+    write_read_memo_file(writer_builder, "dbase_memo.dbf", "dbt");
+}
+
+#[test]
+fn from_scratch_fox_pro_memo() {
+    let writer_builder = TableWriterBuilder::new()
+        .add_character_field("Author".try_into().unwrap(), 20)
+        .add_memo_field("Text".try_into().unwrap())
+        .add_integer_field("Id".try_into().unwrap());
+
+    let dbf_path = std::env::temp_dir().join("fox_pro_memo.dbf");
+    let mut writer = writer_builder.build_with_file_dest(&dbf_path).unwrap();
+    let mut record = Record::default();
+    record.insert(
+        "Author".to_string(),
+        FieldValue::Character(Some("Yoshi".to_string())),
+    );
+    record.insert(
+        "Text".to_string(),
+        FieldValue::Memo("Memo stored in a .fpt file".to_string()),
+    );
+    record.insert("Id".to_string(), FieldValue::Integer(Some(1)));
+    writer.write_record(&record).unwrap();
+    writer.close().unwrap();
+    assert!(dbf_path.with_extension("fpt").exists());
+
+    let records = dbase::read(&dbf_path).unwrap();
+    assert_eq!(records, vec![record]);
+
+    let _ = std::fs::remove_file(&dbf_path);
+    let _ = std::fs::remove_file(dbf_path.with_extension("fpt"));
+}
+
+#[test]
+fn from_scratch_visual_fox_pro_binary_memos() {
+    let writer_builder = TableWriterBuilder::new()
+        .add_blob_field("Scan".try_into().unwrap())
+        .add_binary_memo_field("Raw".try_into().unwrap())
+        .add_general_field("Object".try_into().unwrap())
+        .add_memo_field("Text".try_into().unwrap());
+
+    let dbf_path = std::env::temp_dir().join("visual_fox_pro_binary_memos.dbf");
+    let mut writer = writer_builder.build_with_file_dest(&dbf_path).unwrap();
+    // Trailing zeros and terminator bytes must not be stripped from binary data
+    let scan = vec![0x89, b'P', b'N', b'G', 0x1A, 0x1F, 0, 0];
+    let mut record = Record::default();
+    record.insert("Scan".to_string(), FieldValue::Blob(scan.clone()));
+    record.insert("Raw".to_string(), FieldValue::BinaryMemo(vec![0xFF; 600]));
+    record.insert("Object".to_string(), FieldValue::General(vec![]));
+    record.insert("Text".to_string(), FieldValue::Memo("text".to_string()));
+    writer.write_record(&record).unwrap();
+    writer.close().unwrap();
+
+    let mut reader = Reader::from_path(&dbf_path).unwrap();
+    assert_eq!(reader.fields()[2].field_type(), FieldType::BinaryMemo);
+    assert!(reader.fields()[2].flags().is_binary());
+    let records = reader.read().unwrap();
+    assert_eq!(records, vec![record]);
+    let bytes: Vec<u8> = records[0].get("Scan").cloned().unwrap().try_into().unwrap();
+    assert_eq!(bytes, scan);
+
+    let _ = std::fs::remove_file(&dbf_path);
+    let _ = std::fs::remove_file(dbf_path.with_extension("fpt"));
+}
+
+#[test]
+fn from_scratch_dbase7() {
+    let writer_builder = TableWriterBuilder::new()
+        .add_autoincrement_field("Id".try_into().unwrap())
+        .add_long_field(FieldName::dbase7("Account balance").unwrap())
+        .add_dbase7_double_field("Ratio".try_into().unwrap())
+        .add_timestamp_field("Updated".try_into().unwrap())
+        .add_memo_field("Notes".try_into().unwrap());
+
+    let dbf_path = std::env::temp_dir().join("dbase7.dbf");
+    let writer = writer_builder.build_with_file_dest(&dbf_path).unwrap();
+    let mut records = vec![];
+    for (id, balance, ratio) in [(1, -42, -0.5), (2, 1_000_000, 3.25)] {
+        let mut record = Record::default();
+        // The writer assigns the ids
+        record.insert("Id".to_string(), FieldValue::Autoincrement(None));
+        record.insert(
+            "Account balance".to_string(),
+            FieldValue::Long(Some(balance)),
+        );
+        record.insert("Ratio".to_string(), FieldValue::DBase7Double(Some(ratio)));
+        record.insert(
+            "Updated".to_string(),
+            FieldValue::Timestamp(Some(DateTime::new(
+                Date::new(14, 3, 2021),
+                Time::new(8, 30, 15),
+            ))),
+        );
+        record.insert(
+            "Notes".to_string(),
+            FieldValue::Memo(format!("Record {}", id)),
+        );
+        records.push(record);
+    }
+    let mut record = Record::default();
+    record.insert("Id".to_string(), FieldValue::Autoincrement(None));
+    record.insert("Account balance".to_string(), FieldValue::Long(None));
+    record.insert("Ratio".to_string(), FieldValue::DBase7Double(None));
+    record.insert("Updated".to_string(), FieldValue::Timestamp(None));
+    record.insert("Notes".to_string(), FieldValue::Memo(String::new()));
+    records.push(record);
+    writer.write_records(&records).unwrap();
+    assert!(dbf_path.with_extension("dbt").exists());
+
+    let bytes = std::fs::read(&dbf_path).unwrap();
+    assert_eq!(bytes[0], 0x8C);
+    // 68 bytes header, 48 bytes per field descriptor and the terminator
+    let offset_to_first_record = u16::from_le_bytes([bytes[8], bytes[9]]);
+    assert_eq!(offset_to_first_record as usize, 68 + 5 * 48 + 1);
+
+    let mut reader = Reader::from_path(&dbf_path).unwrap();
+    let field_types = reader
+        .fields()
+        .iter()
+        .skip(1)
+        .map(|field| field.field_type())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        field_types,
+        vec![
+            FieldType::Autoincrement,
+            FieldType::Long,
+            FieldType::DBase7Double,
+            FieldType::Timestamp,
+            FieldType::Memo
+        ]
+    );
+    assert_eq!(reader.fields()[1].autoincrement_next_value(), Some(4));
+    let read_records = reader.read().unwrap();
+    for (id, (read_record, mut record)) in read_records.into_iter().zip(records).enumerate() {
+        record.insert(
+            "Id".to_string(),
+            FieldValue::Autoincrement(Some(id as i32 + 1)),
+        );
+        assert_eq!(read_record, record);
+    }
+
+    let _ = std::fs::remove_file(&dbf_path);
+    let _ = std::fs::remove_file(dbf_path.with_extension("dbt"));
+}
+
+dbase_record! {
+    #[derive(Clone, Debug, PartialEq)]
+    struct Customer {
+        id: Option<i32>,
+        name: String,
+    }
+}
+
+#[test]
+fn test_autoincrement() {
+    let customer = |id, name: &str| Customer {
+        id,
+        name: name.to_string(),
+    };
+    let mut cursor = Cursor::new(Vec::<u8>::new());
+    let writer = TableWriterBuilder::new()
+        .add_integer_field("Id".try_into().unwrap())
+        .autoincrement(10, 5)
+        .add_character_field("Name".try_into().unwrap(), 20)
+        .build_with_dest(&mut cursor);
+    writer
+        .write_records(&[customer(None, "Ferrys"), customer(None, "Alex")])
+        .unwrap();
+
+    // The next value is persisted, appending continues from it
+    let mut writer = TableWriter::append_to(&mut cursor).unwrap();
+    writer.write_record(&customer(None, "Jamie")).unwrap();
+    writer.close().unwrap();
+    drop(writer);
+
+    cursor.set_position(0);
+    let mut reader = Reader::new(&mut cursor).unwrap();
+    let id_field = &reader.fields()[1];
+    assert!(id_field.is_autoincrement());
+    assert!(id_field.flags().is_autoincrement());
+    assert_eq!(id_field.autoincrement_next_value(), Some(25));
+    assert_eq!(id_field.autoincrement_step(), Some(5));
+    assert_eq!(reader.fields()[2].autoincrement_next_value(), None);
+    assert_eq!(
+        reader.read_as::<Customer>().unwrap(),
+        vec![
+            customer(Some(10), "Ferrys"),
+            customer(Some(15), "Alex"),
+            customer(Some(20), "Jamie")
+        ]
+    );
+}
+
+fn users_with_second_one_deleted() -> Cursor<Vec<u8>> {
+    let users = vec![
+        User {
+            first_name: "Ferrys".to_string(),
+            last_name: "Rust".to_string(),
+        },
+        User {
+            first_name: "Alex".to_string(),
+            last_name: "Rider".to_string(),
+        },
+        User {
+            first_name: "Jamie".to_string(),
+            last_name: "Oliver".to_string(),
+        },
+    ];
+
+    let mut cursor = Cursor::new(Vec::<u8>::new());
+    let writer = TableWriterBuilder::new()
+        .add_character_field("First Name".try_into().unwrap(), 50)
+        .add_character_field("Last Name".try_into().unwrap(), 50)
+        .build_with_dest(&mut cursor);
+    writer.write_records(&users).unwrap();
+
+    cursor.set_position(0);
+    let reader = Reader::new(&mut cursor).unwrap();
+    let second_record_pos =
+        reader.header().offset_to_first_record as usize + reader.header().size_of_record as usize;
+    cursor.get_mut()[second_record_pos] = b'*';
+    cursor.set_position(0);
+    cursor
+}
+
+#[test]
+fn test_deletion_policy() {
+    let mut reader = Reader::new(users_with_second_one_deleted()).unwrap();
+    assert_eq!(reader.deletion_policy(), DeletionPolicy::Include);
+    let names = reader
+        .read_as::<User>()
+        .unwrap()
+        .into_iter()
+        .map(|user| user.first_name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["Ferrys", "Alex", "Jamie"]);
+
+    let mut reader = Reader::new(users_with_second_one_deleted()).unwrap();
+    reader.set_deletion_policy(DeletionPolicy::Skip);
+    let names = reader
+        .read_as::<User>()
+        .unwrap()
+        .into_iter()
+        .map(|user| user.first_name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["Ferrys", "Jamie"]);
+
+    let mut reader = Reader::new(users_with_second_one_deleted()).unwrap();
+    reader.set_deletion_policy(DeletionPolicy::Only);
+    let names = reader
+        .read_as::<User>()
+        .unwrap()
+        .into_iter()
+        .map(|user| user.first_name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["Alex"]);
+}
+
+#[test]
+fn test_iter_records_with_meta() {
+    let mut reader = Reader::new(users_with_second_one_deleted()).unwrap();
+    let metas = reader
+        .iter_records_with_meta_as::<User>()
+        .map(|result| result.unwrap().0)
+        .collect::<Vec<RecordMeta>>();
+    assert_eq!(
+        metas,
+        vec![
+            RecordMeta {
+                index: 0,
+                is_deleted: false
+            },
+            RecordMeta {
+                index: 1,
+                is_deleted: true
+            },
+            RecordMeta {
+                index: 2,
+                is_deleted: false
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_table_update_in_place() {
+    let mut cursor = users_with_second_one_deleted();
+    let original_len = cursor.get_ref().len();
+    let mut table = Table::new(&mut cursor).unwrap();
+    assert_eq!(table.num_records(), 3);
+
+    let mut alex = table.get_as::<User>(1).unwrap();
+    assert_eq!(alex.first_name, "Alex");
+    alex.last_name = "Turner".to_string();
+    table.update(1, &alex).unwrap();
+    // Field names are not case sensitive
+    table
+        .update_field(2, "FIRST NAME", &"Jane".to_string())
+        .unwrap();
+
+    assert!(matches!(
+        table.get(3).unwrap_err().kind(),
+        dbase::ErrorKind::RecordIndexOutOfRange(3)
+    ));
+    assert!(matches!(
+        table
+            .update_field(0, "Age", &"42".to_string())
+            .unwrap_err()
+            .kind(),
+        dbase::ErrorKind::FieldNotFound(_)
+    ));
+    table.close().unwrap();
+    drop(table);
+
+    assert_eq!(cursor.get_ref().len(), original_len);
+    cursor.set_position(0);
+    let mut reader = Reader::new(cursor).unwrap();
+    let records = reader
+        .iter_records_with_meta_as::<User>()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let names = records
+        .iter()
+        .map(|(meta, user)| {
+            (
+                meta.is_deleted,
+                user.first_name.as_str(),
+                user.last_name.as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            (false, "Ferrys", "Rust"),
+            (true, "Alex", "Turner"),
+            (false, "Jane", "Oliver")
+        ]
+    );
+}
+
+#[test]
+fn test_table_update_memo() {
+    let dbf_path = std::env::temp_dir().join("table_update_memo.dbf");
+    let records = vec![
+        Comment {
+            author: "Ferrys".to_string(),
+            text: "First comment".to_string(),
+        },
+        Comment {
+            author: "Alex".to_string(),
+            text: "Second comment".to_string(),
+        },
+    ];
+    TableWriterBuilder::new()
+        .add_character_field("Author".try_into().unwrap(), 20)
+        .add_memo_field("Text".try_into().unwrap())
+        .build_with_file_dest(&dbf_path)
+        .unwrap()
+        .write_records(&records)
+        .unwrap();
+
+    let mut table = Table::open(&dbf_path).unwrap();
+    assert_eq!(table.get_as::<Comment>(1).unwrap(), records[1]);
+    let edited = Comment {
+        author: "Alex".to_string(),
+        text: "An edited comment, longer than the first one. ".repeat(20),
+    };
+    table.update(1, &edited).unwrap();
+    assert_eq!(table.get_as::<Comment>(1).unwrap(), edited);
+    table.close().unwrap();
+    drop(table);
+
+    let mut reader = Reader::from_path(&dbf_path).unwrap();
+    let read_records = reader.read_as::<Comment>().unwrap();
+    assert_eq!(read_records, vec![records[0].clone(), edited]);
+
+    let _ = std::fs::remove_file(&dbf_path);
+    let _ = std::fs::remove_file(dbf_path.with_extension("dbt"));
+}
+
+#[test]
+fn test_append_to() {
+    let mut cursor = users_with_second_one_deleted();
+    let original_len = cursor.get_ref().len();
+
+    // Appending nothing must leave the file as is
+    TableWriter::append_to(&mut cursor)
+        .unwrap()
+        .close()
+        .unwrap();
+    assert_eq!(cursor.get_ref().len(), original_len);
+
+    let new_user = User {
+        first_name: "Sam".to_string(),
+        last_name: "Porter".to_string(),
+    };
+    let mut writer = TableWriter::append_to(&mut cursor).unwrap();
+    writer.write_record(&new_user).unwrap();
+    writer.close().unwrap();
+    drop(writer);
+
+    cursor.set_position(0);
+    let mut reader = Reader::new(&mut cursor).unwrap();
+    assert_eq!(reader.header().num_records, 4);
+    let record_size = reader.header().size_of_record as usize;
+    let names = reader
+        .read_as::<User>()
+        .unwrap()
+        .into_iter()
+        .map(|user| user.first_name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["Ferrys", "Alex", "Jamie", "Sam"]);
+    assert_eq!(cursor.get_ref().len(), original_len + record_size);
+    assert_eq!(cursor.get_ref().last(), Some(&0x1A));
+}
+
+#[test]
+fn test_append_to_path_with_memo() {
+    let dbf_path = std::env::temp_dir().join("append_memo.dbf");
+    let first = Comment {
+        author: "Ferrys".to_string(),
+        text: "A comment longer than one memo block. ".repeat(20),
+    };
+    let second = Comment {
+        author: "Alex".to_string(),
+        text: "Appended comment".to_string(),
+    };
+    let mut writer = TableWriterBuilder::new()
+        .add_character_field("Author".try_into().unwrap(), 20)
+        .add_memo_field("Text".try_into().unwrap())
+        .build_with_file_dest(&dbf_path)
+        .unwrap();
+    writer.write_record(&first).unwrap();
+    writer.close().unwrap();
+    drop(writer);
+
+    let mut writer = TableWriter::append_to_path(&dbf_path).unwrap();
+    writer.write_record(&second).unwrap();
+    writer.close().unwrap();
+    drop(writer);
+
+    let mut reader = Reader::from_path(&dbf_path).unwrap();
+    let read_records = reader.read_as::<Comment>().unwrap();
+    assert_eq!(read_records, vec![first, second]);
+
+    let _ = std::fs::remove_file(&dbf_path);
+    let _ = std::fs::remove_file(dbf_path.with_extension("dbt"));
+}
+
+#[test]
+fn test_table_pack_into() {
+    let mut cursor = users_with_second_one_deleted();
+    let mut table = Table::new(&mut cursor).unwrap();
+    let mut packed = Cursor::new(Vec::<u8>::new());
+    table.pack_into(&mut packed).unwrap();
+    drop(table);
+
+    packed.set_position(0);
+    let mut reader = Reader::new(packed).unwrap();
+    assert_eq!(reader.header().num_records, 2);
+    let names = reader
+        .read_as::<User>()
+        .unwrap()
+        .into_iter()
+        .map(|user| user.first_name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["Ferrys", "Jamie"]);
+}
+
+fn pack_memo_file_in_place<R>(
+    writer_builder: TableWriterBuilder,
+    records: Vec<R>,
+    file_name: &str,
+    memo_ext: &str,
+) where
+    R: WritableRecord + ReadableRecord + Clone + Debug + PartialEq,
+{
+    let dbf_path = std::env::temp_dir().join(file_name);
+    let memo_path = dbf_path.with_extension(memo_ext);
+    writer_builder
+        .build_with_file_dest(&dbf_path)
+        .unwrap()
+        .write_records(&records)
+        .unwrap();
+    let memo_len_before = std::fs::metadata(&memo_path).unwrap().len();
+
+    // Mark the second record as deleted
+    let mut dbf_bytes = std::fs::read(&dbf_path).unwrap();
+    let header = *Reader::new(Cursor::new(&dbf_bytes)).unwrap().header();
+    dbf_bytes[header.offset_to_first_record as usize + header.size_of_record as usize] = b'*';
+    std::fs::write(&dbf_path, &dbf_bytes).unwrap();
+
+    let mut table = Table::open(&dbf_path).unwrap();
+    table.pack().unwrap();
+    assert_eq!(table.num_records(), 2);
+    assert_eq!(table.get_as::<R>(1).unwrap(), records[2]);
+    table.close().unwrap();
+    drop(table);
+
+    assert_eq!(
+        std::fs::metadata(&dbf_path).unwrap().len(),
+        dbf_bytes.len() as u64 - header.size_of_record as u64
+    );
+    assert!(std::fs::metadata(&memo_path).unwrap().len() < memo_len_before);
+
+    let mut reader = Reader::from_path(&dbf_path).unwrap();
+    let read_records = reader.read_as::<R>().unwrap();
+    assert_eq!(read_records, vec![records[0].clone(), records[2].clone()]);
+
+    let _ = std::fs::remove_file(&dbf_path);
+    let _ = std::fs::remove_file(&memo_path);
+}
+
+#[test]
+fn test_table_pack_dbase_memo() {
+    let writer_builder = TableWriterBuilder::new()
+        .add_character_field("Author".try_into().unwrap(), 20)
+        .add_memo_field("Text".try_into().unwrap());
+    let records = vec![
+        Comment {
+            author: "Ferrys".to_string(),
+            text: "First comment".to_string(),
+        },
+        Comment {
+            author: "Alex".to_string(),
+            text: "A comment that will be deleted. ".repeat(40),
+        },
+        Comment {
+            author: "Jamie".to_string(),
+            text: "Last comment".to_string(),
+        },
+    ];
+    pack_memo_file_in_place(writer_builder, records, "pack_dbase_memo.dbf", "dbt");
+}
+
+dbase_record! {
+    #[derive(Clone, Debug, PartialEq)]
+    struct NumberedComment {
+        id: i32,
+        text: String,
+    }
+}
+
+#[test]
+fn test_table_pack_fox_pro_memo() {
+    let writer_builder = TableWriterBuilder::new()
+        .add_integer_field("Id".try_into().unwrap())
+        .add_memo_field("Text".try_into().unwrap());
+    let records = vec![
+        NumberedComment {
+            id: 1,
+            text: "First comment".to_string(),
+        },
+        NumberedComment {
+            id: 2,
+            text: "A comment that will be deleted. ".repeat(40),
+        },
+        NumberedComment {
+            id: 3,
+            text: "Last comment".to_string(),
+        },
+    ];
+    pack_memo_file_in_place(writer_builder, records, "pack_fox_pro_memo.dbf", "fpt");
+}
+
+#[test]
+fn test_table_delete_recall() {
+    let mut cursor = users_with_second_one_deleted();
+    let original_bytes = cursor.get_ref().clone();
+    let mut table = Table::new(&mut cursor).unwrap();
+    assert!(!table.is_deleted(0).unwrap());
+    assert!(table.is_deleted(1).unwrap());
+
+    table.delete(0).unwrap();
+    table.recall(1).unwrap();
+    assert!(table.is_deleted(0).unwrap());
+    assert!(!table.is_deleted(1).unwrap());
+    assert!(table.delete(3).is_err());
+    drop(table);
+
+    // Only the deletion flags (and the date of last update) changed
+    let header = *Reader::new(Cursor::new(&original_bytes)).unwrap().header();
+    let first_record_pos = header.offset_to_first_record as usize;
+    let second_record_pos = first_record_pos + header.size_of_record as usize;
+    for (i, (new, old)) in cursor.get_ref().iter().zip(&original_bytes).enumerate() {
+        if i == first_record_pos {
+            assert_eq!((*old, *new), (b' ', b'*'));
+        } else if i == second_record_pos {
+            assert_eq!((*old, *new), (b'*', b' '));
+        } else if !(1..4).contains(&i) {
+            assert_eq!(old, new);
+        }
+    }
+}
+
+#[test]
+fn test_table_zap() {
+    let dbf_path = std::env::temp_dir().join("table_zap.dbf");
+    let records = vec![Comment {
+        author: "Ferrys".to_string(),
+        text: "A comment".to_string(),
+    }];
+    TableWriterBuilder::new()
+        .add_character_field("Author".try_into().unwrap(), 20)
+        .add_memo_field("Text".try_into().unwrap())
+        .build_with_file_dest(&dbf_path)
+        .unwrap()
+        .write_records(&records)
+        .unwrap();
+
+    let mut table = Table::open(&dbf_path).unwrap();
+    table.zap().unwrap();
+    assert_eq!(table.num_records(), 0);
+    table.close().unwrap();
+    drop(table);
+
+    let mut reader = Reader::from_path(&dbf_path).unwrap();
+    assert_eq!(reader.header().num_records, 0);
+    let offset_to_first_record = reader.header().offset_to_first_record as u64;
+    assert!(reader.read().unwrap().is_empty());
+    assert_eq!(
+        std::fs::metadata(&dbf_path).unwrap().len(),
+        offset_to_first_record + 1
+    );
+    assert_eq!(
+        std::fs::metadata(dbf_path.with_extension("dbt"))
+            .unwrap()
+            .len(),
+        512
+    );
+
+    // The table can still be written to after being zapped
+    let mut writer = TableWriter::append_to_path(&dbf_path).unwrap();
+    writer.write_record(&records[0]).unwrap();
+    writer.close().unwrap();
+    drop(writer);
+    assert_eq!(
+        Reader::from_path(&dbf_path)
+            .unwrap()
+            .read_as::<Comment>()
+            .unwrap(),
+        records
+    );
+
+    let _ = std::fs::remove_file(&dbf_path);
+    let _ = std::fs::remove_file(dbf_path.with_extension("dbt"));
+}
+
+#[test]
+fn test_write_structural_cdx() {
+    use dbase::index::cdx::{reindex, CdxIndex};
+
+    let dbf_path = std::env::temp_dir().join("structural_cdx.dbf");
+    let cdx_path = dbf_path.with_extension("cdx");
+    let mut writer = TableWriterBuilder::new()
+        .add_character_field("Name".try_into().unwrap(), 10)
+        .add_numeric_field("Amount".try_into().unwrap(), 8, 2)
+        .add_date_field("Since".try_into().unwrap())
+        .add_index_tag("NAME", &["Name"])
+        .add_index_tag("AMOUNT", &["Amount"])
+        .add_index_tag("SINCE_NAME", &["Since", "Name"])
+        .build_with_file_dest(&dbf_path)
+        .unwrap();
+    // Enough records to need several levels of nodes
+    let num_records = 1000;
+    for i in 0..num_records {
+        let mut record = Record::default();
+        let position = (i * 7919) % num_records;
+        record.insert(
+            "Name".to_string(),
+            FieldValue::Character(Some(format!("N{:04}", position))),
+        );
+        record.insert(
+            "Amount".to_string(),
+            FieldValue::Numeric(Some(500.0 - position as f64)),
+        );
+        record.insert(
+            "Since".to_string(),
+            FieldValue::Date(Some(Date::new(1 + (i % 2) as u32, 1, 2020))),
+        );
+        writer.write_record(&record).unwrap();
+    }
+    writer.close().unwrap();
+    drop(writer);
+
+    let mut reader = Reader::from_path(&dbf_path).unwrap();
+    assert!(reader.header().table_flags.has_structural_cdx());
+    let records = reader.read().unwrap();
+    let name_of = |index: usize| match records[index].get("Name") {
+        Some(FieldValue::Character(Some(name))) => name.clone(),
+        value => panic!("unexpected name {:?}", value),
+    };
+
+    let mut index = CdxIndex::from_path(&cdx_path)
+        .unwrap()
+        .with_table_fields(reader.fields());
+    let tags = index
+        .tags()
+        .iter()
+        .map(|tag| (tag.name().to_string(), tag.key_expression().to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tags,
+        vec![
+            ("NAME".to_string(), "NAME".to_string()),
+            ("AMOUNT".to_string(), "AMOUNT".to_string()),
+            ("SINCE_NAME".to_string(), "DTOS(SINCE)+NAME".to_string()),
+        ]
+    );
+
+    let by_name = index
+        .iter("NAME")
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(by_name.len(), num_records);
+    let names = by_name.iter().map(|i| name_of(*i)).collect::<Vec<_>>();
+    let mut sorted_names = names.clone();
+    sorted_names.sort();
+    assert_eq!(names, sorted_names);
+
+    // Amounts decrease as names increase
+    let by_amount = index
+        .iter("AMOUNT")
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(by_amount.iter().rev().copied().collect::<Vec<_>>(), by_name);
+    assert_eq!(index.seek("AMOUNT", 500).unwrap(), Some(0));
+    assert_eq!(
+        index.seek("NAME", "N0123").unwrap(),
+        by_name.get(123).copied()
+    );
+    assert_eq!(
+        index
+            .range("SINCE_NAME", "20200101".."20200102")
+            .unwrap()
+            .count(),
+        num_records / 2
+    );
+    drop(index);
+
+    // The index is rebuilt when the modified table is closed
+    let mut table = Table::open(&dbf_path).unwrap();
+    table.update_field(0, "Name", &String::from("A")).unwrap();
+    drop(table);
+    let mut index = CdxIndex::from_path(&cdx_path).unwrap();
+    assert_eq!(index.iter("NAME").unwrap().next().unwrap().unwrap(), 0);
+    assert_eq!(index.seek("NAME", "N0000").unwrap(), None);
+    drop(index);
+
+    // Packing moves the records after the deleted one
+    let mut table = Table::open(&dbf_path).unwrap();
+    table.delete(0).unwrap();
+    table.pack().unwrap();
+    table.close().unwrap();
+    drop(table);
+    let mut index = CdxIndex::from_path(&cdx_path).unwrap();
+    assert_eq!(index.iter("NAME").unwrap().count(), num_records - 1);
+    assert_eq!(index.seek("NAME", "A").unwrap(), None);
+    assert_eq!(index.seek("NAME", name_of(1).as_str()).unwrap(), Some(0));
+    drop(index);
+
+    // Appended records are indexed
+    let mut writer = TableWriter::append_to_path(&dbf_path).unwrap();
+    let mut record = records[0].clone();
+    record.insert(
+        "Name".to_string(),
+        FieldValue::Character(Some("A".to_string())),
+    );
+    writer.write_record(&record).unwrap();
+    writer.close().unwrap();
+    drop(writer);
+    let mut index = CdxIndex::from_path(&cdx_path).unwrap();
+    assert_eq!(index.seek("NAME", "A").unwrap(), Some(num_records - 1));
+    drop(index);
+    reindex(&dbf_path).unwrap();
+
+    // Without the path of the table, its index cannot be rebuilt
+    let mut data = Cursor::new(std::fs::read(&dbf_path).unwrap());
+    let mut table = Table::new(&mut data).unwrap();
+    table.delete(0).unwrap();
+    drop(table);
+    data.set_position(0);
+    assert!(!Reader::new(&mut data)
+        .unwrap()
+        .header()
+        .table_flags
+        .has_structural_cdx());
+    let mut data = Cursor::new(std::fs::read(&dbf_path).unwrap());
+    let mut writer = TableWriter::append_to(&mut data).unwrap();
+    writer.write_record(&record).unwrap();
+    drop(writer);
+    data.set_position(0);
+    assert!(!Reader::new(&mut data)
+        .unwrap()
+        .header()
+        .table_flags
+        .has_structural_cdx());
+
+    // The index is not copied when packing into another file
+    let mut table = Table::open(&dbf_path).unwrap();
+    let mut packed = Cursor::new(Vec::<u8>::new());
+    table.pack_into(&mut packed).unwrap();
+    packed.set_position(0);
+    let reader = Reader::new(packed).unwrap();
+    assert!(!reader.header().table_flags.has_structural_cdx());
+    drop(table);
+
+    // Nor when it is missing
+    std::fs::remove_file(&cdx_path).unwrap();
+    let mut table = Table::open(&dbf_path).unwrap();
+    table.recall(0).unwrap();
+    table.close().unwrap();
+    assert!(!table.header().table_flags.has_structural_cdx());
+    drop(table);
+    let reader = Reader::from_path(&dbf_path).unwrap();
+    assert!(!reader.header().table_flags.has_structural_cdx());
+
+    let _ = std::fs::remove_file(&dbf_path);
+    let _ = std::fs::remove_file(&cdx_path);
+}
+
+#[test]
+fn test_invalid_index_tags() {
+    let error_of = |builder: TableWriterBuilder| {
+        builder
+            .build_with_index_dest(
+                Cursor::new(Vec::<u8>::new()),
+                None,
+                Cursor::new(Vec::<u8>::new()),
+            )
+            .err()
+            .unwrap()
+    };
+    let builder = || {
+        TableWriterBuilder::new()
+            .add_character_field("Name".try_into().unwrap(), 200)
+            .add_character_field("City".try_into().unwrap(), 100)
+            .add_varbinary_field("Photo".try_into().unwrap(), 10)
+    };
+    assert!(matches!(
+        error_of(builder().add_index_tag("NAME", &["Nmae"])).kind(),
+        dbase::ErrorKind::FieldNotFound(name) if name == "Nmae"
+    ));
+    assert!(matches!(
+        error_of(builder().add_index_tag("PHOTO", &["Photo"])).kind(),
+        dbase::ErrorKind::UnsupportedKeyExpression(_)
+    ));
+    // The keys would be longer than 240 bytes
+    assert!(matches!(
+        error_of(builder().add_index_tag("NAME_CITY", &["Name", "City"])).kind(),
+        dbase::ErrorKind::UnsupportedKeyExpression(_)
+    ));
+    assert!(matches!(
+        error_of(builder().add_index_tag("NAME_OF_TAG", &["Name"])).kind(),
+        dbase::ErrorKind::Message(_)
+    ));
+
+    // The table is not created
+    let dbf_path = std::env::temp_dir().join("invalid_index_tags.dbf");
+    let _ = std::fs::remove_file(&dbf_path);
+    let result = builder()
+        .add_index_tag("NAME", &["Name"])
+        .add_index_tag("", &["City"])
+        .build_with_file_dest(&dbf_path);
+    assert!(result.is_err());
+    assert!(!dbf_path.exists());
+}
+
+#[test]
+fn test_iter_records_by_tag() {
+    let dbf_path = std::env::temp_dir().join("iter_records_by_tag.dbf");
+    let cdx_path = dbf_path.with_extension("cdx");
+    let mut writer = TableWriterBuilder::new()
+        .add_character_field("Name".try_into().unwrap(), 10)
+        .add_numeric_field("Amount".try_into().unwrap(), 8, 2)
+        .add_index_tag("AMOUNT", &["Amount"])
+        .build_with_file_dest(&dbf_path)
+        .unwrap();
+    for (name, amount) in [("Lovelace", 3.0), ("Hopper", 1.0), ("Turing", 2.0)] {
+        let mut record = Record::default();
+        record.insert(
+            "Name".to_string(),
+            FieldValue::Character(Some(name.to_string())),
+        );
+        record.insert("Amount".to_string(), FieldValue::Numeric(Some(amount)));
+        writer.write_record(&record).unwrap();
+    }
+    writer.close().unwrap();
+    drop(writer);
+
+    let mut table = Table::open(&dbf_path).unwrap();
+    table.delete(2).unwrap();
+    drop(table);
+
+    let names_by_amount = |reader: &mut Reader<_>| {
+        reader
+            .iter_records_by_tag("AMOUNT")
+            .unwrap()
+            .map(|record| match record.unwrap().get("Name") {
+                Some(FieldValue::Character(Some(name))) => name.clone(),
+                value => panic!("unexpected name {:?}", value),
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut reader = Reader::from_path(&dbf_path).unwrap();
+    assert_eq!(
+        names_by_amount(&mut reader),
+        vec!["Hopper", "Turing", "Lovelace"]
+    );
+    reader.set_deletion_policy(DeletionPolicy::Skip);
+    assert_eq!(names_by_amount(&mut reader), vec!["Hopper", "Lovelace"]);
+    assert!(reader.iter_records_by_tag("NAME").is_err());
+
+    // Skipped records are not decoded, so an invalid amount in one of them is not an error
+    let mut bytes = std::fs::read(&dbf_path).unwrap();
+    let turing = bytes.windows(10).position(|w| w == b"Turing    ").unwrap();
+    bytes[turing + 10..turing + 18].copy_from_slice(b"not a nb");
+    std::fs::write(&dbf_path, bytes).unwrap();
+    let mut reader = Reader::from_path(&dbf_path).unwrap();
+    reader.set_deletion_policy(DeletionPolicy::Skip);
+    assert_eq!(names_by_amount(&mut reader), vec!["Hopper", "Lovelace"]);
+
+    let mut reader = Reader::from_path(LINE_DBF).unwrap();
+    let error = reader.iter_records_by_tag("NAME").err().unwrap();
+    assert!(matches!(error.kind(), dbase::ErrorKind::MissingIndexFile));
+
+    let _ = std::fs::remove_file(&dbf_path);
+    let _ = std::fs::remove_file(&cdx_path);
+}
+
+#[test]
+fn test_filter_records() {
+    let mut reader = Reader::from_path("tests/data/stations.dbf").unwrap();
+    let names = reader
+        .filter("UPPER(line) = 'RED' .OR. RECNO() = 1")
+        .unwrap()
+        .map(|record| match record.unwrap().get("name") {
+            Some(FieldValue::Character(Some(name))) => name.clone(),
+            value => panic!("unexpected name {:?}", value),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["Van Dorn Street", "Judiciary Sq", "Metro Center"]
+    );
+
+    // The line is not read by the record type, it is decoded for the expression only
+    struct StationName(String);
+    impl ReadableRecord for StationName {
+        fn read_using<T>(field_iterator: &mut FieldIterator<T>) -> Result<Self, FieldIOError>
+        where
+            T: Read + Seek,
+        {
+            Ok(Self(field_iterator.read_next_field_as()?.value))
+        }
+    }
+    reader.seek(0).unwrap();
+    let station_names = reader
+        .filter_as::<StationName>("UPPER(line) = 'RED' .OR. RECNO() = 1")
+        .unwrap()
+        .map(|record| record.unwrap().0)
+        .collect::<Vec<_>>();
+    assert_eq!(station_names, names);
+
+    let error = reader.filter("colour = 'red'").err().unwrap();
+    assert!(matches!(error.kind(), dbase::ErrorKind::FieldNotFound(_)));
+    let error = reader.filter("line = ").err().unwrap();
+    assert!(matches!(
+        error.kind(),
+        dbase::ErrorKind::InvalidExpression(_)
+    ));
+}
+
+#[test]
+fn test_iter_records_projected() {
+    let mut reader = Reader::from_path("tests/data/stations.dbf").unwrap();
+    let records = reader.read().unwrap();
+    reader.seek(0).unwrap();
+    let projected = reader
+        .iter_records_projected(&["LINE", "name"])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(projected.len(), records.len());
+    for (record, projected) in records.iter().zip(&projected) {
+        let mut fields = projected.as_ref().keys().cloned().collect::<Vec<_>>();
+        fields.sort();
+        assert_eq!(fields, vec!["line", "name"]);
+        assert_eq!(projected.get("name"), record.get("name"));
+        assert_eq!(projected.get("line"), record.get("line"));
+    }
+    assert!(matches!(
+        reader
+            .iter_records_projected(&["colour"])
+            .err()
+            .unwrap()
+            .kind(),
+        dbase::ErrorKind::FieldNotFound(_)
+    ));
+
+    // The memos of unselected fields are not read, so the memo file is not needed
+    let mut dbf = Cursor::new(Vec::<u8>::new());
+    let mut memo = Cursor::new(Vec::<u8>::new());
+    let mut writer = TableWriterBuilder::new()
+        .add_memo_field("Notes".try_into().unwrap())
+        .add_character_field("Author".try_into().unwrap(), 20)
+        .build_with_memo_dest(&mut dbf, &mut memo)
+        .unwrap();
+    let mut record = Record::default();
+    record.insert("Notes".to_string(), FieldValue::Memo("Long".to_string()));
+    record.insert(
+        "Author".to_string(),
+        FieldValue::Character(Some("Ada".to_string())),
+    );
+    writer.write_record(&record).unwrap();
+    writer.close().unwrap();
+    drop(writer);
+    dbf.set_position(0);
+    let mut reader = Reader::new(dbf).unwrap();
+    assert!(reader.iter_records().next().unwrap().is_err());
+    reader.seek(0).unwrap();
+    let projected = reader
+        .iter_records_projected(&["Author"])
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        projected.get("Author"),
+        Some(&FieldValue::Character(Some("Ada".to_string())))
+    );
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_par_iter_records() {
+    let mut dst = Cursor::new(Vec::<u8>::new());
+    let mut writer = TableWriterBuilder::new()
+        .add_numeric_field("Id".try_into().unwrap(), 10, 0)
+        .add_character_field("Name".try_into().unwrap(), 12)
+        .build_with_dest(&mut dst);
+    for id in 0..5000 {
+        let mut record = Record::default();
+        record.insert("Id".to_string(), FieldValue::Numeric(Some(f64::from(id))));
+        record.insert(
+            "Name".to_string(),
+            FieldValue::Character(Some(format!("Record {}", id))),
+        );
+        writer.write_record(&record).unwrap();
+    }
+    writer.close().unwrap();
+    drop(writer);
+    dst.set_position(0);
+
+    let mut reader = Reader::new(&mut dst).unwrap();
+    let records = reader.read().unwrap();
+    reader.seek(0).unwrap();
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(3)
+        .build()
+        .unwrap();
+    let decoded = reader
+        .par_iter_records()
+        .thread_pool(&pool)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(decoded, records);
+
+    // Starts from the record the reader was moved to
+    reader.seek(4990).unwrap();
+    let decoded = reader
+        .par_iter_records()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(decoded, records[4990..]);
+
+    // Deleted records are skipped depending on the policy
+    drop(reader);
+    dst.set_position(0);
+    let mut table = Table::new(&mut dst).unwrap();
+    table.delete(1).unwrap();
+    table.delete(4000).unwrap();
+    drop(table);
+    dst.set_position(0);
+    let mut reader = Reader::new(&mut dst).unwrap();
+    reader.set_deletion_policy(DeletionPolicy::Skip);
+    let ids = reader
+        .par_iter_records()
+        .thread_pool(&pool)
+        .map(|record| record.unwrap().get("Id").cloned().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids.len(), 4998);
+    assert_eq!(ids[1], FieldValue::Numeric(Some(2.0)));
+
+    // Encodings that are not Send are used on the calling thread
+    dst.set_position(0);
+    let mut reader =
+        Reader::new_with_encoding(&mut dst, NotSendUnicode(std::marker::PhantomData)).unwrap();
+    reader.set_deletion_policy(DeletionPolicy::Skip);
+    let decoded = reader
+        .par_iter_records()
+        .thread_pool(&pool)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(decoded.len(), 4998);
+    reader.seek(0).unwrap();
+    let decoded = reader
+        .par_iter_records()
+        .with_encoding(dbase::Unicode)
+        .thread_pool(&pool)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(decoded.len(), 4998);
+}
+
+/// Unicode, with a field that is not Send
+#[cfg(feature = "rayon")]
+#[derive(Clone)]
+struct NotSendUnicode(std::marker::PhantomData<std::rc::Rc<()>>);
+
+#[cfg(feature = "rayon")]
+impl dbase::encoding::AsCodePageMark for NotSendUnicode {
+    fn code_page_mark(&self) -> dbase::CodePageMark {
+        dbase::CodePageMark::Utf8
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl dbase::Encoding for NotSendUnicode {
+    fn decode<'a>(
+        &self,
+        bytes: &'a [u8],
+    ) -> Result<std::borrow::Cow<'a, str>, dbase::encoding::DecodeError> {
+        dbase::Unicode.decode(bytes)
+    }
+
+    fn encode<'a>(
+        &self,
+        s: &'a str,
+    ) -> Result<std::borrow::Cow<'a, [u8]>, dbase::encoding::EncodeError> {
+        dbase::Unicode.encode(s)
+    }
+}
+
+#[cfg(feature = "encoding_rs")]
+#[test]
+fn test_truncation_keeps_double_byte_characters() {
+    let mut dst = Cursor::new(Vec::<u8>::new());
+    let mut writer = TableWriterBuilder::with_encoding(dbase::encoding::CP932)
+        .add_character_field("Name".try_into().unwrap(), 4)
+        .add_varchar_field("Nick".try_into().unwrap(), 6)
+        .build_with_dest(&mut dst);
+    let mut record = Record::default();
+    record.insert(
+        "Name".to_string(),
+        FieldValue::Character(Some("aあいう".to_string())),
+    );
+    record.insert(
+        "Nick".to_string(),
+        FieldValue::Varchar(Some("bbあいう".to_string())),
+    );
+    writer.write_record(&record).unwrap();
+    writer.close().unwrap();
+    drop(writer);
+    dst.set_position(0);
+
+    let mut reader = Reader::new_with_encoding(dst, dbase::encoding::CP932).unwrap();
+    let records = reader.read().unwrap();
+    assert_eq!(
+        records[0].get("Name"),
+        Some(&FieldValue::Character(Some("aあ".to_string())))
+    );
+    assert_eq!(
+        records[0].get("Nick"),
+        Some(&FieldValue::Varchar(Some("bbあい".to_string())))
+    );
+}
+
+#[test]
+fn test_decode_error_policy() {
+    let mut dst = Cursor::new(Vec::<u8>::new());
+    let mut writer = TableWriterBuilder::with_encoding(dbase::encoding::MacRoman)
+        .add_character_field("Name".try_into().unwrap(), 10)
+        .build_with_dest(&mut dst);
+    for name in ["Tea", "Café"] {
+        let mut record = Record::default();
+        record.insert(
+            "Name".to_string(),
+            FieldValue::Character(Some(name.to_string())),
+        );
+        writer.write_record(&record).unwrap();
+    }
+    writer.close().unwrap();
+    drop(writer);
+    dst.set_position(0);
+
+    // The é of Mac OS Roman is not valid UTF-8
+    let mut reader = Reader::new_with_encoding(dst.clone(), dbase::Unicode).unwrap();
+    assert_eq!(reader.decode_error_policy(), DecodeErrorPolicy::Strict);
+    let error = reader.read().unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        dbase::ErrorKind::StringDecodeError(_)
+    ));
+    assert!(reader.diagnostics().is_empty());
+
+    let mut reader = Reader::new_with_encoding(dst.clone(), dbase::Unicode).unwrap();
+    reader.set_decode_error_policy(DecodeErrorPolicy::Replace);
+    let records = reader.read().unwrap();
+    assert_eq!(
+        records[1].get("Name"),
+        Some(&FieldValue::Character(Some("Caf\u{FFFD}".to_string())))
+    );
+    let diagnostics = reader.take_diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].record_index, 1);
+    assert_eq!(diagnostics[0].field_name, "Name");
+    assert!(reader.diagnostics().is_empty());
+
+    // Each field is decoded, and reported, once when filtering
+    reader.seek(0).unwrap();
+    let records = reader
+        .filter(".NOT. name = 'Tea'")
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(reader.diagnostics().len(), 1);
+    assert_eq!(reader.take_diagnostics()[0].record_index, 1);
+
+    let mut reader = Reader::new_with_encoding(dst, dbase::Unicode).unwrap();
+    reader.set_decode_error_policy(DecodeErrorPolicy::KeepBytes);
+    let records = reader.read().unwrap();
+    assert_eq!(
+        records[0].get("Name"),
+        Some(&FieldValue::Character(Some("Tea".to_string())))
+    );
+    assert_eq!(
+        records[1].get("Name"),
+        Some(&FieldValue::Varbinary(Some(b"Caf\x8E".to_vec())))
+    );
+    assert_eq!(reader.diagnostics().len(), 1);
+}
+
+#[test]
+fn test_decode_error_policy_memo() {
+    let dbf_path = std::env::temp_dir().join("decode_error_policy_memo.dbf");
+    let mut writer = TableWriterBuilder::with_encoding(dbase::encoding::MacRoman)
+        .add_memo_field("Notes".try_into().unwrap())
+        .build_with_file_dest(&dbf_path)
+        .unwrap();
+    let mut record = Record::default();
+    record.insert("Notes".to_string(), FieldValue::Memo("Café".to_string()));
+    writer.write_record(&record).unwrap();
+    writer.close().unwrap();
+    drop(writer);
+
+    let mut reader = Reader::from_path_with_encoding(&dbf_path, dbase::Unicode).unwrap();
+    reader.set_decode_error_policy(DecodeErrorPolicy::KeepBytes);
+    let records = reader.read().unwrap();
+    assert_eq!(
+        records[0].get("Notes"),
+        Some(&FieldValue::BinaryMemo(b"Caf\x8E".to_vec()))
+    );
+    assert_eq!(reader.diagnostics()[0].field_name, "Notes");
+
+    let _ = std::fs::remove_file(&dbf_path);
+    let _ = std::fs::remove_file(dbf_path.with_extension("dbt"));
+}
+
+#[test]
+fn test_cpg_file() {
+    let dbf_path = std::env::temp_dir().join("cpg_file.dbf");
+    let cpg_path = dbf_path.with_extension("cpg");
+    let _ = std::fs::remove_file(&cpg_path);
+    let mut writer = TableWriterBuilder::new()
+        .add_character_field("Name".try_into().unwrap(), 10)
+        .write_cpg_file()
+        .build_with_file_dest(&dbf_path)
+        .unwrap();
+    writer.close().unwrap();
+    drop(writer);
+    assert_eq!(std::fs::read_to_string(&cpg_path).unwrap(), "UTF-8");
+
+    // The .cpg file takes precedence over the code page mark of the table,
+    // which is unknown
+    let mut data = std::fs::read(&dbf_path).unwrap();
+    data[29] = 0xFF;
+    std::fs::write(&dbf_path, data).unwrap();
+    assert!(Reader::from_path(&dbf_path).is_ok());
+    std::fs::remove_file(&cpg_path).unwrap();
+    #[cfg(feature = "yore")]
+    assert!(matches!(
+        Reader::from_path(&dbf_path).err().unwrap().kind(),
+        dbase::ErrorKind::UnsupportedCodePage(dbase::CodePageMark::Other(0xFF))
+    ));
+
+    #[cfg(feature = "encoding_rs")]
+    {
+        let mut writer =
+            TableWriterBuilder::with_encoding(dbase::encoding::Iso8859::new(15).unwrap())
+                .add_character_field("Name".try_into().unwrap(), 10)
+                .build_with_file_dest(&dbf_path)
+                .unwrap();
+        let mut record = Record::default();
+        record.insert(
+            "Name".to_string(),
+            FieldValue::Character(Some("5 €".to_string())),
+        );
+        writer.write_record(&record).unwrap();
+        writer.close().unwrap();
+        drop(writer);
+        std::fs::write(&cpg_path, "ISO 8859-15").unwrap();
+        let records = Reader::from_path(&dbf_path).unwrap().read().unwrap();
+        assert_eq!(
+            records[0].get("Name"),
+            Some(&FieldValue::Character(Some("5 €".to_string())))
+        );
+        std::fs::remove_file(&cpg_path).unwrap();
+    }
+}
+
+#[test]
+fn test_cpg_file_when_modifying() {
+    let dbf_path = std::env::temp_dir().join("cpg_file_when_modifying.dbf");
+    let cpg_path = dbf_path.with_extension("cpg");
+    let mut writer = TableWriterBuilder::new()
+        .add_character_field("Name".try_into().unwrap(), 10)
+        .build_with_file_dest(&dbf_path)
+        .unwrap();
+    let mut record = Record::default();
+    record.insert(
+        "Name".to_string(),
+        FieldValue::Character(Some("Ada".to_string())),
+    );
+    writer.write_record(&record).unwrap();
+    writer.close().unwrap();
+    drop(writer);
+    // Like shapefiles, the code page of the table is undefined and given by the .cpg file
+    let mut data = std::fs::read(&dbf_path).unwrap();
+    data[29] = 0;
+    std::fs::write(&dbf_path, data).unwrap();
+    std::fs::write(&cpg_path, "UTF-8").unwrap();
+
+    let mut writer = TableWriter::append_to_path(&dbf_path).unwrap();
+    record.insert(
+        "Name".to_string(),
+        FieldValue::Character(Some("Müller".to_string())),
+    );
+    writer.write_record(&record).unwrap();
+    writer.close().unwrap();
+    drop(writer);
+    let mut table = Table::open(&dbf_path).unwrap();
+    table.update_field(0, "Name", &String::from("Zoë")).unwrap();
+    table.close().unwrap();
+    drop(table);
+
+    let names = vec![
+        FieldValue::Character(Some("Zoë".to_string())),
+        FieldValue::Character(Some("Müller".to_string())),
+    ];
+    let records = Reader::from_path(&dbf_path).unwrap().read().unwrap();
+    assert_eq!(
+        records
+            .iter()
+            .map(|record| record.get("Name").cloned().unwrap())
+            .collect::<Vec<_>>(),
+        names
+    );
+    #[cfg(feature = "mmap")]
+    {
+        let reader = unsafe { dbase::MappedReader::from_path(&dbf_path) }.unwrap();
+        assert_eq!(
+            reader
+                .iter_records()
+                .map(|record| record.field(0).unwrap().value().unwrap())
+                .collect::<Vec<_>>(),
+            names
+        );
+    }
+
+    let _ = std::fs::remove_file(&dbf_path);
+    let _ = std::fs::remove_file(&cpg_path);
+}
+
+#[cfg(feature = "encoding_rs")]
+#[test]
+fn test_language_driver_is_kept() {
+    let mut data = std::fs::read("tests/data/line.dbf").unwrap();
+    // The Japanese OEM language driver, which uses the code page 932 like CP932 (0x7B)
+    data[29] = 0x13;
+    let mut reader = Reader::new(Cursor::new(data)).unwrap();
+    assert_eq!(
+        reader.header().code_page_mark,
+        dbase::CodePageMark::Other(0x13)
+    );
+    assert_eq!(reader.header().code_page_mark.code_page(), Some(932));
+    let records = reader.read().unwrap();
+
+    let mut dst = Cursor::new(Vec::<u8>::new());
+    let writer = TableWriterBuilder::from_reader(reader).build_with_dest(&mut dst);
+    writer.write_records(&records).unwrap();
+    assert_eq!(dst.get_ref()[29], 0x13);
+}
+
+#[cfg(feature = "encoding_rs")]
+#[test]
+fn test_read_cp936() {
+    let mut reader = Reader::from_path("tests/data/cp936.dbf").unwrap();
+    // The Chinese GBK (PRC) language driver
+    assert_eq!(
+        reader.header().code_page_mark,
+        dbase::CodePageMark::Other(0x4D)
+    );
+    let records = reader.read().unwrap();
+    assert_eq!(
+        records[0].get("TEST"),
+        Some(&FieldValue::Character(Some("测试中文".to_string())))
+    );
+}
+
+#[cfg(feature = "yore")]
+#[test]
+fn test_detect_encoding() {
+    let dbf_path = std::env::temp_dir().join("detect_encoding.dbf");
+    let mut writer = TableWriterBuilder::new()
+        .add_character_field("Name".try_into().unwrap(), 30)
+        .build_with_file_dest(&dbf_path)
+        .unwrap();
+    for name in ["Müller", "Café Noël", "Øresund"] {
+        let mut record = Record::default();
+        record.insert(
+            "Name".to_string(),
+            FieldValue::Character(Some(name.to_string())),
+        );
+        writer.write_record(&record).unwrap();
+    }
+    writer.close().unwrap();
+    drop(writer);
+    // Leave the code page of the table undefined
+    let mut data = std::fs::read(&dbf_path).unwrap();
+    data[29] = 0;
+    std::fs::write(&dbf_path, data).unwrap();
+
+    let (mut reader, detected) = Reader::from_path_detect_encoding(&dbf_path, 10).unwrap();
+    assert_eq!(detected.code_page_mark, dbase::CodePageMark::Utf8);
+    assert!(detected.confidence > 0.5, "{:?}", detected);
+    let records = reader.read().unwrap();
+    assert_eq!(
+        records[0].get("Name"),
+        Some(&FieldValue::Character(Some("Müller".to_string())))
+    );
+
+    // A .cpg file is trusted
+    let cpg_path = dbf_path.with_extension("cpg");
+    std::fs::write(&cpg_path, "1252").unwrap();
+    let (_, detected) = Reader::from_path_detect_encoding(&dbf_path, 10).unwrap();
+    std::fs::remove_file(&cpg_path).unwrap();
+    assert_eq!(detected.code_page_mark, dbase::CodePageMark::CP1252);
+    assert_eq!(detected.confidence, 1.0);
+
+    // The mark of this table is CP1252, but its text is CP850
+    let (mut reader, detected) = Reader::from_path_detect_encoding(CP850_DBF, 10).unwrap();
+    assert_eq!(detected.code_page_mark, dbase::CodePageMark::CP850);
+    let records = reader.read().unwrap();
+    assert_eq!(
+        records[0].get("TEXT"),
+        Some(&FieldValue::Character(Some("Äöü!§$%&/".to_string())))
+    );
+
+    // The records that cannot be read are reported
+    let mut writer = TableWriterBuilder::new()
+        .add_numeric_field("Amount".try_into().unwrap(), 8, 0)
+        .build_with_file_dest(&dbf_path)
+        .unwrap();
+    let mut record = Record::default();
+    record.insert("Amount".to_string(), FieldValue::Numeric(Some(12.0)));
+    writer.write_record(&record).unwrap();
+    writer.close().unwrap();
+    drop(writer);
+    let mut data = std::fs::read(&dbf_path).unwrap();
+    let amount = data.len() - 9;
+    data[amount..amount + 8].copy_from_slice(b"not a nb");
+    std::fs::write(&dbf_path, data).unwrap();
+    assert!(Reader::from_path_detect_encoding(&dbf_path, 10).is_err());
+    let _ = std::fs::remove_file(&dbf_path);
+}